@@ -1,7 +1,9 @@
+use serde::Deserialize;
 use std::fmt::Debug;
 use std::hash::Hash;
+use system::dynamic::BoxedInternal;
 use system::{ActorInternal, NextState, Sender, SendError};
-use system_derive::ActorInternal;
+use system_derive::{ActorInternal, Classify};
 
 pub mod acceptor;
 pub mod learner;
@@ -10,7 +12,7 @@ pub mod proposer;
 
 pub type TimeStamp = u32;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Classify)]
 pub enum Message<T> {
     NewTime(TimeStamp, AgentID),
     Proposal(TimeStamp, T, AgentID),
@@ -20,7 +22,7 @@ pub enum Message<T> {
     Terminated(AgentID, T),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 pub enum AgentID {
     Proposer(usize),
     Acceptor(usize),
@@ -42,7 +44,7 @@ impl AgentID {
 // Errors
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AgentError<T> {
-    SendError((AgentID, Message<T>)),
+    SendError(SendError<(AgentID, Message<T>)>),
     WrongMessageType,
     NoConsensus,
     NoMessage,
@@ -73,10 +75,84 @@ where
     }
 }
 
+/// A Paxos agent boxed as a type-erased actor, an alternative to [`PaxosInternal`].
+pub type BoxedPaxosInternal<T> = BoxedInternal<AgentID, Message<T>>;
+
+/// An actor that can play each of the roles of Paxos.
+pub trait PaxosActor<T>:
+    ActorInternal<Key = AgentID, Message = Message<T>>
+    + From<learner::LearnerInternal<T>>
+    + From<proposer::ProposerInternal<T>>
+    + From<acceptor::AcceptorInternal<T>>
+{
+}
+
+impl<T, I> PaxosActor<T> for I where
+    I: ActorInternal<Key = AgentID, Message = Message<T>>
+        + From<learner::LearnerInternal<T>>
+        + From<proposer::ProposerInternal<T>>
+        + From<acceptor::AcceptorInternal<T>>
+{
+}
+
+impl<T> From<learner::LearnerInternal<T>> for PaxosInternal<T>
+where
+    T: Send + Clone + 'static + Eq + Hash + PartialEq + Debug,
+{
+    fn from(internal: learner::LearnerInternal<T>) -> Self {
+        PaxosInternal::Learner(internal)
+    }
+}
+
+impl<T> From<proposer::ProposerInternal<T>> for PaxosInternal<T>
+where
+    T: Send + Clone + 'static + Eq + Hash + PartialEq + Debug,
+{
+    fn from(internal: proposer::ProposerInternal<T>) -> Self {
+        PaxosInternal::Proposer(internal)
+    }
+}
+
+impl<T> From<acceptor::AcceptorInternal<T>> for PaxosInternal<T>
+where
+    T: Send + Clone + 'static + Eq + Hash + PartialEq + Debug,
+{
+    fn from(internal: acceptor::AcceptorInternal<T>) -> Self {
+        PaxosInternal::Acceptor(internal)
+    }
+}
+
+impl<T> From<learner::LearnerInternal<T>> for BoxedPaxosInternal<T>
+where
+    T: Send + Clone + 'static + Eq + Hash + PartialEq + Debug,
+{
+    fn from(internal: learner::LearnerInternal<T>) -> Self {
+        Box::new(internal)
+    }
+}
+
+impl<T> From<proposer::ProposerInternal<T>> for BoxedPaxosInternal<T>
+where
+    T: Send + Clone + 'static + Eq + Hash + PartialEq + Debug,
+{
+    fn from(internal: proposer::ProposerInternal<T>) -> Self {
+        Box::new(internal)
+    }
+}
+
+impl<T> From<acceptor::AcceptorInternal<T>> for BoxedPaxosInternal<T>
+where
+    T: Send + Clone + 'static + Eq + Hash + PartialEq + Debug,
+{
+    fn from(internal: acceptor::AcceptorInternal<T>) -> Self {
+        Box::new(internal)
+    }
+}
+
 
 impl<T> From<SendError<(AgentID, Message<T>)>> for AgentError<T> {
     fn from(err: SendError<(AgentID, Message<T>)>) -> Self {
-        AgentError::SendError(err.0)
+        AgentError::SendError(err)
     }
 }
 
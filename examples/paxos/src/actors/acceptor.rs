@@ -1,5 +1,6 @@
 use super::*;
 use std::collections::HashSet;
+use system_derive::message_handlers;
 
 #[derive(Debug, Clone)]
 pub struct AcceptorInternal<T> {
@@ -64,6 +65,7 @@ where
     }
 }
 
+#[message_handlers(fallback = wrong_message)]
 impl<T: Clone + Eq + Send + Debug + 'static> ActorInternal for AcceptorInternal<T> {
     type Message = Message<T>;
     type Key = AgentID;
@@ -85,36 +87,42 @@ impl<T: Clone + Eq + Send + Debug + 'static> ActorInternal for AcceptorInternal<
         Ok(NextState::Get)
     }
 
-    fn process_message<S: Sender<Key = AgentID, Message = Message<T>>>(
+    #[handle(Message::NewTime)]
+    fn on_new_time<S: Sender<Key = AgentID, Message = Message<T>>>(
         &mut self,
-        message: Option<Message<T>>,
+        ts: TimeStamp,
+        id: AgentID,
         tx: &mut S,
-    ) -> Result<NextState<Self::Message>, Self::Error> {
-        if let Some(msg) = message {
-            match msg {
-                Message::NewTime(ts, id) => {
-                    self.proposers.insert(id);
-                    if let Some(m) = self.parse_new_time(ts) {
-                        tx.send(&id, m)?;
-                    }
-                }
-
-                Message::Proposal(ts, value, id) => {
-                    assert!(self.proposers.contains(&id));
-                    if self.parse_proposal(ts, value) {
-                        let vote = self.make_vote().unwrap();
-
-                        for &id in self.learners.iter() {
-                            tx.send(&id, vote.clone()).ok();
-                        }
-                    }
-                }
-                _ => return Err(AgentError::WrongMessageType),
-            };
-            Ok(NextState::Get)
-        } else {
-            Ok(NextState::Get)
+    ) -> Result<NextState<Message<T>>, AgentError<T>> {
+        self.proposers.insert(id);
+        if let Some(m) = self.parse_new_time(ts) {
+            tx.send(&id, m)?;
         }
+        Ok(NextState::Get)
+    }
+
+    #[handle(Message::Proposal)]
+    fn on_proposal<S: Sender<Key = AgentID, Message = Message<T>>>(
+        &mut self,
+        ts: TimeStamp,
+        value: T,
+        id: AgentID,
+        tx: &mut S,
+    ) -> Result<NextState<Message<T>>, AgentError<T>> {
+        assert!(self.proposers.contains(&id));
+        if self.parse_proposal(ts, value) {
+            let vote = self.make_vote().unwrap();
+            tx.broadcast(&self.learners, &vote).ok();
+        }
+        Ok(NextState::Get)
+    }
+
+    fn wrong_message<S: Sender<Key = AgentID, Message = Message<T>>>(
+        &mut self,
+        _: Message<T>,
+        _: &mut S,
+    ) -> Result<NextState<Message<T>>, AgentError<T>> {
+        Err(AgentError::WrongMessageType)
     }
 }
 
@@ -136,7 +144,7 @@ mod tests {
             .unwrap();
 
         assert_eq!(acceptor.time, 1);
-        assert_eq!(next_state, NextState::Get.into());
+        assert_eq!(next_state, NextState::Get);
 
         let message_sent = match instructions.pop_front().unwrap() {
             Instruction::Send(_, m) => m,
@@ -151,6 +159,7 @@ mod tests {
         let next_state = acceptor
             .process_message(Some(Message::NewTime(0, prop_id)), &mut instructions)
             .unwrap();
+        assert_eq!(next_state, NextState::Get);
         assert_eq!(acceptor.time, 1);
     }
 
@@ -141,10 +141,7 @@ impl<T: Clone + Send + Hash + Eq + Debug + 'static> ActorInternal for ProposerIn
         if let Some(msg) = message {
             if self.parse_message(msg)? {
                 let proposal = self.make_proposal();
-
-                for id in self.acceptors.iter() {
-                    tx.send(id, proposal.clone()).ok();
-                }
+                tx.broadcast(&self.acceptors, &proposal).ok();
             }
 
             let elapsed = self.buffer.instance.elapsed();
@@ -171,10 +168,7 @@ fn new_time<
 ) -> Result<NextState<Message<T>>, <LearnerInternal<T> as ActorInternal>::Error> {
     internal.set_new_time(time).unwrap();
     let message = Message::NewTime(internal.time, internal.id);
-
-    for id in internal.acceptors.iter() {
-        tx.send(id, message.clone()).unwrap();
-    }
+    tx.broadcast(&internal.acceptors, &message).unwrap();
 
     Ok(NextState::GetTimeout(internal.timeout))
 }
@@ -23,7 +23,7 @@ async fn main() {
 
     println!("Building the system...");
     let paxos = setup_paxos(
-        CrossbeamSystem::new(),
+        CrossbeamSystem::<PaxosInternal<String>>::new(),
         initial_values,
         num_of_acceptors,
         num_of_learners,
@@ -0,0 +1,50 @@
+use paxos::actors::*;
+use paxos::setup_paxos;
+use std::time::Duration;
+use system::synchronous::PoolSystem;
+use system::tokio::sync::ActorType;
+
+fn main() {
+    let num_of_learners: usize = 200;
+    let num_of_acceptors: usize = 1000;
+    let num_of_proposers: usize = 20;
+    let modulus = 17;
+    let timeout = Duration::from_millis(500);
+    let rng_range = 500;
+
+    let initial_values: Vec<(String, u32, Duration)> = (0..num_of_proposers)
+        .map(|i| -> String {
+            let k = i % modulus;
+            format!("The answer is {}", k)
+        })
+        .map(|val| (val, rng_range, timeout))
+        .collect();
+
+    println!("Building the system...");
+    let paxos = setup_paxos(
+        PoolSystem::<PaxosInternal<String>>::new(),
+        initial_values,
+        num_of_acceptors,
+        num_of_learners,
+        ActorType::Light,
+    );
+    println!("Runnning...");
+
+    let mut verdicts: Vec<String> = paxos
+        .run()
+        .unwrap()
+        .into_values()
+        .map(|m| match m {
+            Some(Message::Terminated(_, val)) => val,
+            _ => String::from("No Yo"),
+        })
+        .collect();
+
+    assert!(verdicts.windows(2).all(|a| a[0] == a[1]));
+
+    let verdict = verdicts.pop();
+
+    if let Some(s) = verdict {
+        println!("A consensus has been reached! {}", s);
+    }
+}
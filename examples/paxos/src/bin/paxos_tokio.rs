@@ -1,42 +1,96 @@
 use paxos::actors::*;
-use paxos::setup_paxos;
+use paxos::{majority_decided, paxos_agent};
+use std::env;
 use std::time::Duration;
-use system::tokio::sync::{ActorType, TokioSystem};
+use system::config::SystemConfig;
+use system::internal::ActorInternal;
+use system::metrics::Meter;
+use system::tokio::sync::{RuntimeSettings, TokioSystem};
+use system::trace::{ClockLog, SHIVIZ_REGEX};
+use system::watchdog::Watchdog;
+use system::System;
 
-#[tokio::main]
-async fn main() {
-    let num_of_learners: usize = 10;
-    let num_of_acceptors: usize = 20;
-    let num_of_proposers: usize = 30;
-    let modulus = 17;
-    let timeout = Duration::from_secs(10);
-    let rng_range = 50;
-    let kind = ActorType::Blocking;
+/// The configuration used when no file is given, see `paxos.toml`.
+const DEFAULT_CONFIG: &str = include_str!("../../paxos.toml");
 
-    let initial_values: Vec<(String, u32, Duration)> = (0..num_of_proposers)
-        .map(|i| -> String {
-            let k = i % modulus;
-            format!("The answer is {}", k)
-        })
-        .map(|val| (val, rng_range, timeout))
-        .collect();
+fn main() {
+    // With `--dot`, the wiring of the system is printed instead of running it, with `--shiviz`
+    // the run is logged with vector clocks and the log is printed, with `--metrics` the
+    // messages of the run are counted and timed, and with `--majority` the run ends once a
+    // majority of the learners decided
+    let (flags, paths): (Vec<String>, Vec<String>) =
+        env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let print_dot = flags.iter().any(|flag| flag == "--dot");
+    let print_shiviz = flags.iter().any(|flag| flag == "--shiviz");
+    let print_metrics = flags.iter().any(|flag| flag == "--metrics");
+    let until_majority = flags.iter().any(|flag| flag == "--majority");
+
+    let config = match paths.first() {
+        Some(path) => SystemConfig::<AgentID>::from_path(path),
+        None => SystemConfig::from_toml(DEFAULT_CONFIG),
+    }
+    .unwrap();
+
+    if print_shiviz {
+        let log = ClockLog::new();
+        let system = build(&config, |key, agent| log.wrap(*key, agent));
+        log.run(system).unwrap();
+        eprintln!("Parse the log with {}", SHIVIZ_REGEX);
+        print!("{}", log.to_shiviz());
+        return;
+    }
+
+    if print_dot {
+        print!("{}", build(&config, |_, agent| agent).to_dot());
+        return;
+    }
 
     println!("Building the system...");
-    let paxos = setup_paxos(
-        TokioSystem::new(num_of_learners),
-        initial_values,
-        num_of_acceptors,
-        num_of_learners,
-        kind,
-    );
+    let verdict_messages = if print_metrics {
+        let meter = Meter::new();
+        let system = build(&config, |key, agent| meter.wrap_classified(*key, agent));
+        println!("Runnning...");
+        let (values, metrics) = meter.run(system).unwrap();
 
-    println!("Runnning...");
-    let verdict_messages = paxos.run().await.unwrap();
+        let mailbox_time = metrics.mailbox_time();
+        println!(
+            "{} messages sent and {} delivered in {:?}, with {} timeouts",
+            metrics.sent(),
+            metrics.delivered(),
+            metrics.duration,
+            metrics.timeouts()
+        );
+        println!(
+            "Time in mailbox: mean {:?}, 99% below {:?}",
+            mailbox_time.mean().unwrap_or_default(),
+            mailbox_time.quantile(0.99).unwrap_or_default()
+        );
+        let mut kinds: Vec<_> = metrics.kinds.iter().collect();
+        kinds.sort_by_key(|(kind, _)| **kind);
+        for (kind, counts) in kinds {
+            println!(
+                "    {:<12} {:>8} sent {:>8} delivered",
+                kind, counts.sent, counts.delivered
+            );
+        }
+        values
+    } else if until_majority {
+        let num_of_learners = config.role_keys("learner").len();
+        let watchdog =
+            Watchdog::new().until(move |values| majority_decided(values, num_of_learners));
+        let system = build(&config, |key, agent| watchdog.wrap(*key, agent));
+        println!("Runnning...");
+        watchdog.run(system).unwrap()
+    } else {
+        let system = build(&config, |_, agent| agent);
+        println!("Runnning...");
+        system.run_blocking().unwrap()
+    };
 
     let mut verdicts: Vec<String> = verdict_messages
-        .into_iter()
+        .into_values()
         .map(|m| match m {
-            Message::Terminated(_, val) => val,
+            Some(Message::Terminated(_, val)) => val,
             _ => String::from("No Yo"),
         })
         .collect();
@@ -49,3 +103,34 @@ async fn main() {
         println!("A consensus has been reached! {}", s);
     }
 }
+
+/// Set up the system described by the configuration, passing each agent through `wrap`.
+fn build<I, F>(config: &SystemConfig<AgentID>, mut wrap: F) -> TokioSystem<I>
+where
+    I: ActorInternal<Key = AgentID>,
+    F: FnMut(&AgentID, PaxosInternal<String>) -> I,
+{
+    let modulus = 17;
+    let timeout = Duration::from_secs(10);
+    let rng_range = 50;
+
+    let num_of_learners = config.role_keys("learner").len();
+    let num_of_agents = config.actors.iter().map(|group| group.keys.len()).sum();
+
+    let mut system = TokioSystem::<I>::new(num_of_learners);
+    system.set_runtime(RuntimeSettings {
+        max_blocking_threads: Some(num_of_agents),
+        ..Default::default()
+    });
+
+    config
+        .apply(&mut system, |role, key| {
+            let agent = paxos_agent(role, key, |i| {
+                let k = i % modulus;
+                (format!("The answer is {}", k), rng_range, timeout)
+            })?;
+            Some(wrap(key, agent))
+        })
+        .unwrap();
+    system
+}
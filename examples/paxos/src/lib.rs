@@ -6,4 +6,4 @@
 pub mod actors;
 mod system;
 
-pub use crate::system::setup_paxos;
+pub use crate::system::{majority_decided, paxos_agent, setup_paxos};
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::time::Duration;
 use system::tokio::sync::ActorType;
+use system::topology::{self, Direction};
 use system::System;
 
 use crate::actors::acceptor::AcceptorInternal;
@@ -10,6 +12,9 @@ use crate::actors::proposer::ProposerInternal;
 use crate::actors::*;
 
 /// Setting up Paxos
+///
+/// The agents of the system can be a [`PaxosInternal`] or any other [`PaxosActor`], such as a
+/// [`BoxedPaxosInternal`].
 pub fn setup_paxos<S, T>(
     mut system: S,
     proposer_initial_values: Vec<(T, TimeStamp, Duration)>,
@@ -19,47 +24,87 @@ pub fn setup_paxos<S, T>(
 ) -> S
 where
     T: Clone + Eq + Hash + Debug + Send + 'static,
-    S: System<Internal = PaxosInternal<T>>,
+    S: System,
+    S::Internal: PaxosActor<T>,
     S::ActorParameters: From<(ActorType, usize, usize)>,
 {
     let buffer = 10000;
     let internal_buffer = 1000;
-    let kind = kind;
 
     // Initialize acceptors
+    let acceptors: Vec<_> = (0..n_acceptors).map(AgentID::Acceptor).collect();
     for i in 0..n_acceptors {
-        let internal = PaxosInternal::Acceptor(AcceptorInternal::new(i));
+        let internal = AcceptorInternal::new(i);
         system.add_actor(
-            internal.id(),
-            internal,
+            internal.id,
+            internal.into(),
             Some((kind, buffer, internal_buffer).into()),
         );
     }
 
     // Initialize Learners
-    // Add Acceptor->Learner channels
+    let mut learners = Vec::with_capacity(n_learners);
     for i in 0..n_learners {
-        let internal = PaxosInternal::Learner(LearnerInternal::new(i));
-        let id = internal.id();
+        let internal = LearnerInternal::new(i);
+        let id = internal.id;
+        system.add_actor(id, internal.into(), Some((kind, buffer, internal_buffer).into()));
         system.add_terminal(id);
-        system.add_actor(id, internal, Some((kind, buffer, internal_buffer).into()));
-        system.add_terminal(id);
-        for j in 0..n_acceptors {
-            system.add_channel(&AgentID::Acceptor(j), &id);
-        }
+        learners.push(id);
     }
 
     // Initialize proposers
-    // Add proposer->Acceptor and Acceptor->Proposer channels
+    let mut proposers = Vec::with_capacity(proposer_initial_values.len());
     for (i, (val, range, timeout)) in proposer_initial_values.into_iter().enumerate() {
-        let internal = PaxosInternal::Proposer(ProposerInternal::new(i, val, range, timeout));
-        let id = internal.id();
-        system.add_actor(id, internal, Some((kind, buffer, internal_buffer).into()));
-        for j in 0..n_acceptors {
-            //if j > n_acceptors/2 {break};
-            system.add_channel(&AgentID::Acceptor(j), &id);
-            system.add_channel(&id, &AgentID::Acceptor(j));
-        }
+        let internal = ProposerInternal::new(i, val, range, timeout);
+        let id = internal.id;
+        system.add_actor(id, internal.into(), Some((kind, buffer, internal_buffer).into()));
+        proposers.push(id);
     }
+
+    // Add Acceptor->Learner, Proposer->Acceptor and Acceptor->Proposer channels
+    topology::bipartite(&mut system, &acceptors, &learners, Direction::Forward);
+    topology::bipartite(&mut system, &proposers, &acceptors, Direction::Both);
+
     system
 }
+
+/// Create the agent with the given key, for a system set up from a configuration.
+///
+/// The role of the agent must match its key and be one of "acceptor", "learner" or "proposer".
+/// Each proposer gets its initial value, range and timeout from `proposer_values`.
+///
+/// See [`SystemConfig`](system::config::SystemConfig).
+pub fn paxos_agent<T, I, F>(role: &str, key: &AgentID, proposer_values: F) -> Option<I>
+where
+    T: Clone + Eq + Hash + Debug + Send + 'static,
+    I: PaxosActor<T>,
+    F: FnOnce(usize) -> (T, TimeStamp, Duration),
+{
+    match (role, *key) {
+        ("acceptor", AgentID::Acceptor(i)) => Some(AcceptorInternal::new(i).into()),
+        ("learner", AgentID::Learner(i)) => Some(LearnerInternal::new(i).into()),
+        ("proposer", AgentID::Proposer(i)) => {
+            let (val, range, timeout) = proposer_values(i);
+            Some(ProposerInternal::new(i, val, range, timeout).into())
+        }
+        _ => None,
+    }
+}
+
+/// Whether a majority of the `n_learners` learners decided on the same value, given the
+/// termination messages of the learners done so far.
+///
+/// A run can be ended as soon as this holds, see
+/// [`Watchdog::until`](system::watchdog::Watchdog::until).
+pub fn majority_decided<T: Eq + Hash>(
+    values: &HashMap<AgentID, Option<Message<T>>>,
+    n_learners: usize,
+) -> bool {
+    let mut counts = HashMap::new();
+    for value in values.values() {
+        if let Some(Message::Terminated(_, value)) = value {
+            *counts.entry(value).or_insert(0) += 1;
+        }
+    }
+    counts.values().any(|&count| count > n_learners / 2)
+}
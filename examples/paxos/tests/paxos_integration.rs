@@ -1 +1,297 @@
+use paxos::actors::*;
+use paxos::{majority_decided, paxos_agent, setup_paxos};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Duration;
+use system::config::SystemConfig;
+use system::tokio::local::LocalSystem;
+use system::tokio::sync::{ActorType, RuntimeSettings, TokioSystem};
+use system::metrics::Meter;
+use system::trace::ClockLog;
+use system::watchdog::{Deadline, Watchdog, WatchdogError, Watched};
+use system::synchronous::PoolSystem;
+use system::{CrossbeamSystem, Shared, System};
 
+const N_PROPOSERS: usize = 3;
+const N_ACCEPTORS: usize = 5;
+const N_LEARNERS: usize = 3;
+
+fn initial_values<T: From<String>>() -> Vec<(T, TimeStamp, Duration)> {
+    (0..N_PROPOSERS)
+        .map(|i| format!("The answer is {}", i).into())
+        .map(|val| (val, 50, Duration::from_millis(200)))
+        .collect()
+}
+
+/// Run a Paxos instance on the given system and check all learners agree on a value.
+fn check_consensus<S, T>(system: S, kind: ActorType)
+where
+    T: From<String> + Clone + Eq + Hash + Debug + Send + 'static,
+    S: System<Internal = PaxosInternal<T>>,
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    check_agents_consensus(system, kind)
+}
+
+/// Run a Paxos instance whose agents are of any type playing all roles.
+fn check_agents_consensus<S, T>(system: S, kind: ActorType)
+where
+    T: From<String> + Clone + Eq + Hash + Debug + Send + 'static,
+    S: System,
+    S::Internal: PaxosActor<T>,
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let paxos = setup_paxos(system, initial_values(), N_ACCEPTORS, N_LEARNERS, kind);
+
+    let verdicts: Vec<T> = paxos
+        .run()
+        .unwrap()
+        .into_values()
+        .map(|m| match m {
+            Some(Message::Terminated(_, val)) => val,
+            m => panic!("unexpected termination message {:?}", m),
+        })
+        .collect();
+
+    assert_eq!(verdicts.len(), N_LEARNERS);
+    assert!(verdicts.windows(2).all(|a| a[0] == a[1]));
+}
+
+#[test]
+fn test_paxos_crossbeam() {
+    check_consensus::<_, String>(CrossbeamSystem::new(), ActorType::Light);
+}
+
+#[test]
+fn test_paxos_crossbeam_mailboxes() {
+    let system = CrossbeamSystem::<PaxosInternal<String>>::new();
+    let paxos = setup_paxos(system, initial_values(), N_ACCEPTORS, N_LEARNERS, ActorType::Light);
+    let monitor = paxos.mailbox_monitor();
+    paxos.run().unwrap();
+
+    // The buffer of the set-up bounds the mailboxes, none of which fills up
+    let reports = monitor.report();
+    assert_eq!(reports.len(), N_PROPOSERS + N_ACCEPTORS + N_LEARNERS);
+    for report in reports.values() {
+        assert_eq!(report.capacity, Some(10000));
+        assert_eq!(report.rejected, 0);
+    }
+    assert!(reports[&AgentID::Acceptor(0)].high_watermark > 0);
+}
+
+#[test]
+fn test_paxos_pool() {
+    check_consensus::<_, String>(PoolSystem::with_workers(2), ActorType::Light);
+}
+
+#[test]
+fn test_paxos_tokio_light() {
+    check_consensus::<_, String>(TokioSystem::new(N_LEARNERS), ActorType::Light);
+}
+
+#[test]
+fn test_paxos_tokio_blocking() {
+    check_consensus::<_, String>(TokioSystem::new(N_LEARNERS), ActorType::Blocking);
+}
+
+#[test]
+fn test_paxos_tokio_heavy() {
+    let mut system = TokioSystem::new(N_LEARNERS);
+    system.set_heavy_workers(2);
+    check_consensus::<_, String>(system, ActorType::Heavy);
+}
+
+#[test]
+fn test_paxos_tokio_paused_time() {
+    // the proposers' timeouts fire as soon as the system is idle
+    let mut system = TokioSystem::new(N_LEARNERS);
+    system.set_runtime(RuntimeSettings::paused());
+    check_consensus::<_, String>(system, ActorType::Light);
+}
+
+#[test]
+fn test_paxos_local() {
+    check_consensus::<_, String>(LocalSystem::new(), ActorType::Light);
+}
+
+#[test]
+fn test_paxos_boxed_agents() {
+    type Boxed = BoxedPaxosInternal<String>;
+    check_agents_consensus(CrossbeamSystem::<Boxed>::new(), ActorType::Light);
+    check_agents_consensus(PoolSystem::<Boxed>::with_workers(2), ActorType::Light);
+    check_agents_consensus(TokioSystem::<Boxed>::new(N_LEARNERS), ActorType::Light);
+}
+
+#[test]
+fn test_paxos_shared_values() {
+    check_consensus::<_, Shared<String>>(CrossbeamSystem::new(), ActorType::Light);
+    check_consensus::<_, Shared<String>>(TokioSystem::new(N_LEARNERS), ActorType::Light);
+}
+
+/// A Paxos instance of the size used by [`check_consensus`], with the learners as terminals.
+const CONFIG: &str = r#"
+    [[actors]]
+    role = "learner"
+    keys = [{ Learner = 0 }, { Learner = 1 }, { Learner = 2 }]
+    terminal = true
+
+    [[actors]]
+    role = "acceptor"
+    keys = [{ Acceptor = 0 }, { Acceptor = 1 }, { Acceptor = 2 }, { Acceptor = 3 }, { Acceptor = 4 }]
+
+    [[actors]]
+    role = "proposer"
+    keys = [{ Proposer = 0 }, { Proposer = 1 }, { Proposer = 2 }]
+
+    [[shapes]]
+    shape = "bipartite"
+    left = { role = "acceptor" }
+    right = { role = "learner" }
+
+    [[shapes]]
+    shape = "bipartite"
+    left = { role = "proposer" }
+    right = { role = "acceptor" }
+    direction = "both"
+    "#;
+
+#[test]
+fn test_paxos_config() {
+    let config = SystemConfig::<AgentID>::from_toml(CONFIG).unwrap();
+
+    let values: Vec<(String, TimeStamp, Duration)> = initial_values();
+    let mut system = CrossbeamSystem::<PaxosInternal<String>>::new();
+    config
+        .apply(&mut system, |role, key| {
+            paxos_agent(role, key, |i| values[i].clone())
+        })
+        .unwrap();
+
+    let verdicts: Vec<String> = system
+        .run()
+        .unwrap()
+        .into_values()
+        .map(|m| match m {
+            Some(Message::Terminated(_, val)) => val,
+            m => panic!("unexpected termination message {:?}", m),
+        })
+        .collect();
+
+    assert_eq!(verdicts.len(), N_LEARNERS);
+    assert!(verdicts.windows(2).all(|a| a[0] == a[1]));
+}
+
+#[test]
+fn test_paxos_vector_clocks() {
+    let config = SystemConfig::<AgentID>::from_toml(CONFIG).unwrap();
+    let values: Vec<(String, TimeStamp, Duration)> = initial_values();
+    let log = ClockLog::new();
+    let mut system = CrossbeamSystem::new();
+    config
+        .apply(&mut system, |role, key| {
+            let agent: PaxosInternal<String> = paxos_agent(role, key, |i| values[i].clone())?;
+            Some(log.wrap(*key, agent))
+        })
+        .unwrap();
+
+    let verdicts = system.run().unwrap();
+    assert_eq!(verdicts.len(), N_LEARNERS);
+    for stamped in verdicts.into_values() {
+        // A learner decides after hearing from a majority of the acceptors
+        let stamped = stamped.unwrap();
+        assert!(matches!(stamped.message, Message::Terminated(..)));
+        let acceptors = (0..N_ACCEPTORS)
+            .filter(|&i| stamped.clock.get(&AgentID::Acceptor(i)) > 0)
+            .count();
+        assert!(acceptors > N_ACCEPTORS / 2);
+    }
+    assert!(log.to_shiviz().contains("receive NewTime"));
+}
+
+#[test]
+fn test_paxos_metrics() {
+    let config = SystemConfig::<AgentID>::from_toml(CONFIG).unwrap();
+    let values: Vec<(String, TimeStamp, Duration)> = initial_values();
+    let meter = Meter::new();
+    let mut system = CrossbeamSystem::new();
+    config
+        .apply(&mut system, |role, key| {
+            let agent: PaxosInternal<String> = paxos_agent(role, key, |i| values[i].clone())?;
+            Some(meter.wrap_classified(*key, agent))
+        })
+        .unwrap();
+
+    let (verdicts, metrics) = meter.run(system).unwrap();
+    assert_eq!(verdicts.len(), N_LEARNERS);
+    assert!(verdicts
+        .values()
+        .all(|m| matches!(m, Some(Message::Terminated(..)))));
+
+    // Every proposer sent something, and only channels of the configuration were used
+    assert!((0..N_PROPOSERS).all(|i| metrics.actors[&AgentID::Proposer(i)].sent > 0));
+    assert!(metrics.channels.keys().all(|channel| matches!(
+        channel,
+        (AgentID::Proposer(_), AgentID::Acceptor(_))
+            | (AgentID::Acceptor(_), AgentID::Proposer(_))
+            | (AgentID::Acceptor(_), AgentID::Learner(_))
+    )));
+    assert!(metrics.delivered() <= metrics.sent());
+    assert_eq!(metrics.mailbox_time().count(), metrics.delivered());
+
+    // Each proposer starts by asking the acceptors for a new time
+    let new_time = metrics.kinds["NewTime"];
+    assert!(new_time.sent >= (N_PROPOSERS * N_ACCEPTORS) as u64);
+    let by_kind: u64 = metrics.kinds.values().map(|kind| kind.sent).sum();
+    assert_eq!(by_kind, metrics.sent());
+}
+
+/// Set up the Paxos instance of the configuration, watched by the given watchdog.
+fn watched_paxos<S>(mut system: S, watchdog: &Watchdog<PaxosInternal<String>>) -> S
+where
+    S: System<Internal = Watched<PaxosInternal<String>>>,
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let config = SystemConfig::<AgentID>::from_toml(CONFIG).unwrap();
+    let values: Vec<(String, TimeStamp, Duration)> = initial_values();
+    config
+        .apply(&mut system, |role, key| {
+            let agent: PaxosInternal<String> = paxos_agent(role, key, |i| values[i].clone())?;
+            Some(watchdog.wrap(*key, agent))
+        })
+        .unwrap();
+    system
+}
+
+#[test]
+fn test_paxos_deadline() {
+    // A day of virtual time is plenty to reach consensus
+    let watchdog = Watchdog::new().deadline(Deadline::Virtual(Duration::from_secs(86400)));
+    let mut system = TokioSystem::new(N_LEARNERS);
+    system.set_runtime(RuntimeSettings::paused());
+    let verdicts = watchdog.run(watched_paxos(system, &watchdog)).unwrap();
+    assert_eq!(verdicts.len(), N_LEARNERS);
+
+    // Stopped right away, the proposers, which never terminate, are still running
+    let watchdog = Watchdog::new().deadline(Deadline::Wall(Duration::ZERO));
+    let system = watched_paxos(CrossbeamSystem::new(), &watchdog);
+    match watchdog.run(system) {
+        Err(WatchdogError::Expired(expired)) => {
+            assert!((0..N_PROPOSERS).all(|i| expired.running.contains(&AgentID::Proposer(i))));
+            assert!(expired
+                .values
+                .values()
+                .all(|m| matches!(m, Some(Message::Terminated(..)))));
+        }
+        _ => panic!("the run can not finish before it starts"),
+    }
+}
+
+#[test]
+fn test_paxos_majority() {
+    let watchdog = Watchdog::new().until(|values| majority_decided(values, N_LEARNERS));
+    let verdicts = watchdog
+        .run(watched_paxos(CrossbeamSystem::new(), &watchdog))
+        .unwrap();
+    assert!(verdicts.len() > N_LEARNERS / 2);
+    assert!(majority_decided(&verdicts, N_LEARNERS));
+}
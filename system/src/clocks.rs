@@ -0,0 +1,195 @@
+//! Logical clocks for ordering the events of actors.
+//!
+//! Actors running concurrently do not share a notion of time. A logical clock counts the events
+//! of an actor and travels with its messages, so that the receiver can order its own events after
+//! the sending of the message. Each clock has the same operations:
+//! * `tick` counts a local event, such as sending a message, and should be called before the
+//!   clock is attached to the message.
+//! * `merge` joins the clock with one received from another actor.
+//! * `receive` counts the receipt of a message carrying a clock, merging it and ticking.
+//!
+//! A [`LamportClock`] is a single counter, ordering events consistently with causality. A
+//! [`VectorClock`] keeps a counter for each actor, so that comparing two clocks tells whether
+//! one event happened before the other or they are concurrent. A [`HybridClock`] is a Lamport
+//! clock which stays close to the physical time, so its values can be read as timestamps.
+//! ```
+//! use system::clocks::VectorClock;
+//!
+//! let (mut alice, mut bob) = (VectorClock::new(), VectorClock::new());
+//! alice.tick(&"alice");
+//! let sent = alice.clone();
+//! bob.tick(&"bob");
+//! let before_receipt = bob.clone();
+//! bob.receive(&"bob", &sent);
+//!
+//! assert!(sent.happened_before(&bob));
+//! assert!(sent.is_concurrent(&before_receipt));
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A Lamport clock, a counter larger than the counters of all the events known to happen before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct LamportClock(u64);
+
+impl LamportClock {
+    pub fn new() -> Self {
+        LamportClock(0)
+    }
+
+    pub fn time(&self) -> u64 {
+        self.0
+    }
+
+    /// Count a local event, returning its time.
+    pub fn tick(&mut self) -> u64 {
+        self.0 += 1;
+        self.0
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.0 = self.0.max(other.0);
+    }
+
+    /// Count the receipt of a message stamped with `other`, returning its time.
+    pub fn receive(&mut self, other: &Self) -> u64 {
+        self.merge(other);
+        self.tick()
+    }
+}
+
+/// A vector clock, counting for each actor the events known to have happened to it.
+///
+/// Vector clocks are partially ordered: a clock is smaller than another if it happened before
+/// it, and clocks of concurrent events are not comparable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorClock<K: Hash + Eq> {
+    counts: HashMap<K, u64>,
+}
+
+impl<K: Hash + Eq + Clone> VectorClock<K> {
+    pub fn new() -> Self {
+        VectorClock {
+            counts: HashMap::new(),
+        }
+    }
+
+    /// The number of events of the actor known to this clock.
+    pub fn get(&self, key: &K) -> u64 {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    /// Count a new event of the actor.
+    pub fn tick(&mut self, key: &K) {
+        *self.counts.entry(key.clone()).or_insert(0) += 1;
+    }
+
+    /// Take the largest count of each actor from the two clocks.
+    pub fn merge(&mut self, other: &Self) {
+        for (key, &count) in &other.counts {
+            let entry = self.counts.entry(key.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+    }
+
+    /// Count the receipt by the actor of a message stamped with `other`.
+    pub fn receive(&mut self, key: &K, other: &Self) {
+        self.merge(other);
+        self.tick(key);
+    }
+
+    /// The actors with a positive count, with their counts.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, u64)> {
+        self.counts.iter().map(|(key, &count)| (key, count))
+    }
+
+    /// Whether the event of this clock happened before the event of `other`.
+    pub fn happened_before(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Less)
+    }
+
+    /// Whether neither of the events of the two clocks happened before the other.
+    pub fn is_concurrent(&self, other: &Self) -> bool {
+        self.partial_cmp(other).is_none()
+    }
+}
+
+impl<K: Hash + Eq + Clone> PartialOrd for VectorClock<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let le = self.iter().all(|(key, count)| count <= other.get(key));
+        let ge = other.iter().all(|(key, count)| count <= self.get(key));
+        match (le, ge) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (false, false) => None,
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for VectorClock<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A hybrid logical clock, pairing the largest physical time known with a logical counter.
+///
+/// The physical times are given in any unit, such as the milliseconds of [`physical_time`]. As
+/// long as the physical clocks of the actors are roughly synchronised, the physical part of the
+/// clock stays close to the physical time, while the counter orders events happening within the
+/// same unit of time. Clocks are ordered by their physical part and then by their counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct HybridClock {
+    physical: u64,
+    logical: u64,
+}
+
+impl HybridClock {
+    pub fn new() -> Self {
+        HybridClock {
+            physical: 0,
+            logical: 0,
+        }
+    }
+
+    /// The largest physical time known to the clock.
+    pub fn physical(&self) -> u64 {
+        self.physical
+    }
+
+    /// The number of events known to have happened at the physical time of the clock.
+    pub fn logical(&self) -> u64 {
+        self.logical
+    }
+
+    /// Count a local event happening at the given physical time.
+    pub fn tick(&mut self, now: u64) {
+        if now > self.physical {
+            self.physical = now;
+            self.logical = 0;
+        } else {
+            self.logical += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        *self = (*self).max(*other);
+    }
+
+    /// Count the receipt at the given physical time of a message stamped with `other`.
+    pub fn receive(&mut self, other: &Self, now: u64) {
+        self.merge(other);
+        self.tick(now);
+    }
+}
+
+/// The physical time in milliseconds since the Unix epoch, for ticking a [`HybridClock`].
+pub fn physical_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64)
+}
@@ -0,0 +1,444 @@
+//! Describing the actors and channels of a system in a configuration file.
+//!
+//! A [`SystemConfig`] lists the actors of a system by role, together with their parameters,
+//! the channels between them and the set of terminals. It can be read from TOML or JSON and
+//! applied to any [`System`], given a factory mapping the role and key of each actor to its
+//! internal. Channels are given either one by one or as the shapes of [`topology`](crate::topology),
+//! whose key lists may name all the actors of a role:
+//! ```toml
+//! # The parameters of actors which do not set their own
+//! [defaults]
+//! kind = "light"
+//! buffer = 100
+//! internal_buffer = 100
+//!
+//! [[actors]]
+//! role = "starter"
+//! keys = [0]
+//! terminal = true
+//!
+//! [[actors]]
+//! role = "relay"
+//! keys = [1, 2, 3]
+//! kind = "blocking"
+//!
+//! [[channels]]
+//! from = 3
+//! to = 0
+//!
+//! [[shapes]]
+//! shape = "ring"
+//! keys = [0, 1, 2, 3]
+//! direction = "forward"
+//!
+//! [[shapes]]
+//! shape = "bipartite"
+//! left = { role = "starter" }
+//! right = { role = "relay" }
+//! direction = "both"
+//! ```
+//!
+//! The shapes are `ring`, `complete`, `star` (with a `center` and `leaves`), `bipartite` (with
+//! `left` and `right`), `random` (with a probability `p` and a `seed`) and `grid` (with a
+//! `width`). The direction of a channel or shape is `forward` unless given.
+//!
+//! Applying a configuration:
+//! ```
+//! # use system::internal::*;
+//! use system::config::SystemConfig;
+//! use system::{CrossbeamSystem, System};
+//! # #[derive(Debug)]
+//! # pub struct Relay {
+//! #     output_key: Option<usize>,
+//! #     starter: bool,
+//! # }
+//! # impl ActorInternal for Relay {
+//! #     type Message = usize;
+//! #     type Error = SendError<(usize, usize)>;
+//! #     type Key = usize;
+//! #
+//! #     fn new_incoming_key(&mut self, _: &usize) {}
+//! #     fn new_outgoing_key(&mut self, key: &usize) {
+//! #         self.output_key = Some(*key);
+//! #     }
+//! #
+//! #     fn start<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         if self.starter {
+//! #             tx.send(&self.output_key.unwrap(), 0)?;
+//! #         }
+//! #         Ok(NextState::Get)
+//! #     }
+//! #
+//! #     fn process_message<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         message: Option<usize>,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         let value = message.unwrap() + 1;
+//! #         tx.send(&self.output_key.unwrap(), value).ok();
+//! #         Ok(NextState::Terminate(Some(value)))
+//! #     }
+//! # }
+//!
+//! let config = SystemConfig::<usize>::from_toml(r#"
+//!     [[actors]]
+//!     role = "starter"
+//!     keys = [0]
+//!     terminal = true
+//!
+//!     [[actors]]
+//!     role = "relay"
+//!     keys = [1, 2]
+//!
+//!     [[shapes]]
+//!     shape = "ring"
+//!     keys = [0, 1, 2]
+//! "#).unwrap();
+//!
+//! let mut system = CrossbeamSystem::new();
+//! config
+//!     .apply(&mut system, |role, _| match role {
+//!         "starter" => Some(Relay { output_key: None, starter: true }),
+//!         "relay" => Some(Relay { output_key: None, starter: false }),
+//!         _ => None,
+//!     })
+//!     .unwrap();
+//!
+//! let values = system.run().unwrap();
+//! assert_eq!(values[&0], Some(3));
+//! ```
+
+use crate::internal::ActorInternal;
+use crate::tokio::sync::ActorType;
+use crate::topology::{self, Direction};
+use crate::System;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::path::Path;
+use std::{fs, io};
+
+/// The description of a system: its actors, channels and terminals.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, bound(deserialize = "K: Deserialize<'de>"))]
+pub struct SystemConfig<K> {
+    /// The parameters of actors which do not set their own.
+    #[serde(default)]
+    pub defaults: ActorDefaults,
+    /// The actors of the system, grouped by role.
+    pub actors: Vec<ActorConfig<K>>,
+    /// Channels added one by one.
+    #[serde(default)]
+    pub channels: Vec<ChannelConfig<K>>,
+    /// Channels added as shapes of [`topology`].
+    #[serde(default)]
+    pub shapes: Vec<ShapeConfig<K>>,
+}
+
+/// The parameters given to actors, as the `(kind, buffer, internal_buffer)` of the tokio system.
+///
+/// Systems without actor types or internal buffers ignore them, see
+/// [`SyncParameters`](crate::synchronous::SyncParameters).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ActorDefaults {
+    #[serde(default = "ActorDefaults::default_kind")]
+    pub kind: ActorType,
+    #[serde(default = "ActorDefaults::default_buffer")]
+    pub buffer: usize,
+    #[serde(default = "ActorDefaults::default_buffer")]
+    pub internal_buffer: usize,
+}
+
+/// A group of actors sharing a role and parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ActorConfig<K> {
+    /// The role of the actors, passed to the factory creating their internals.
+    pub role: String,
+    pub keys: Vec<K>,
+    /// Whether the actors are terminals.
+    #[serde(default)]
+    pub terminal: bool,
+    pub kind: Option<ActorType>,
+    pub buffer: Option<usize>,
+    pub internal_buffer: Option<usize>,
+}
+
+/// A single channel, or a pair of channels if the direction is [`Direction::Both`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelConfig<K> {
+    pub from: K,
+    pub to: K,
+    #[serde(default = "forward")]
+    pub direction: Direction,
+}
+
+/// A set of actors, given by their keys or by their role.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum KeySet<K> {
+    Role { role: String },
+    Keys(Vec<K>),
+}
+
+/// A shape of channels, see the functions of [`topology`] of the same name.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "shape", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ShapeConfig<K> {
+    Ring {
+        keys: KeySet<K>,
+        #[serde(default = "forward")]
+        direction: Direction,
+    },
+    Complete {
+        keys: KeySet<K>,
+        #[serde(default = "forward")]
+        direction: Direction,
+    },
+    Star {
+        center: K,
+        leaves: KeySet<K>,
+        #[serde(default = "forward")]
+        direction: Direction,
+    },
+    Bipartite {
+        left: KeySet<K>,
+        right: KeySet<K>,
+        #[serde(default = "forward")]
+        direction: Direction,
+    },
+    Random {
+        keys: KeySet<K>,
+        p: f64,
+        seed: u64,
+        #[serde(default = "forward")]
+        direction: Direction,
+    },
+    Grid {
+        keys: KeySet<K>,
+        width: usize,
+        #[serde(default = "forward")]
+        direction: Direction,
+    },
+}
+
+/// The error returned when a configuration cannot be read or applied.
+#[derive(Debug)]
+pub enum ConfigError<K> {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The file is neither `.toml` nor `.json`.
+    UnknownFormat,
+    /// The factory did not create an actor for the role, or a shape refers to a role which no
+    /// group of actors has.
+    UnknownRole(String),
+    /// A channel or shape refers to a key which is not one of the actors.
+    UnknownKey(K),
+    /// The key is given to more than one actor.
+    DuplicateKey(K),
+    /// A shape has invalid parameters, such as a probability outside of `[0, 1]`.
+    InvalidShape(String),
+}
+
+fn forward() -> Direction {
+    Direction::Forward
+}
+
+impl ActorDefaults {
+    fn default_kind() -> ActorType {
+        ActorType::Light
+    }
+
+    fn default_buffer() -> usize {
+        100
+    }
+}
+
+impl Default for ActorDefaults {
+    fn default() -> Self {
+        ActorDefaults {
+            kind: ActorDefaults::default_kind(),
+            buffer: ActorDefaults::default_buffer(),
+            internal_buffer: ActorDefaults::default_buffer(),
+        }
+    }
+}
+
+impl<K> SystemConfig<K>
+where
+    K: Hash + Eq + Clone + Debug + for<'de> Deserialize<'de>,
+{
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError<K>> {
+        toml::from_str(source).map_err(ConfigError::Toml)
+    }
+
+    pub fn from_json(source: &str) -> Result<Self, ConfigError<K>> {
+        serde_json::from_str(source).map_err(ConfigError::Json)
+    }
+
+    /// Read a configuration from a `.toml` or a `.json` file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError<K>> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&source),
+            Some("json") => Self::from_json(&source),
+            _ => Err(ConfigError::UnknownFormat),
+        }
+    }
+
+    /// The keys of the actors of the given role, in the order of the configuration.
+    pub fn role_keys(&self, role: &str) -> Vec<K> {
+        self.actors
+            .iter()
+            .filter(|group| group.role == role)
+            .flat_map(|group| group.keys.iter().cloned())
+            .collect()
+    }
+
+    fn resolve(&self, set: &KeySet<K>) -> Vec<K> {
+        match set {
+            KeySet::Role { role } => self.role_keys(role),
+            KeySet::Keys(keys) => keys.clone(),
+        }
+    }
+
+    /// Check that every key is given to a single actor and that channels and shapes only refer
+    /// to actors and roles of the configuration.
+    fn validate(&self) -> Result<(), ConfigError<K>> {
+        let mut actors = HashSet::new();
+        for key in self.actors.iter().flat_map(|group| &group.keys) {
+            if !actors.insert(key) {
+                return Err(ConfigError::DuplicateKey(key.clone()));
+            }
+        }
+
+        let known = |key: &K| match actors.contains(key) {
+            true => Ok(()),
+            false => Err(ConfigError::UnknownKey(key.clone())),
+        };
+        for channel in &self.channels {
+            known(&channel.from)?;
+            known(&channel.to)?;
+        }
+        for shape in &self.shapes {
+            let (center, sets) = match shape {
+                ShapeConfig::Ring { keys, .. }
+                | ShapeConfig::Complete { keys, .. }
+                | ShapeConfig::Random { keys, .. }
+                | ShapeConfig::Grid { keys, .. } => (None, vec![keys]),
+                ShapeConfig::Star { center, leaves, .. } => (Some(center), vec![leaves]),
+                ShapeConfig::Bipartite { left, right, .. } => (None, vec![left, right]),
+            };
+            center.map(known).transpose()?;
+            for set in &sets {
+                match set {
+                    KeySet::Role { role } if !self.actors.iter().any(|g| &g.role == role) => {
+                        return Err(ConfigError::UnknownRole(role.clone()))
+                    }
+                    _ => {}
+                }
+            }
+            for key in sets.into_iter().flat_map(|set| self.resolve(set)) {
+                known(&key)?;
+            }
+
+            match shape {
+                ShapeConfig::Random { p, .. } if !(0.0..=1.0).contains(p) => {
+                    return Err(ConfigError::InvalidShape(format!(
+                        "the probability {} is not between 0 and 1",
+                        p
+                    )))
+                }
+                ShapeConfig::Grid { width: 0, .. } => {
+                    return Err(ConfigError::InvalidShape(String::from("a grid of width 0")))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the actors, channels and terminals of the configuration to the system.
+    ///
+    /// The internal of each actor is created by `factory` from its role and key. The
+    /// configuration is checked before anything is added, but the system may be partially set
+    /// up if the factory does not know one of the roles.
+    pub fn apply<S, F>(&self, system: &mut S, mut factory: F) -> Result<(), ConfigError<K>>
+    where
+        S: System,
+        S::Internal: ActorInternal<Key = K>,
+        S::ActorParameters: From<(ActorType, usize, usize)>,
+        F: FnMut(&str, &K) -> Option<S::Internal>,
+    {
+        self.validate()?;
+
+        for group in &self.actors {
+            let kind = group.kind.unwrap_or(self.defaults.kind);
+            let buffer = group.buffer.unwrap_or(self.defaults.buffer);
+            let internal_buffer = group.internal_buffer.unwrap_or(self.defaults.internal_buffer);
+            for key in &group.keys {
+                let internal = factory(&group.role, key)
+                    .ok_or_else(|| ConfigError::UnknownRole(group.role.clone()))?;
+                system.add_actor(
+                    key.clone(),
+                    internal,
+                    Some((kind, buffer, internal_buffer).into()),
+                );
+                if group.terminal {
+                    system.add_terminal(key.clone());
+                }
+            }
+        }
+
+        for channel in &self.channels {
+            topology::connect(system, &channel.from, &channel.to, channel.direction);
+        }
+
+        for shape in &self.shapes {
+            match shape {
+                ShapeConfig::Ring { keys, direction } => {
+                    topology::ring(system, &self.resolve(keys), *direction)
+                }
+                ShapeConfig::Complete { keys, direction } => {
+                    topology::complete(system, &self.resolve(keys), *direction)
+                }
+                ShapeConfig::Star {
+                    center,
+                    leaves,
+                    direction,
+                } => topology::star(system, center, &self.resolve(leaves), *direction),
+                ShapeConfig::Bipartite {
+                    left,
+                    right,
+                    direction,
+                } => topology::bipartite(
+                    system,
+                    &self.resolve(left),
+                    &self.resolve(right),
+                    *direction,
+                ),
+                ShapeConfig::Random {
+                    keys,
+                    p,
+                    seed,
+                    direction,
+                } => topology::random(system, &self.resolve(keys), *p, *seed, *direction),
+                ShapeConfig::Grid {
+                    keys,
+                    width,
+                    direction,
+                } => topology::grid(system, &self.resolve(keys), *width, *direction),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+//! Type-erased actors, allowing a single system to run actors of different types.
+//!
+//! A system is generic over a single internal type. Actors of different types can be combined
+//! into one system by boxing them as a [`BoxedInternal`], as long as they share the same key and
+//! message types. Every [`ActorInternal`] with those types can be boxed:
+//! ```
+//! # use system::internal::*;
+//! use system::dynamic::BoxedInternal;
+//! use system::{CrossbeamSystem, System};
+//!
+//! // Sends a greeting to all of its peers and terminates.
+//! #[derive(Debug)]
+//! struct Greeter {
+//!     peers: Vec<usize>,
+//! }
+//!
+//! // Counts the greetings it gets.
+//! #[derive(Debug)]
+//! struct Counter {
+//!     expected: usize,
+//!     count: usize,
+//! }
+//! # impl ActorInternal for Greeter {
+//! #     type Message = String;
+//! #     type Key = usize;
+//! #     type Error = SendError<(usize, String)>;
+//! #
+//! #     fn new_incoming_key(&mut self, _: &usize) {}
+//! #     fn new_outgoing_key(&mut self, key: &usize) {
+//! #         self.peers.push(*key);
+//! #     }
+//! #
+//! #     fn start<S: Sender<Key = usize, Message = String>>(
+//! #         &mut self,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<String>, Self::Error> {
+//! #         tx.broadcast(&self.peers, &String::from("hello"))?;
+//! #         Ok(NextState::Terminate(None))
+//! #     }
+//! #
+//! #     fn process_message<S: Sender<Key = usize, Message = String>>(
+//! #         &mut self,
+//! #         _: Option<String>,
+//! #         _: &mut S,
+//! #     ) -> Result<NextState<String>, Self::Error> {
+//! #         Ok(NextState::Terminate(None))
+//! #     }
+//! # }
+//! # impl ActorInternal for Counter {
+//! #     type Message = String;
+//! #     type Key = usize;
+//! #     type Error = SendError<(usize, String)>;
+//! #
+//! #     fn new_incoming_key(&mut self, _: &usize) {
+//! #         self.expected += 1;
+//! #     }
+//! #     fn new_outgoing_key(&mut self, _: &usize) {}
+//! #
+//! #     fn start<S: Sender<Key = usize, Message = String>>(
+//! #         &mut self,
+//! #         _: &mut S,
+//! #     ) -> Result<NextState<String>, Self::Error> {
+//! #         Ok(NextState::Get)
+//! #     }
+//! #
+//! #     fn process_message<S: Sender<Key = usize, Message = String>>(
+//! #         &mut self,
+//! #         _: Option<String>,
+//! #         _: &mut S,
+//! #     ) -> Result<NextState<String>, Self::Error> {
+//! #         self.count += 1;
+//! #         match self.count == self.expected {
+//! #             true => Ok(NextState::Terminate(Some(self.count.to_string()))),
+//! #             false => Ok(NextState::Get),
+//! #         }
+//! #     }
+//! # }
+//!
+//! let mut system = CrossbeamSystem::<BoxedInternal<usize, String>>::new();
+//! system.add_actor(0, Box::new(Counter { expected: 0, count: 0 }), None);
+//! for key in 1..4 {
+//!     system.add_actor(key, Box::new(Greeter { peers: vec![] }), None);
+//!     system.add_channel(&key, &0);
+//! }
+//! system.add_terminal(0);
+//!
+//! let values = system.run().unwrap();
+//! assert_eq!(values[&0].as_deref(), Some("3"));
+//! ```
+
+use crate::internal::*;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+/// An object-safe version of [`Sender`].
+///
+/// Every [`Sender`] is a `DynSender`, and a mutable reference to a `DynSender` trait object is
+/// itself a [`Sender`].
+pub trait DynSender<K, M>: Debug + Send {
+    /// Send a message to the channel identified by [`key`].
+    fn send_dyn(&mut self, key: &K, message: M) -> SendResult<K, M>;
+}
+
+/// An object-safe version of [`ActorInternal`] for actors with the given key and message types.
+///
+/// Every [`ActorInternal`] is a `DynInternal`, and a boxed `DynInternal` is itself an
+/// [`ActorInternal`], see [`BoxedInternal`]. Errors of the actor are boxed into a [`DynError`].
+pub trait DynInternal<K, M>: Debug + Send {
+    fn new_incoming_key_dyn(&mut self, key: &K);
+    fn new_outgoing_key_dyn(&mut self, key: &K);
+
+    fn start_dyn(&mut self, tx: &mut dyn DynSender<K, M>) -> Result<NextState<M>, DynError>;
+
+    fn process_message_dyn(
+        &mut self,
+        message: Option<M>,
+        tx: &mut dyn DynSender<K, M>,
+    ) -> Result<NextState<M>, DynError>;
+}
+
+/// A boxed actor which can be run by any system, regardless of its concrete type.
+pub type BoxedInternal<K, M> = Box<dyn DynInternal<K, M>>;
+
+/// The error of a type-erased actor, wrapping the error of the underlying actor.
+pub struct DynError(Box<dyn Debug + Send>);
+
+impl DynError {
+    pub fn new<E: Debug + Send + 'static>(error: E) -> Self {
+        DynError(Box::new(error))
+    }
+}
+
+impl Debug for DynError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<K, M> From<SendError<(K, M)>> for DynError
+where
+    K: Debug + Send + 'static,
+    M: Debug + Send + 'static,
+{
+    fn from(err: SendError<(K, M)>) -> Self {
+        DynError::new(err)
+    }
+}
+
+impl<S: Sender> DynSender<S::Key, S::Message> for S {
+    fn send_dyn(&mut self, key: &S::Key, message: S::Message) -> SendResult<S::Key, S::Message> {
+        self.send(key, message)
+    }
+}
+
+impl<K, M> Sender for &mut dyn DynSender<K, M>
+where
+    K: Hash + Send + Clone + Debug + Eq + PartialEq,
+    M: Send + Clone + Debug + 'static,
+{
+    type Key = K;
+    type Message = M;
+
+    fn send(&mut self, key: &K, message: M) -> SendResult<K, M> {
+        (**self).send_dyn(key, message)
+    }
+}
+
+impl<I: ActorInternal> DynInternal<I::Key, I::Message> for I {
+    fn new_incoming_key_dyn(&mut self, key: &I::Key) {
+        self.new_incoming_key(key)
+    }
+
+    fn new_outgoing_key_dyn(&mut self, key: &I::Key) {
+        self.new_outgoing_key(key)
+    }
+
+    fn start_dyn(
+        &mut self,
+        mut tx: &mut dyn DynSender<I::Key, I::Message>,
+    ) -> Result<NextState<I::Message>, DynError> {
+        self.start(&mut tx).map_err(DynError::new)
+    }
+
+    fn process_message_dyn(
+        &mut self,
+        message: Option<I::Message>,
+        mut tx: &mut dyn DynSender<I::Key, I::Message>,
+    ) -> Result<NextState<I::Message>, DynError> {
+        self.process_message(message, &mut tx).map_err(DynError::new)
+    }
+}
+
+impl<K, M> ActorInternal for BoxedInternal<K, M>
+where
+    K: Hash + Send + Clone + Debug + Eq + PartialEq + 'static,
+    M: Send + Clone + Debug + 'static,
+{
+    type Message = M;
+    type Key = K;
+    type Error = DynError;
+
+    fn new_incoming_key(&mut self, key: &K) {
+        (**self).new_incoming_key_dyn(key)
+    }
+
+    fn new_outgoing_key(&mut self, key: &K) {
+        (**self).new_outgoing_key_dyn(key)
+    }
+
+    fn start<S: Sender<Key = K, Message = M>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<M>, DynError> {
+        (**self).start_dyn(tx)
+    }
+
+    fn process_message<S: Sender<Key = K, Message = M>>(
+        &mut self,
+        message: Option<M>,
+        tx: &mut S,
+    ) -> Result<NextState<M>, DynError> {
+        (**self).process_message_dyn(message, tx)
+    }
+}
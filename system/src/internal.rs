@@ -3,15 +3,52 @@
 //!
 //! See the example in the main documentation. 
 
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::hash::Hash;
+use std::ops::Deref;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Error returned by the Sender.
 /// 
 /// If a message failed to send, the error returns the message back. 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct SendError<T>(pub T);
+pub enum SendError<T> {
+    /// There is no channel associated with the key.
+    UnknownKey(T),
+    /// The recieving actor is no longer listening to the channel.
+    Disconnected(T),
+    /// The channel is at capacity and the message was not sent.
+    Full(T),
+}
+
+impl<T> SendError<T> {
+    /// Returns the message that failed to send.
+    pub fn into_inner(self) -> T {
+        match self {
+            SendError::UnknownKey(t) | SendError::Disconnected(t) | SendError::Full(t) => t,
+        }
+    }
+
+    /// Returns a reference to the message that failed to send.
+    pub fn inner(&self) -> &T {
+        match self {
+            SendError::UnknownKey(t) | SendError::Disconnected(t) | SendError::Full(t) => t,
+        }
+    }
+
+    /// Apply a function to the message, keeping the kind of the error.
+    pub fn map<S, F: FnOnce(T) -> S>(self, f: F) -> SendError<S> {
+        match self {
+            SendError::UnknownKey(t) => SendError::UnknownKey(f(t)),
+            SendError::Disconnected(t) => SendError::Disconnected(f(t)),
+            SendError::Full(t) => SendError::Full(f(t)),
+        }
+    }
+}
+
+/// The result of sending a message of type `M` to the actor identified by a key of type `K`.
+pub type SendResult<K, M> = Result<(), SendError<(K, M)>>;
 
 
 /// The next state the actor can be in after doing a local operation. 
@@ -30,21 +67,185 @@ pub enum NextState<T> {
 /// Actors will implement their operations with respect to a generic sender. Each 
 /// implementation of the system will instatitate its own sender. Users can also implement thier own
 /// senders. 
-pub trait Sender: Debug + Send + Clone + 'static {
+///
+/// Senders are only borrowed by actors, so they need not be `Clone` or `'static`. This allows a
+/// type-erased sender to be passed to actors, see [`dynamic`](crate::dynamic).
+pub trait Sender: Debug + Send {
     /// The type of messages that are sent between actors
     type Message: Send + Clone + Debug + 'static;
 
     /// Identifier for an actor
     ///
     /// In the future, added possibly for a set of actors (currently not supported).
-    type Key: Hash + Send + Copy + Debug + Eq + PartialEq;
+    type Key: Hash + Send + Clone + Debug + Eq + PartialEq;
 
     /// Send a message to the channel identified by [`key`].
     fn send(
         &mut self,
         key: &Self::Key,
         message: Self::Message,
-    ) -> Result<(), SendError<(Self::Key, Self::Message)>>;
+    ) -> SendResult<Self::Key, Self::Message>;
+
+    /// Send a copy of the message to each of the channels identified by [`keys`].
+    ///
+    /// The message is sent to all the keys even if some of the sends fail, in which case the
+    /// first error is returned. Copies are made by cloning the message, so for large messages
+    /// consider wrapping them in a [`Shared`] pointer, or having the system share them, see
+    /// [`sharing`](crate::sharing).
+    fn broadcast<'a, I>(
+        &mut self,
+        keys: I,
+        message: &Self::Message,
+    ) -> SendResult<Self::Key, Self::Message>
+    where
+        I: IntoIterator<Item = &'a Self::Key>,
+        Self::Key: 'a,
+    {
+        let mut result = Ok(());
+        for key in keys {
+            let sent = self.send(key, message.clone());
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+}
+
+/// A message whose content is shared between all of its copies.
+///
+/// Cloning a [`Shared`] value only copies a pointer, so using it as (part of) the message
+/// type makes sending the same message to many actors cheap in every backend. The content is
+/// immutable and can be accessed by dereferencing.
+///
+/// ```
+/// # use system::internal::Shared;
+/// let value = Shared::new(String::from("a large value"));
+/// let copy = value.clone();
+///
+/// assert!(Shared::ptr_eq(&value, &copy));
+/// assert_eq!(copy.len(), 13);
+/// ```
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Shared<T: ?Sized>(Arc<T>);
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Shared(Arc::new(value))
+    }
+
+    /// Returns the inner value, cloning it only if other copies of the message exist.
+    pub fn into_inner(self) -> T
+    where
+        T: Clone,
+    {
+        Arc::try_unwrap(self.0).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+impl<T: ?Sized> Shared<T> {
+    /// Returns true if the two messages point to the same content.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl<T: ?Sized> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for Shared<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Shared<T> {
+    fn from(value: T) -> Self {
+        Shared::new(value)
+    }
+}
+
+impl<T: ?Sized> From<Arc<T>> for Shared<T> {
+    fn from(value: Arc<T>) -> Self {
+        Shared(value)
+    }
+}
+
+impl<T: Debug + ?Sized> Debug for Shared<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: Display + ?Sized> Display for Shared<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A message which can be grouped by its kind.
+///
+/// Tools looking at the messages of a run, such as [`metrics`](crate::metrics) and
+/// [`trace`](crate::trace), can use the kind to break down the messages instead of their debug
+/// representation. The trait can be derived with `system_derive::Classify`, using the name of
+/// each variant of an enum as its kind, as in:
+/// ```
+/// use system::internal::Classify;
+///
+/// #[derive(Debug, Clone)]
+/// pub enum Message {
+///     Ping(usize),
+///     Pong { round: usize },
+///     Stop,
+/// }
+///
+/// impl Classify for Message {
+///     fn kind(&self) -> &'static str {
+///         match self {
+///             Message::Ping(..) => "Ping",
+///             Message::Pong { .. } => "Pong",
+///             Message::Stop => "Stop",
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Message::Pong { round: 3 }.kind(), "Pong");
+/// assert_eq!(Message::Stop.size_hint(), std::mem::size_of::<Message>());
+/// ```
+pub trait Classify {
+    /// A label of the kind of the message, which does not depend on its content.
+    fn kind(&self) -> &'static str;
+
+    /// An estimate of the size of the message in bytes.
+    ///
+    /// By default this is the size of the message itself, not counting the memory it points to.
+    /// Messages holding data on the heap can count it in their own estimate, which the derive
+    /// takes from `#[classify(size = "path::to::function")]`.
+    fn size_hint(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+impl<T: Classify + ?Sized> Classify for Shared<T> {
+    fn kind(&self) -> &'static str {
+        self.0.kind()
+    }
+
+    /// The size of the shared content, although copies of the message share it.
+    fn size_hint(&self) -> usize {
+        self.0.size_hint()
+    }
 }
 
 
@@ -63,7 +264,7 @@ pub trait ActorInternal: Debug + Send + 'static {
     /// Identifier for an actor
     ///
     /// In the future, added possibly for a set of actors (currently not supported).
-    type Key: Hash + Send + Copy + Debug + Eq + PartialEq;
+    type Key: Hash + Send + Clone + Debug + Eq + PartialEq;
 
     /// The error type for an actor's internal system
     type Error: Send + Debug + From<SendError<(Self::Key, Self::Message)>>;
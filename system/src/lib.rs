@@ -7,16 +7,42 @@
 //! Systems are then built by instantiating a corresponding system.
 //!
 //! Currently there are two types of systems avaialble:
-//! * [crossbeam::System](synchronous::crossbeam::System) - implements actors as threads with message
-//! passing between them.
+//! * [crossbeam::System](synchronous::crossbeam::CrossbeamSystem) - implements actors as threads with message
+//!   passing between them.
 //!     * Simple to run and test.
 //!     * Has limitations of scale as each actor runs on a dedicated thread.
 //!
+//! * [pool::PoolSystem](synchronous::pool::PoolSystem) - implements actors as state machines run by a
+//!   fixed pool of worker threads.
+//!     * Runs many actors without an asynchronous runtime.
+//!     * Timeouts are served by a shared timer, with a resolution of one millisecond by default.
+//!
 //! * [tokio::sync::System] - implementing actors using [tokio](https://tokio.rs) tasks and message passing.
 //!     * Easily run many actors in a single simulation.
 //!     * Users can specify different types of actors. For actors with internal operations that
-//!     are potentially computationally heavy, blocking threads are spawn.
+//!       are potentially computationally heavy, blocking threads are spawn.
+//!
+//! * [tokio::local::LocalSystem] - runs all actors as tasks on a single thread.
+//!     * Actors and messages do not need to be `Send`, so they may share `Rc` or `RefCell` state.
+//!     * No synchronization between threads, the cheapest way to run many light actors.
+//!
+//! All systems run actors of a single internal type. Actors of different types sharing key and
+//! message types can be combined in a system by boxing them, see [`dynamic`]. Actors sending
+//! large messages to many actors can have them passed behind a shared pointer, see [`sharing`].
+//!
+//! Channels of common shapes, such as rings, complete or bipartite graphs, can be added to any
+//! system using the functions of [`topology`]. The whole set-up of a system can also be read
+//! from a TOML or JSON file, see [`config`].
 //!
+//! The messages passed during a run can be recorded and rendered as sequence charts, see
+//! [`trace`]. Actors needing to order events themselves can use the logical clocks of [`clocks`].
+//! Counts of messages and the time spent delivering and processing them are collected by
+//! [`metrics`]. Runs which can never finish, with all actors waiting for messages that never
+//! come, are detected by [`watchdog`]. A running system can be stopped from another thread
+//! with its [`shutdown`] signal.
+//!
+//! Tokio runtimes whose clock starts paused, which skip ahead to the next timeout whenever all
+//! actors wait, are enabled by the `paused-time` feature, see [`tokio::sync::RuntimeSettings`].
 //!
 //! # Example
 //! We demonstrate the use of the library by implementing a system consisting of three
@@ -170,23 +196,47 @@
 //!```
 //!
 
+pub mod clocks;
+pub mod config;
+pub mod dynamic;
 pub mod internal;
+pub mod metrics;
+pub mod sharing;
+pub mod shutdown;
 pub mod synchronous;
 pub mod tokio;
+pub mod topology;
+pub mod trace;
+pub mod watchdog;
 
 //pub use crate::tokio::sync::TokioSystem;
 pub use synchronous::crossbeam::CrossbeamSystem;
 
-pub use internal::{ActorInternal, NextState, Sender, SendError};
+pub use internal::{ActorInternal, NextState, SendError, Sender, Shared};
+
+use shutdown::Shutdown;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use topology::Graph;
+
+/// The values collected from the terminal actors of a system after a run.
+///
+/// Each terminal is identified by its key and mapped to the optional termination message
+/// it returned.
+pub type TerminalValues<I> =
+    HashMap<<I as ActorInternal>::Key, Option<<I as ActorInternal>::Message>>;
 
-/// An interface defining methods of a system useful for set-up
-/// 
+/// An interface defining methods of a system useful for set-up and running.
 ///
-/// **Note**: The reason we did not abstract away some other properties of actors and systems in a trait is
-/// that including async methods in trait is an unstable fearture in Rust.
+/// Generic code written against this trait (such as test harnesses) can be run on any backend.
+///
+/// **Note**: [`System::run`] is blocking. Systems based on an asynchronous runtime provide their
+/// own asynchronous run methods in addition to the blocking one.
 pub trait System: Sized {
     type Internal: ActorInternal;
     type ActorParameters;
+    /// The error returned when a run of the system fails.
+    type Error: Debug;
 
     /// Add a new actors to the system, with a given internal core and identifying key
     fn add_actor(
@@ -209,4 +259,26 @@ pub trait System: Sized {
     /// has been terminated. Once all the actors in the set of terminals is done executing,
     ///  an actor not in this set will be dropped regardless of whether it terminated or not.
     fn add_terminal(&mut self, key: <Self::Internal as ActorInternal>::Key);
+
+    /// The actors, channels and terminals added to the system so far.
+    fn graph(&self) -> &Graph<<Self::Internal as ActorInternal>::Key>;
+
+    /// Render the actors, channels and terminals of the system as a
+    /// [Graphviz](https://graphviz.org) DOT document, see [`Graph::to_dot`].
+    fn to_dot(&self) -> String {
+        self.graph().to_dot()
+    }
+
+    /// The signal stopping a run of the system, `None` if the system can not be stopped.
+    ///
+    /// Once the signal is triggered the run returns, with no termination message for the
+    /// terminals which did not terminate, see [`shutdown`].
+    fn shutdown(&self) -> Option<Shutdown> {
+        None
+    }
+
+    /// Run the system, blocking the current thread until all the terminals are done.
+    ///
+    /// Returns the termination messages of all terminal actors.
+    fn run(self) -> Result<TerminalValues<Self::Internal>, Self::Error>;
 }
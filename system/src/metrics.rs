@@ -0,0 +1,597 @@
+//! Counting the messages of a run and timing their delivery and processing.
+//!
+//! A [`Meter`] wraps actors into [`Metered`] actors, which can be run by any system. Every
+//! message sent by a metered actor is timestamped, so when it is delivered the meter knows how
+//! long it waited in the mailbox of its reciever. The meter also times each call to
+//! [`ActorInternal::process_message`] and counts the timeouts of each actor.
+//!
+//! Running the system with [`Meter::run`] returns the collected [`RunMetrics`] alongside the
+//! termination messages of the terminals:
+//! ```
+//! # use system::internal::*;
+//! use system::metrics::Meter;
+//! use system::CrossbeamSystem;
+//! # #[derive(Debug)]
+//! # pub struct Relay {
+//! #     output_key: Option<usize>,
+//! #     starter: bool,
+//! # }
+//! # impl ActorInternal for Relay {
+//! #     type Message = usize;
+//! #     type Error = SendError<(usize, usize)>;
+//! #     type Key = usize;
+//! #
+//! #     fn new_incoming_key(&mut self, _: &usize) {}
+//! #     fn new_outgoing_key(&mut self, key: &usize) {
+//! #         self.output_key = Some(*key);
+//! #     }
+//! #
+//! #     fn start<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         if self.starter {
+//! #             tx.send(&self.output_key.unwrap(), 0)?;
+//! #         }
+//! #         Ok(NextState::Get)
+//! #     }
+//! #
+//! #     fn process_message<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         message: Option<usize>,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         let value = message.unwrap() + 1;
+//! #         if !self.starter {
+//! #             tx.send(&self.output_key.unwrap(), value)?;
+//! #         }
+//! #         Ok(NextState::Terminate(Some(value)))
+//! #     }
+//! # }
+//! use system::System;
+//!
+//! let meter = Meter::new();
+//!
+//! // A cycle 0 -> 1 -> 2 -> 0 passing on a counter.
+//! let mut system = CrossbeamSystem::new();
+//! for key in 0..3 {
+//!     let relay = Relay { output_key: None, starter: key == 0 };
+//!     system.add_actor(key, meter.wrap(key, relay), None);
+//! }
+//! for key in 0..3 {
+//!     system.add_channel(&key, &((key + 1) % 3));
+//! }
+//! system.add_terminal(0);
+//!
+//! let (values, metrics) = meter.run(system).unwrap();
+//! assert_eq!(values[&0], Some(3));
+//! assert_eq!(metrics.sent(), 3);
+//! assert_eq!(metrics.channels[&(2, 0)].delivered, 1);
+//! assert_eq!(metrics.actors[&1].processing_time.count(), 1);
+//! ```
+//!
+//! The time a message spends in a mailbox is measured from the moment it is sent, so it
+//! includes the time the system takes to pass it to the mailbox. Each metered actor keeps its own
+//! metrics, which the meter merges when they are read, so actors never wait on each other to
+//! record them.
+//!
+//! Actors whose messages implement [`Classify`] can be wrapped with [`Meter::wrap_classified`]
+//! instead, to break down their messages by kind.
+
+use crate::internal::*;
+use crate::{System, TerminalValues};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A histogram of durations, with buckets growing by powers of two from one microsecond.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Histogram {
+    count: u64,
+    total: Duration,
+    min: Option<Duration>,
+    max: Option<Duration>,
+    buckets: Vec<u64>,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bucket of a duration, the ones shorter than a microsecond being in the first bucket.
+    fn bucket(duration: Duration) -> usize {
+        let micros = duration.as_micros();
+        (u128::BITS - micros.leading_zeros()) as usize
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.min = Some(self.min.map_or(duration, |min| min.min(duration)));
+        self.max = Some(self.max.map_or(duration, |max| max.max(duration)));
+
+        let bucket = Self::bucket(duration);
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+    }
+
+    /// Add the durations recorded in another histogram.
+    pub fn merge(&mut self, other: &Self) {
+        self.count += other.count;
+        self.total += other.total;
+        self.min = self.min.into_iter().chain(other.min).min();
+        self.max = self.max.into_iter().chain(other.max).max();
+        if self.buckets.len() < other.buckets.len() {
+            self.buckets.resize(other.buckets.len(), 0);
+        }
+        for (bucket, count) in self.buckets.iter_mut().zip(&other.buckets) {
+            *bucket += count;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.max
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total / self.count as u32)
+    }
+
+    /// An upper bound on the `q`-quantile of the durations, the end of the bucket containing it.
+    ///
+    /// # Panics
+    /// Panics if `q` is not between 0 and 1.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        assert!(
+            (0.0..=1.0).contains(&q),
+            "a quantile must be between 0 and 1"
+        );
+        let rank = ((q * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let end = Duration::from_micros(1 << bucket);
+                return Some(end.min(self.max.unwrap()));
+            }
+        }
+        None
+    }
+}
+
+/// The metrics of a single actor.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ActorMetrics {
+    /// The messages the actor sent successfully.
+    pub sent: u64,
+    /// The messages delivered to the actor.
+    pub delivered: u64,
+    /// The times the actor waited for a message but got none.
+    pub timeouts: u64,
+    /// The time the messages delivered to the actor spent in its mailbox.
+    pub mailbox_time: Histogram,
+    /// The execution time of each call to `process_message`.
+    pub processing_time: Histogram,
+}
+
+/// The metrics of the channel between two actors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChannelMetrics {
+    pub sent: u64,
+    pub delivered: u64,
+}
+
+/// The metrics of the messages of a kind, see [`Classify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KindMetrics {
+    pub sent: u64,
+    pub delivered: u64,
+    /// The estimated size of the messages sent, in bytes.
+    pub bytes: u64,
+}
+
+/// The metrics collected during a run.
+///
+/// Messages still in a mailbox when the run ended are counted as sent but not delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunMetrics<K: Hash + Eq> {
+    /// The wall-clock duration of the run.
+    pub duration: Duration,
+    pub actors: HashMap<K, ActorMetrics>,
+    /// The metrics of each channel that carried a message, by sender and reciever.
+    pub channels: HashMap<(K, K), ChannelMetrics>,
+    /// The metrics of each kind of message sent by actors wrapped with
+    /// [`Meter::wrap_classified`].
+    pub kinds: HashMap<&'static str, KindMetrics>,
+}
+
+impl<K: Hash + Eq> RunMetrics<K> {
+    fn new() -> Self {
+        RunMetrics {
+            duration: Duration::ZERO,
+            actors: HashMap::new(),
+            channels: HashMap::new(),
+            kinds: HashMap::new(),
+        }
+    }
+
+    /// Add the metrics of another part of the run.
+    fn merge(&mut self, other: &Self)
+    where
+        K: Clone,
+    {
+        self.duration += other.duration;
+        for (key, metrics) in &other.actors {
+            let actor = self.actors.entry(key.clone()).or_default();
+            actor.sent += metrics.sent;
+            actor.delivered += metrics.delivered;
+            actor.timeouts += metrics.timeouts;
+            actor.mailbox_time.merge(&metrics.mailbox_time);
+            actor.processing_time.merge(&metrics.processing_time);
+        }
+        for (key, metrics) in &other.channels {
+            let channel = self.channels.entry(key.clone()).or_default();
+            channel.sent += metrics.sent;
+            channel.delivered += metrics.delivered;
+        }
+        for (kind, metrics) in &other.kinds {
+            let counts = self.kinds.entry(kind).or_default();
+            counts.sent += metrics.sent;
+            counts.delivered += metrics.delivered;
+            counts.bytes += metrics.bytes;
+        }
+    }
+
+    fn record_sent(&mut self, from: &K, to: &K, class: Option<Class>)
+    where
+        K: Clone,
+    {
+        self.actors.entry(from.clone()).or_default().sent += 1;
+        let channel = (from.clone(), to.clone());
+        self.channels.entry(channel).or_default().sent += 1;
+        if let Some((kind, size)) = class {
+            let kind = self.kinds.entry(kind).or_default();
+            kind.sent += 1;
+            kind.bytes += size as u64;
+        }
+    }
+
+    fn record_delivered(
+        &mut self,
+        from: &K,
+        to: &K,
+        kind: Option<&'static str>,
+        mailbox_time: Duration,
+    ) where
+        K: Clone,
+    {
+        let actor = self.actors.entry(to.clone()).or_default();
+        actor.delivered += 1;
+        actor.mailbox_time.record(mailbox_time);
+        let channel = (from.clone(), to.clone());
+        self.channels.entry(channel).or_default().delivered += 1;
+        if let Some(kind) = kind {
+            self.kinds.entry(kind).or_default().delivered += 1;
+        }
+    }
+
+    /// The number of messages sent by all actors.
+    pub fn sent(&self) -> u64 {
+        self.actors.values().map(|actor| actor.sent).sum()
+    }
+
+    /// The number of messages delivered to all actors.
+    pub fn delivered(&self) -> u64 {
+        self.actors.values().map(|actor| actor.delivered).sum()
+    }
+
+    /// The number of timeouts of all actors.
+    pub fn timeouts(&self) -> u64 {
+        self.actors.values().map(|actor| actor.timeouts).sum()
+    }
+
+    /// The time spent in a mailbox by all the delivered messages.
+    pub fn mailbox_time(&self) -> Histogram {
+        let mut histogram = Histogram::new();
+        for actor in self.actors.values() {
+            histogram.merge(&actor.mailbox_time);
+        }
+        histogram
+    }
+
+    /// The execution times of `process_message` of all actors.
+    pub fn processing_time(&self) -> Histogram {
+        let mut histogram = Histogram::new();
+        for actor in self.actors.values() {
+            histogram.merge(&actor.processing_time);
+        }
+        histogram
+    }
+}
+
+/// A message carrying its sender and the time it was sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timed<K, M> {
+    pub from: K,
+    pub sent: Instant,
+    pub message: M,
+}
+
+/// The metrics recorded by a single metered actor.
+type Shard<K> = Arc<Mutex<RunMetrics<K>>>;
+
+/// Collects the metrics of the actors it wraps, shared between all of them.
+///
+/// Each wrapped actor records its metrics in a shard of its own, merged with the others when the
+/// metrics are read.
+#[derive(Debug)]
+pub struct Meter<K: Hash + Eq> {
+    metrics: Shard<K>,
+    shards: Arc<Mutex<Vec<Shard<K>>>>,
+}
+
+/// An actor whose messages are counted and timed by a [`Meter`].
+///
+/// The actor behaves as the actor it wraps, with the same key and error types, but its messages
+/// are [`Timed`].
+#[derive(Debug)]
+pub struct Metered<I: ActorInternal> {
+    key: I::Key,
+    internal: I,
+    shard: Shard<I::Key>,
+    classify: Option<Classifier<I::Message>>,
+    // Whether the actor last waited with a timeout, so that a missing message counts as one.
+    timed_out: bool,
+}
+
+/// The kind and estimated size of a message.
+type Class = (&'static str, usize);
+
+type Classifier<M> = fn(&M) -> Class;
+
+/// The error of a [`Metered`] actor, which is the error of the actor it wraps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeteredError<E>(pub E);
+
+/// A sender timestamping the messages sent through it, keeping their reciever and kind until
+/// they are counted.
+#[derive(Debug)]
+pub struct MeteredSender<'a, S: Sender, M> {
+    key: S::Key,
+    tx: &'a mut S,
+    sent: Vec<(S::Key, Option<Class>)>,
+    classify: Option<Classifier<M>>,
+}
+
+impl<K: Hash + Eq + Clone> Meter<K> {
+    pub fn new() -> Self {
+        Meter {
+            metrics: Arc::new(Mutex::new(RunMetrics::new())),
+            shards: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// A new shard of the metrics, for a single actor.
+    fn shard(&self) -> Shard<K> {
+        let shard = Arc::new(Mutex::new(RunMetrics::new()));
+        self.shards.lock().unwrap().push(shard.clone());
+        shard
+    }
+
+    /// Wrap the actor with the given key, collecting its metrics in this meter.
+    pub fn wrap<I: ActorInternal<Key = K>>(&self, key: K, internal: I) -> Metered<I> {
+        Metered {
+            key,
+            internal,
+            shard: self.shard(),
+            classify: None,
+            timed_out: false,
+        }
+    }
+
+    /// Wrap the actor with the given key, also counting its messages by kind.
+    pub fn wrap_classified<I>(&self, key: K, internal: I) -> Metered<I>
+    where
+        I: ActorInternal<Key = K>,
+        I::Message: Classify,
+    {
+        Metered {
+            key,
+            internal,
+            shard: self.shard(),
+            classify: Some(|message| (message.kind(), message.size_hint())),
+            timed_out: false,
+        }
+    }
+
+    /// The metrics collected so far.
+    pub fn metrics(&self) -> RunMetrics<K> {
+        let mut metrics = self.metrics.lock().unwrap().clone();
+        for shard in self.shards.lock().unwrap().iter() {
+            metrics.merge(&shard.lock().unwrap());
+        }
+        metrics
+    }
+
+    /// Run the system, returning the termination messages of the terminals together with the
+    /// metrics of the run.
+    ///
+    /// The metrics include the ones collected before the run, if the meter was already used.
+    pub fn run<S, I>(&self, system: S) -> Result<(TerminalValues<I>, RunMetrics<K>), S::Error>
+    where
+        S: System<Internal = Metered<I>>,
+        I: ActorInternal<Key = K>,
+        K: Debug + Send,
+    {
+        let start = Instant::now();
+        let values = system.run()?;
+        self.metrics.lock().unwrap().duration += start.elapsed();
+
+        let values = values
+            .into_iter()
+            .map(|(key, value)| (key, value.map(|timed| timed.message)))
+            .collect();
+        Ok((values, self.metrics()))
+    }
+}
+
+impl<K: Hash + Eq> Clone for Meter<K> {
+    fn clone(&self) -> Self {
+        Meter {
+            metrics: self.metrics.clone(),
+            shards: self.shards.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for Meter<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: ActorInternal> Metered<I> {
+    /// A sender for a single step of the actor.
+    fn sender<'a, S: Sender<Key = I::Key>>(
+        &self,
+        tx: &'a mut S,
+    ) -> MeteredSender<'a, S, I::Message> {
+        MeteredSender {
+            key: self.key.clone(),
+            tx,
+            sent: Vec::new(),
+            classify: self.classify,
+        }
+    }
+
+    /// Count the messages sent during a step of the actor.
+    fn record_sent<S: Sender<Key = I::Key>>(&self, tx: MeteredSender<S, I::Message>) {
+        let mut shard = self.shard.lock().unwrap();
+        for (to, class) in &tx.sent {
+            shard.record_sent(&self.key, to, *class);
+        }
+    }
+
+    /// Timestamp the termination message of the actor, if it terminates, and remember whether
+    /// it waits with a timeout.
+    fn time_next(&mut self, next: NextState<I::Message>) -> NextState<Timed<I::Key, I::Message>> {
+        self.timed_out = matches!(next, NextState::GetTimeout(_));
+        match next {
+            NextState::Get => NextState::Get,
+            NextState::GetTimeout(duration) => NextState::GetTimeout(duration),
+            NextState::Terminate(value) => NextState::Terminate(value.map(|message| Timed {
+                from: self.key.clone(),
+                sent: Instant::now(),
+                message,
+            })),
+        }
+    }
+}
+
+impl<'a, S, M> Sender for MeteredSender<'a, S, M>
+where
+    S: Sender<Message = Timed<<S as Sender>::Key, M>>,
+    M: Send + Clone + Debug + 'static,
+{
+    type Key = S::Key;
+    type Message = M;
+
+    fn send(&mut self, key: &S::Key, message: M) -> SendResult<S::Key, M> {
+        let class = self.classify.map(|classify| classify(&message));
+        let timed = Timed {
+            from: self.key.clone(),
+            sent: Instant::now(),
+            message,
+        };
+        self.tx
+            .send(key, timed)
+            .map_err(|err| err.map(|(key, timed)| (key, timed.message)))?;
+        self.sent.push((key.clone(), class));
+        Ok(())
+    }
+}
+
+impl<K, M, E> From<SendError<(K, Timed<K, M>)>> for MeteredError<E>
+where
+    E: From<SendError<(K, M)>>,
+{
+    fn from(err: SendError<(K, Timed<K, M>)>) -> Self {
+        MeteredError(err.map(|(key, timed)| (key, timed.message)).into())
+    }
+}
+
+impl<I: ActorInternal> ActorInternal for Metered<I> {
+    type Message = Timed<I::Key, I::Message>;
+    type Key = I::Key;
+    type Error = MeteredError<I::Error>;
+
+    fn new_incoming_key(&mut self, key: &I::Key) {
+        self.internal.new_incoming_key(key)
+    }
+
+    fn new_outgoing_key(&mut self, key: &I::Key) {
+        self.internal.new_outgoing_key(key)
+    }
+
+    fn start<S: Sender<Key = I::Key, Message = Self::Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        let mut tx = self.sender(tx);
+        let next = self.internal.start(&mut tx);
+        self.record_sent(tx);
+        Ok(self.time_next(next.map_err(MeteredError)?))
+    }
+
+    fn process_message<S: Sender<Key = I::Key, Message = Self::Message>>(
+        &mut self,
+        message: Option<Self::Message>,
+        tx: &mut S,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        let message = match message {
+            Some(timed) => {
+                let mailbox_time = timed.sent.elapsed();
+                let kind = self.classify.map(|classify| classify(&timed.message).0);
+                let mut shard = self.shard.lock().unwrap();
+                shard.record_delivered(&timed.from, &self.key, kind, mailbox_time);
+                Some(timed.message)
+            }
+            None if self.timed_out => {
+                let mut shard = self.shard.lock().unwrap();
+                shard.actors.entry(self.key.clone()).or_default().timeouts += 1;
+                None
+            }
+            None => None,
+        };
+
+        let mut tx = self.sender(tx);
+        let start = Instant::now();
+        let next = self.internal.process_message(message, &mut tx);
+        let processing_time = start.elapsed();
+
+        self.record_sent(tx);
+        let mut shard = self.shard.lock().unwrap();
+        let actor = shard.actors.entry(self.key.clone()).or_default();
+        actor.processing_time.record(processing_time);
+        drop(shard);
+
+        Ok(self.time_next(next.map_err(MeteredError)?))
+    }
+}
@@ -0,0 +1,138 @@
+//! Passing messages behind a shared pointer, from their sender to all of their recievers.
+//!
+//! By default every backend passes messages by value, and [`Sender::broadcast`] clones the
+//! message once for each reciever. Actors sending large messages to many actors can instead
+//! implement [`SharedInternal`], whose messages are recieved as a [`Shared`] pointer, and be
+//! wrapped into a [`Sharing`] actor to be run by any system. The system then passes
+//! `Shared<Message>` values between actors: a sent message is moved into a single allocation,
+//! and broadcasting it only copies the pointer, however many actors recieve it.
+//!
+//! The actor sends plain messages, which are wrapped by the [`SharingSender`] it is given, and
+//! recieves the shared pointer, which it can read through or keep without cloning the content.
+//! The termination messages of a run are [`Shared`] as well.
+
+use crate::internal::*;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// An actor recieving its messages behind a [`Shared`] pointer.
+///
+/// The trait mirrors [`ActorInternal`], only [`SharedInternal::process_message`] gets the
+/// message as a [`Shared`] pointer. Actors implementing it are run by wrapping them in a
+/// [`Sharing`] actor.
+pub trait SharedInternal: Debug + Send + 'static {
+    /// Messages that are sent between actors, shared between threads by their recievers.
+    type Message: Debug + Send + Sync + Clone + 'static;
+    /// Identifier for an actor
+    type Key: Hash + Send + Clone + Debug + Eq + PartialEq;
+    /// The error type for an actor's internal system
+    type Error: Send + Debug + From<SendError<(Self::Key, Self::Message)>>;
+
+    /// See [`ActorInternal::new_incoming_key`].
+    fn new_incoming_key(&mut self, key: &Self::Key);
+    /// See [`ActorInternal::new_outgoing_key`].
+    fn new_outgoing_key(&mut self, key: &Self::Key);
+
+    /// See [`ActorInternal::start`].
+    fn start<S: Sender<Key = Self::Key, Message = Self::Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Self::Message>, Self::Error>;
+
+    /// Process a message shared with all of its other recievers, see
+    /// [`ActorInternal::process_message`].
+    fn process_message<S: Sender<Key = Self::Key, Message = Self::Message>>(
+        &mut self,
+        message: Option<Shared<Self::Message>>,
+        tx: &mut S,
+    ) -> Result<NextState<Self::Message>, Self::Error>;
+}
+
+/// An actor passing its messages behind a [`Shared`] pointer.
+#[derive(Debug)]
+pub struct Sharing<I>(pub I);
+
+/// The error of a [`Sharing`] actor, which is the error of the actor it wraps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharingError<E>(pub E);
+
+/// A sender moving each message into a [`Shared`] pointer, broadcasting a single pointer.
+#[derive(Debug)]
+pub struct SharingSender<'a, S> {
+    tx: &'a mut S,
+}
+
+impl<'a, S, M> Sender for SharingSender<'a, S>
+where
+    S: Sender<Message = Shared<M>>,
+    M: Debug + Send + Sync + Clone + 'static,
+{
+    type Key = S::Key;
+    type Message = M;
+
+    fn send(&mut self, key: &S::Key, message: M) -> SendResult<S::Key, M> {
+        self.tx
+            .send(key, Shared::new(message))
+            .map_err(|err| err.map(|(key, shared)| (key, shared.into_inner())))
+    }
+
+    fn broadcast<'b, I>(&mut self, keys: I, message: &M) -> SendResult<S::Key, M>
+    where
+        I: IntoIterator<Item = &'b S::Key>,
+        S::Key: 'b,
+    {
+        let shared = Shared::new(message.clone());
+        self.tx
+            .broadcast(keys, &shared)
+            .map_err(|err| err.map(|(key, shared)| (key, shared.into_inner())))
+    }
+}
+
+impl<K, M, E> From<SendError<(K, Shared<M>)>> for SharingError<E>
+where
+    M: Clone,
+    E: From<SendError<(K, M)>>,
+{
+    fn from(err: SendError<(K, Shared<M>)>) -> Self {
+        SharingError(err.map(|(key, shared)| (key, shared.into_inner())).into())
+    }
+}
+
+fn share<M>(next: NextState<M>) -> NextState<Shared<M>> {
+    match next {
+        NextState::Get => NextState::Get,
+        NextState::GetTimeout(duration) => NextState::GetTimeout(duration),
+        NextState::Terminate(value) => NextState::Terminate(value.map(Shared::new)),
+    }
+}
+
+impl<I: SharedInternal> ActorInternal for Sharing<I> {
+    type Message = Shared<I::Message>;
+    type Key = I::Key;
+    type Error = SharingError<I::Error>;
+
+    fn new_incoming_key(&mut self, key: &I::Key) {
+        self.0.new_incoming_key(key)
+    }
+
+    fn new_outgoing_key(&mut self, key: &I::Key) {
+        self.0.new_outgoing_key(key)
+    }
+
+    fn start<S: Sender<Key = I::Key, Message = Self::Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        let next = self.0.start(&mut SharingSender { tx });
+        Ok(share(next.map_err(SharingError)?))
+    }
+
+    fn process_message<S: Sender<Key = I::Key, Message = Self::Message>>(
+        &mut self,
+        message: Option<Self::Message>,
+        tx: &mut S,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        let next = self.0.process_message(message, &mut SharingSender { tx });
+        Ok(share(next.map_err(SharingError)?))
+    }
+}
@@ -0,0 +1,86 @@
+//! Stopping a running system from outside of its actors.
+//!
+//! A system may hold a [`Shutdown`] signal, given by [`System::shutdown`]. Once the signal is
+//! triggered, from any thread, the actors waiting for a message or a timeout stop waiting and
+//! are dropped, and the run of the system returns. Terminals stopped this way have no
+//! termination message. An actor busy processing a message finishes its step first.
+//!
+//! [`System::shutdown`]: crate::System::shutdown
+
+use crossbeam_channel as cb;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// A signal stopping a running system, shared by all of its clones.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    // Dropped once triggered, which disconnects `disconnected` for threads selecting on it.
+    trigger: Mutex<Option<cb::Sender<()>>>,
+    disconnected: cb::Receiver<()>,
+    // Set once triggered, for tasks waiting asynchronously.
+    triggered: watch::Sender<bool>,
+    watch: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (trigger, disconnected) = cb::bounded(0);
+        let (triggered, watch) = watch::channel(false);
+        Shutdown {
+            inner: Arc::new(Inner {
+                trigger: Mutex::new(Some(trigger)),
+                disconnected,
+                triggered,
+                watch,
+            }),
+        }
+    }
+
+    /// Trigger the signal, stopping the system holding it.
+    pub fn trigger(&self) {
+        self.inner.trigger.lock().unwrap().take();
+        self.inner.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.inner.watch.borrow()
+    }
+
+    /// A receiver which never gets a message, and is disconnected once the signal is triggered.
+    ///
+    /// Threads waiting on a channel can wait on the signal as well with [`cb::select!`].
+    pub fn receiver(&self) -> &cb::Receiver<()> {
+        &self.inner.disconnected
+    }
+
+    /// Wait until the signal is triggered.
+    pub async fn triggered(&self) {
+        let mut watch = self.inner.watch.clone();
+        while !*watch.borrow_and_update() {
+            if watch.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Run `future` to completion unless the signal is triggered first, returning `None` if it
+    /// is.
+    pub async fn unless_triggered<F: Future>(self, future: F) -> Option<F::Output> {
+        tokio::select! {
+            output = future => Some(output),
+            _ = self.triggered() => None,
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
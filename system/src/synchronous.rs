@@ -1,13 +1,22 @@
 //! An interface for synchronuous code message passing systems.
 //!
-//! One concrete implementation is provided by the crossbeam_channel crate.
+//! One concrete implementation is provided by the crossbeam_channel crate, running each actor on
+//! a dedicated thread. The [`PoolSystem`] instead runs all the actors on a fixed pool of worker
+//! threads, and can be used for systems with a large number of actors.
+//!
+//! The incoming channel (mailbox) of each actor can be bounded using [`SyncParameters`], together
+//! with an [`OverflowPolicy`] deciding what happens to messages sent to a full mailbox. The growth
+//! of mailboxes can be followed during and after a run using a [`MailboxMonitor`].
 //!
 
 pub mod actor;
 pub mod channel;
 pub mod crossbeam;
+pub mod pool;
 mod standard;
 pub mod system;
 
 pub use actor::Actor;
-pub use channel::{InChannel, OutChannels};
+pub use channel::{InChannel, MailboxReport, OutChannels, OverflowPolicy};
+pub use pool::PoolSystem;
+pub use system::{MailboxMonitor, SyncParameters};
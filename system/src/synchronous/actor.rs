@@ -1,5 +1,7 @@
 use super::channel::{InChannel, OutChannels};
+use super::system::SyncParameters;
 use crate::internal::*;
+use crate::shutdown::Shutdown;
 use std::fmt::Debug;
 use std::hash::Hash;
 
@@ -13,7 +15,7 @@ pub trait ActorInterface: Send + 'static {
     /// The type of message that the actor will send and recieve.
     type Message: Send + Clone + Debug + 'static;
     /// The type of key that the actor will use to identify other actors.
-    type Key: Hash + Send + Copy + Debug + Eq + PartialEq;
+    type Key: Hash + Send + Clone + Debug + Eq + PartialEq;
     /// The type of error that the actor may return.
     type Error: Send + From<<Self::Internal as ActorInternal>::Error> + Debug;
     /// The type of sender that the actor will use to send messages.
@@ -45,36 +47,54 @@ impl<I: ActorInterface> Actor<I> {
         }
     }
 
-    /// Act with respect to a given next state 
+    /// A new actor whose incoming channel is set by the given parameters.
+    pub fn with_parameters(internal: I::Internal, parameters: SyncParameters) -> Self {
+        let in_channel = match parameters.capacity {
+            Some(capacity) => I::InChannel::bounded(capacity, parameters.overflow),
+            None => I::InChannel::new(),
+        };
+        Actor {
+            internal,
+            in_channel,
+            out_channels: I::OutChannels::new(),
+        }
+    }
+
+    /// Act with respect to a given next state, terminating without a message once `shutdown`
+    /// is triggered.
     fn act_next(
         &mut self,
         next_state: NextState<I::Message>,
+        shutdown: &Shutdown,
     ) -> Result<NextState<I::Message>, I::Error> {
-        match next_state {
-            NextState::Get => {
-                let message = self.in_channel.recv();
-                return Ok(self
-                    .internal
-                    .process_message(message, &mut self.out_channels)?);
-            }
-            NextState::GetTimeout(t) => {
-                let message = self.in_channel.recv_timeout(t);
-                return Ok(self
-                    .internal
-                    .process_message(message, &mut self.out_channels)?);
-            }
+        let timeout = match next_state {
+            NextState::Get => None,
+            NextState::GetTimeout(t) => Some(t),
             NextState::Terminate(m) => return Ok(NextState::Terminate(m)),
+        };
+        let message = self.in_channel.recv_until(timeout, shutdown);
+        if message.is_none() && shutdown.is_triggered() {
+            return Ok(NextState::Terminate(None));
         }
+        Ok(self
+            .internal
+            .process_message(message, &mut self.out_channels)?)
     }
 
     pub fn run(&mut self) -> Result<Option<I::Message>, I::Error> {
+        self.run_until(&Shutdown::new())
+    }
+
+    /// Run the actor until it terminates, or until `shutdown` is triggered while it waits for a
+    /// message, in which case it has no termination message.
+    pub fn run_until(&mut self, shutdown: &Shutdown) -> Result<Option<I::Message>, I::Error> {
         let mut next_state = self.internal.start(&mut self.out_channels)?;
 
         loop {
             if let NextState::Terminate(m) = next_state {
                 return Ok(m);
             }
-            next_state = self.act_next(next_state)?;
+            next_state = self.act_next(next_state, shutdown)?;
         }
     }
 }
@@ -1,6 +1,9 @@
 use crate::internal::*;
+use crate::shutdown::Shutdown;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 /// A generic interface for an Agent's incoming channel
@@ -8,15 +11,99 @@ pub trait InChannel: Clone + Send {
     type Message;
     type Sender;
 
+    /// A channel of unlimited capacity
     fn new() -> Self;
 
+    /// A channel holding at most `capacity` messages, with full channels handled by `policy`
+    fn bounded(capacity: usize, policy: OverflowPolicy) -> Self;
+
     fn tx(&self) -> Self::Sender;
 
+    /// The statistics of the channel, shared by all of its senders.
+    fn stats(&self) -> Arc<MailboxStats>;
+
     /// Blocking current thread and wait for a message
     fn recv(&self) -> Option<Self::Message>;
 
     /// Block thread to wait for message for a limited time
     fn recv_timeout(&self, timeout: Duration) -> Option<Self::Message>;
+
+    /// Block thread to wait for a message, for a limited time if a timeout is given, until
+    /// `shutdown` is triggered.
+    fn recv_until(&self, timeout: Option<Duration>, shutdown: &Shutdown) -> Option<Self::Message>;
+}
+
+/// The policy for handling a message sent to a full incoming channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Block the sender until there is room in the channel.
+    ///
+    /// A [`PoolSystem`](super::PoolSystem) never blocks its senders, and treats this policy
+    /// as [`OverflowPolicy::Error`].
+    #[default]
+    Block,
+    /// Drop the message being sent.
+    DropNewest,
+    /// Drop the oldest message in the channel to make room for the new one, which needs a
+    /// positive capacity.
+    DropOldest,
+    /// Return the message to the sender with a [`SendError::Full`] error.
+    Error,
+}
+
+/// Statistics of an incoming channel, updated by its senders.
+#[derive(Debug, Default)]
+pub struct MailboxStats {
+    capacity: Option<usize>,
+    high_watermark: AtomicUsize,
+    dropped: AtomicUsize,
+    rejected: AtomicUsize,
+}
+
+/// A snapshot of the statistics of an incoming channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MailboxReport {
+    /// The capacity of the channel, `None` for unbounded channels.
+    pub capacity: Option<usize>,
+    /// The largest number of messages waiting in the channel after a send.
+    pub high_watermark: usize,
+    /// The number of messages dropped because the channel was full.
+    pub dropped: usize,
+    /// The number of messages returned to their sender because the channel was full.
+    pub rejected: usize,
+}
+
+impl MailboxStats {
+    pub fn new(capacity: Option<usize>) -> Self {
+        MailboxStats {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Record the number of messages in the channel after a send.
+    pub fn record_len(&self, len: usize) {
+        self.high_watermark.fetch_max(len, Ordering::Relaxed);
+    }
+
+    /// Record a message dropped due to overflow.
+    pub fn record_drop(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a message returned to its sender due to overflow.
+    pub fn record_rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn report(&self) -> MailboxReport {
+        MailboxReport {
+            capacity: self.capacity,
+            high_watermark: self.high_watermark.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -29,7 +116,7 @@ pub enum ChannelError<T> {
 /// A generic interface for the functionality of Agent's outgoing channels
 pub trait OutChannels: Debug + Clone + Send + 'static {
     type Message: Debug + Send + Clone + 'static;
-    type Key: Debug + Send + Clone + Copy + Hash + Eq + 'static;
+    type Key: Debug + Send + Clone + Hash + Eq + 'static;
     type Sender;
 
     /// The empry interface without outgoing channels
@@ -40,7 +127,7 @@ pub trait OutChannels: Debug + Clone + Send + 'static {
         &self,
         key: &Self::Key,
         message: Self::Message,
-    ) -> Result<(), SendError<(Self::Key, Self::Message)>>;
+    ) -> SendResult<Self::Key, Self::Message>;
 
     /// Insert an outgoing channel
     ///
@@ -61,7 +148,7 @@ impl<S: OutChannels> Sender for S {
         &mut self,
         key: &Self::Key,
         message: Self::Message,
-    ) -> Result<(), SendError<(Self::Key, Self::Message)>> {
+    ) -> SendResult<Self::Key, Self::Message> {
         OutChannels::send(self, key, message)
     }
 }
@@ -81,6 +168,6 @@ pub enum RecvTimeoutError {
 
 impl<T> From<SendError<T>> for ChannelError<T> {
     fn from(err: SendError<T>) -> Self {
-        ChannelError::SendError(err.0)
+        ChannelError::SendError(err.into_inner())
     }
 }
@@ -2,11 +2,13 @@ use super::actor::*;
 use super::channel::*;
 use super::system::SyncSystem;
 use crate::internal::*;
+use crate::shutdown::Shutdown;
 use crossbeam_channel as cb;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::sync::{Arc, Weak};
 
 #[derive(Debug, Clone)]
 pub struct CrossbeamInterface<I: ActorInternal> {
@@ -15,7 +17,28 @@ pub struct CrossbeamInterface<I: ActorInternal> {
 
 #[derive(Debug, Clone)]
 pub struct OutChannelsCB<K, T> {
-    pub ch_map: HashMap<K, cb::Sender<T>>,
+    pub ch_map: HashMap<K, SenderCB<T>>,
+}
+
+/// The incoming channel of an actor, realized as a crossbeam channel.
+#[derive(Debug, Clone)]
+pub struct InChannelCB<T> {
+    tx: cb::Sender<T>,
+    rx: cb::Receiver<T>,
+    // Senders holding a reciever can only tell the channel is closed when this is dropped.
+    alive: Arc<()>,
+    policy: OverflowPolicy,
+    stats: Arc<MailboxStats>,
+}
+
+/// A sender to an [`InChannelCB`], applying the channel's overflow policy.
+#[derive(Debug, Clone)]
+pub struct SenderCB<T> {
+    tx: cb::Sender<T>,
+    // Used to evict the oldest message under `OverflowPolicy::DropOldest`.
+    evict: Option<(cb::Receiver<T>, Weak<()>)>,
+    policy: OverflowPolicy,
+    stats: Arc<MailboxStats>,
 }
 
 pub type CrossbeamSystem<I> = SyncSystem<CrossbeamInterface<I>>;
@@ -24,21 +47,21 @@ impl<I: ActorInternal> ActorInterface for CrossbeamInterface<I> {
     type Message = I::Message;
     type Key = I::Key;
     type Error = I::Error;
-    type Sender = cb::Sender<I::Message>;
+    type Sender = SenderCB<I::Message>;
 
-    type InChannel = (cb::Sender<I::Message>, cb::Receiver<I::Message>);
+    type InChannel = InChannelCB<I::Message>;
     type OutChannels = OutChannelsCB<I::Key, I::Message>;
     type Internal = I;
 }
 
 impl<K, T> OutChannels for OutChannelsCB<K, T>
 where
-    K: Debug + Eq + Hash + Copy + Send + 'static,
+    K: Debug + Eq + Hash + Clone + Send + 'static,
     T: Debug + Clone + Send + 'static,
 {
     type Message = T;
     type Key = K;
-    type Sender = cb::Sender<T>;
+    type Sender = SenderCB<T>;
 
     fn new() -> Self {
         OutChannelsCB {
@@ -46,13 +69,13 @@ where
         }
     }
 
-    fn send(&self, key: &K, message: T) -> Result<(), SendError<(K, T)>> {
-        match self.ch_map.get(key).unwrap().send(message) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(SendError((*key, err.0))),
+    fn send(&self, key: &K, message: T) -> SendResult<K, T> {
+        match self.ch_map.get(key) {
+            Some(tx) => tx.send(message).map_err(|err| err.map(|m| (key.clone(), m))),
+            None => Err(SendError::UnknownKey((key.clone(), message))),
         }
     }
-    fn insert(&mut self, key: K, tx: cb::Sender<T>) -> Option<cb::Sender<T>> {
+    fn insert(&mut self, key: K, tx: SenderCB<T>) -> Option<SenderCB<T>> {
         self.ch_map.insert(key, tx)
     }
     fn remove(&mut self, key: Self::Key) -> Option<Self::Sender> {
@@ -60,29 +83,133 @@ where
     }
 }
 
+impl<T> SenderCB<T> {
+    /// Send a message, applying the overflow policy if the channel is full.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        // The receiver kept for eviction holds the channel open once the actor is dropped.
+        if let Some((_, alive)) = &self.evict {
+            if alive.strong_count() == 0 {
+                return Err(SendError::Disconnected(message));
+            }
+        }
+        let result = match self.policy {
+            OverflowPolicy::Block => self.tx.send(message).map_err(SendError::from),
+            _ => match self.tx.try_send(message) {
+                Ok(()) => Ok(()),
+                Err(cb::TrySendError::Disconnected(m)) => Err(SendError::Disconnected(m)),
+                Err(cb::TrySendError::Full(m)) => self.overflow(m),
+            },
+        };
+        if result.is_ok() {
+            self.stats.record_len(self.tx.len());
+        }
+        result
+    }
+
+    fn overflow(&self, mut message: T) -> Result<(), SendError<T>> {
+        match (self.policy, &self.evict) {
+            (OverflowPolicy::DropNewest, _) => {
+                self.stats.record_drop();
+                Ok(())
+            }
+            (OverflowPolicy::DropOldest, Some((rx, alive))) => loop {
+                if alive.strong_count() == 0 {
+                    return Err(SendError::Disconnected(message));
+                }
+                if rx.try_recv().is_ok() {
+                    self.stats.record_drop();
+                }
+                match self.tx.try_send(message) {
+                    Ok(()) => return Ok(()),
+                    Err(cb::TrySendError::Full(m)) => message = m,
+                    Err(cb::TrySendError::Disconnected(m)) => {
+                        return Err(SendError::Disconnected(m))
+                    }
+                }
+            },
+            _ => {
+                self.stats.record_rejected();
+                Err(SendError::Full(message))
+            }
+        }
+    }
+}
+
 impl<T> From<cb::SendError<T>> for SendError<T> {
     fn from(err: cb::SendError<T>) -> Self {
-        SendError(err.0)
+        SendError::Disconnected(err.0)
     }
 }
 
-impl<T: Clone + Send> InChannel for (cb::Sender<T>, cb::Receiver<T>) {
+impl<T: Clone + Send> InChannel for InChannelCB<T> {
     type Message = T;
-    type Sender = cb::Sender<T>;
+    type Sender = SenderCB<T>;
+
+    fn new() -> Self {
+        let (tx, rx) = cb::unbounded();
+        InChannelCB {
+            tx,
+            rx,
+            alive: Arc::new(()),
+            policy: OverflowPolicy::Block,
+            stats: Arc::new(MailboxStats::new(None)),
+        }
+    }
 
-    fn new() -> (cb::Sender<T>, cb::Receiver<T>) {
-        cb::unbounded()
+    /// # Panics
+    ///
+    /// If `capacity` is zero under [`OverflowPolicy::DropOldest`], as there is never a message
+    /// to evict to make room for a new one.
+    fn bounded(capacity: usize, policy: OverflowPolicy) -> Self {
+        assert!(
+            capacity > 0 || policy != OverflowPolicy::DropOldest,
+            "a channel dropping its oldest messages needs a positive capacity"
+        );
+        let (tx, rx) = cb::bounded(capacity);
+        InChannelCB {
+            tx,
+            rx,
+            alive: Arc::new(()),
+            policy,
+            stats: Arc::new(MailboxStats::new(Some(capacity))),
+        }
     }
 
     fn tx(&self) -> Self::Sender {
-        self.0.clone()
+        let evict = match self.policy {
+            OverflowPolicy::DropOldest => Some((self.rx.clone(), Arc::downgrade(&self.alive))),
+            _ => None,
+        };
+        SenderCB {
+            tx: self.tx.clone(),
+            evict,
+            policy: self.policy,
+            stats: self.stats.clone(),
+        }
+    }
+
+    fn stats(&self) -> Arc<MailboxStats> {
+        self.stats.clone()
     }
 
     fn recv(&self) -> Option<Self::Message> {
-        self.1.recv().ok()
+        self.rx.recv().ok()
     }
 
     fn recv_timeout(&self, timeout: std::time::Duration) -> Option<Self::Message> {
-        self.1.recv_timeout(timeout).ok()
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    fn recv_until(
+        &self,
+        timeout: Option<std::time::Duration>,
+        shutdown: &Shutdown,
+    ) -> Option<Self::Message> {
+        let timeout = timeout.map_or_else(cb::never, cb::after);
+        cb::select! {
+            recv(self.rx) -> message => message.ok(),
+            recv(timeout) -> _ => None,
+            recv(shutdown.receiver()) -> _ => None,
+        }
     }
 }
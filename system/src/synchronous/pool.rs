@@ -0,0 +1,569 @@
+//! A synchronous system running actors as state machines over a fixed pool of worker threads.
+//!
+//! Each step of an actor (its start, or the processing of a single message or timeout) is run by
+//! one of the workers. An actor waiting for a message does not occupy a thread, so the number of
+//! actors is not limited by the number of threads the system can spawn. Timeouts are served by a
+//! single timer thread holding a hashed timer wheel.
+
+use super::channel::{MailboxStats, OverflowPolicy};
+use super::system::{MailboxMonitor, SyncParameters, SystemError};
+use crate::internal::*;
+use crate::shutdown::Shutdown;
+use crate::topology::Graph;
+use crate::{System, TerminalValues};
+use crossbeam_channel as cb;
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A worker is woken up by this index to check whether the system is shutting down.
+const WAKE_UP: usize = usize::MAX;
+
+/// A system of actors multiplexed over a fixed number of worker threads.
+///
+/// Senders never block, as a blocked sender would hold one of the workers. A message sent to a
+/// full mailbox under [`OverflowPolicy::Block`] is therefore rejected with [`SendError::Full`],
+/// as under [`OverflowPolicy::Error`].
+pub struct PoolSystem<I: ActorInternal> {
+    keys: HashMap<I::Key, usize>,
+    actors: Vec<PoolActor<I>>,
+    terminals: HashSet<I::Key>,
+    workers: usize,
+    tick: Duration,
+    graph: Graph<I::Key>,
+    shutdown: Shutdown,
+}
+
+/// The error returned by [`PoolSystem::run`].
+///
+/// A thread error carries the payload of a panic of the actor's internal operations.
+pub type PoolSystemError<I> = SystemError<<I as ActorInternal>::Error, Box<dyn Any + Send>>;
+
+/// An actor before the system starts running.
+struct PoolActor<I: ActorInternal> {
+    key: I::Key,
+    internal: I,
+    out: HashMap<I::Key, usize>,
+    parameters: SyncParameters,
+    stats: Arc<MailboxStats>,
+}
+
+/// The scheduling state of an actor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// The actor did not start yet.
+    Start,
+    /// The actor is waiting for a message or a timeout.
+    Waiting,
+    /// The actor is in the ready queue.
+    Scheduled,
+    /// The actor is run by a worker.
+    Running,
+    Terminated,
+}
+
+#[derive(Debug)]
+struct Mailbox<M> {
+    queue: VecDeque<M>,
+    state: State,
+    timed_out: bool,
+    // Incremented on every wait, so that timers of previous waits are ignored.
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct MailboxSlot<M> {
+    mailbox: Mutex<Mailbox<M>>,
+    capacity: Option<usize>,
+    overflow: OverflowPolicy,
+    stats: Arc<MailboxStats>,
+}
+
+/// The sender used by actors of a [`PoolSystem`].
+///
+/// Sending a message to a waiting actor schedules it to be run by one of the workers.
+pub struct PoolSender<K, M> {
+    out: HashMap<K, usize>,
+    mailboxes: Arc<Vec<MailboxSlot<M>>>,
+    ready: cb::Sender<usize>,
+}
+
+/// The input of a single step of an actor.
+enum Input<M> {
+    Start,
+    Message(Option<M>),
+}
+
+/// The internal of an actor together with its sender, while the system is running.
+type RunningActor<I> = (
+    I,
+    PoolSender<<I as ActorInternal>::Key, <I as ActorInternal>::Message>,
+);
+
+type Outcome<I> = Result<Option<<I as ActorInternal>::Message>, PoolSystemError<I>>;
+
+/// The state shared by the workers and the timer of a running system.
+struct Pool<I: ActorInternal> {
+    mailboxes: Arc<Vec<MailboxSlot<I::Message>>>,
+    actors: Vec<Mutex<Option<RunningActor<I>>>>,
+    ready: cb::Sender<usize>,
+    ready_rx: cb::Receiver<usize>,
+    timer: Mutex<TimerWheel>,
+    results: cb::Sender<(usize, Outcome<I>)>,
+    shutdown: AtomicBool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Timer {
+    tick: u64,
+    actor: usize,
+    generation: u64,
+}
+
+/// A hashed timer wheel.
+///
+/// Timers are placed in the slot of the tick at which they expire, and the wheel is advanced
+/// one tick at a time. A slot may hold timers of later rotations, which are kept until their
+/// tick is reached.
+#[derive(Debug)]
+struct TimerWheel {
+    start: Instant,
+    tick: Duration,
+    slots: Vec<Vec<Timer>>,
+    // The next tick to be processed.
+    current: u64,
+}
+
+impl TimerWheel {
+    const SLOTS: usize = 512;
+
+    fn new(tick: Duration) -> Self {
+        TimerWheel {
+            start: Instant::now(),
+            tick,
+            slots: vec![Vec::new(); Self::SLOTS],
+            current: 0,
+        }
+    }
+
+    /// The first tick at or after the given instant.
+    fn tick_of(&self, instant: Instant) -> u64 {
+        let elapsed = instant.saturating_duration_since(self.start).as_nanos();
+        elapsed.div_ceil(self.tick.as_nanos().max(1)) as u64
+    }
+
+    fn insert(&mut self, deadline: Instant, actor: usize, generation: u64) {
+        let tick = self.tick_of(deadline).max(self.current);
+        let slot = (tick % Self::SLOTS as u64) as usize;
+        self.slots[slot].push(Timer {
+            tick,
+            actor,
+            generation,
+        });
+    }
+
+    /// Advance the wheel up to the given instant and return the expired timers.
+    fn advance(&mut self, now: Instant) -> Vec<Timer> {
+        let mut expired = Vec::new();
+        let elapsed = now.saturating_duration_since(self.start).as_nanos();
+        let last = (elapsed / self.tick.as_nanos().max(1)) as u64;
+
+        while self.current <= last {
+            let current = self.current;
+            let slot = &mut self.slots[(current % Self::SLOTS as u64) as usize];
+            let (fired, pending) = slot.drain(..).partition(|timer| timer.tick <= current);
+            *slot = pending;
+            expired.extend::<Vec<Timer>>(fired);
+            self.current += 1;
+        }
+        expired
+    }
+}
+
+impl<I: ActorInternal> PoolSystem<I> {
+    /// A new system using one worker per available CPU.
+    pub fn new() -> Self {
+        let workers = thread::available_parallelism().map_or(4, |n| n.get());
+        Self::with_workers(workers)
+    }
+
+    /// A new system running its actors on the given number of worker threads.
+    pub fn with_workers(workers: usize) -> Self {
+        assert!(workers > 0, "a pool system needs at least one worker");
+        PoolSystem {
+            keys: HashMap::new(),
+            actors: Vec::new(),
+            terminals: HashSet::new(),
+            workers,
+            tick: Duration::from_millis(1),
+            graph: Graph::new(),
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    /// Set the resolution of the timer serving timeouts (one millisecond by default).
+    pub fn set_tick(&mut self, tick: Duration) {
+        self.tick = tick;
+    }
+
+    /// A monitor of the incoming channels of all the actors currently in the system.
+    pub fn mailbox_monitor(&self) -> MailboxMonitor<I::Key> {
+        self.actors
+            .iter()
+            .map(|actor| (actor.key.clone(), actor.stats.clone()))
+            .collect()
+    }
+
+    /// Run the system, return the termination messages of all terminal actors.
+    ///
+    /// The method blocks until all terminals are done. Actors that are not terminals are
+    /// dropped once the terminals are done. A terminal with no actor is an
+    /// [`SystemError::UnknownTerminal`] error, returned before any actor starts.
+    ///
+    /// Once the system's [`Shutdown`] signal is triggered, the run returns as soon as the
+    /// workers are done with their current steps, and the terminals which did not terminate have
+    /// no termination message.
+    pub fn run(self) -> Result<TerminalValues<I>, PoolSystemError<I>> {
+        if !self.terminals.iter().all(|key| self.keys.contains_key(key)) {
+            return Err(SystemError::UnknownTerminal);
+        }
+
+        let (ready, ready_rx) = cb::unbounded();
+        let (results, results_rx) = cb::unbounded();
+
+        let mailboxes: Arc<Vec<_>> = Arc::new(
+            self.actors
+                .iter()
+                .map(|actor| MailboxSlot {
+                    mailbox: Mutex::new(Mailbox {
+                        queue: VecDeque::new(),
+                        state: State::Start,
+                        timed_out: false,
+                        generation: 0,
+                    }),
+                    capacity: actor.parameters.capacity,
+                    overflow: actor.parameters.overflow,
+                    stats: actor.stats.clone(),
+                })
+                .collect(),
+        );
+
+        let mut keys = Vec::with_capacity(self.actors.len());
+        let mut actors = Vec::with_capacity(self.actors.len());
+        for actor in self.actors {
+            let tx = PoolSender {
+                out: actor.out,
+                mailboxes: mailboxes.clone(),
+                ready: ready.clone(),
+            };
+            keys.push(actor.key);
+            actors.push(Mutex::new(Some((actor.internal, tx))));
+        }
+
+        for index in 0..actors.len() {
+            ready.send(index).unwrap();
+        }
+
+        let pool = Arc::new(Pool {
+            mailboxes,
+            actors,
+            ready,
+            ready_rx,
+            timer: Mutex::new(TimerWheel::new(self.tick)),
+            results,
+            shutdown: AtomicBool::new(false),
+        });
+
+        let workers: Vec<_> = (0..self.workers)
+            .map(|_| {
+                let pool = pool.clone();
+                thread::spawn(move || pool.work())
+            })
+            .collect();
+
+        let timer = {
+            let pool = pool.clone();
+            let tick = self.tick;
+            thread::spawn(move || pool.serve_timers(tick))
+        };
+
+        // Collect the values of the terminals
+        let mut terminal_values = HashMap::new();
+        let mut result = Ok(());
+        while terminal_values.len() < self.terminals.len() {
+            let (index, outcome) = cb::select! {
+                recv(results_rx) -> result => result.unwrap(),
+                recv(self.shutdown.receiver()) -> _ => {
+                    for key in self.terminals.iter() {
+                        terminal_values.entry(key.clone()).or_insert(None);
+                    }
+                    break;
+                }
+            };
+            let key = &keys[index];
+            if !self.terminals.contains(key) {
+                continue;
+            }
+            match outcome {
+                Ok(value) => {
+                    terminal_values.insert(key.clone(), value);
+                }
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+
+        pool.shutdown.store(true, Ordering::SeqCst);
+        for _ in 0..workers.len() {
+            pool.ready.send(WAKE_UP).ok();
+        }
+        for worker in workers {
+            worker.join().ok();
+        }
+        timer.join().ok();
+
+        result.map(|_| terminal_values)
+    }
+}
+
+impl<I: ActorInternal> Default for PoolSystem<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: ActorInternal> Pool<I> {
+    /// The loop of a worker thread, running actors from the ready queue.
+    fn work(&self) {
+        while let Ok(index) = self.ready_rx.recv() {
+            if self.shutdown.load(Ordering::SeqCst) {
+                return;
+            }
+            if index != WAKE_UP {
+                self.step(index);
+            }
+        }
+    }
+
+    /// The loop of the timer thread.
+    fn serve_timers(&self, tick: Duration) {
+        while !self.shutdown.load(Ordering::SeqCst) {
+            thread::sleep(tick);
+            let expired = self.timer.lock().unwrap().advance(Instant::now());
+            for timer in expired {
+                self.time_out(timer);
+            }
+        }
+    }
+
+    fn time_out(&self, timer: Timer) {
+        let mut mailbox = self.mailboxes[timer.actor].mailbox.lock().unwrap();
+        if mailbox.state == State::Waiting && mailbox.generation == timer.generation {
+            mailbox.timed_out = true;
+            mailbox.state = State::Scheduled;
+            self.ready.send(timer.actor).ok();
+        }
+    }
+
+    /// Run a single step of the actor.
+    fn step(&self, index: usize) {
+        let input = {
+            let mut mailbox = self.mailboxes[index].mailbox.lock().unwrap();
+            let input = match mailbox.state {
+                State::Start => Input::Start,
+                State::Scheduled => match mailbox.queue.pop_front() {
+                    Some(message) => Input::Message(Some(message)),
+                    None if mailbox.timed_out => Input::Message(None),
+                    None => {
+                        mailbox.state = State::Waiting;
+                        return;
+                    }
+                },
+                _ => return,
+            };
+            mailbox.timed_out = false;
+            mailbox.state = State::Running;
+            input
+        };
+
+        let mut actor = self.actors[index].lock().unwrap();
+        let (internal, tx) = match actor.as_mut() {
+            Some(actor) => actor,
+            None => return,
+        };
+        let next_state = panic::catch_unwind(AssertUnwindSafe(|| match input {
+            Input::Start => internal.start(tx),
+            Input::Message(message) => internal.process_message(message, tx),
+        }));
+        drop(actor);
+
+        match next_state {
+            Ok(Ok(NextState::Get)) => self.wait(index, None),
+            Ok(Ok(NextState::GetTimeout(timeout))) => self.wait(index, Some(timeout)),
+            Ok(Ok(NextState::Terminate(value))) => self.terminate(index, Ok(value)),
+            Ok(Err(err)) => self.terminate(index, Err(SystemError::ActorError(err))),
+            Err(payload) => self.terminate(index, Err(SystemError::ThreadError(payload))),
+        }
+    }
+
+    fn wait(&self, index: usize, timeout: Option<Duration>) {
+        let mut mailbox = self.mailboxes[index].mailbox.lock().unwrap();
+        if !mailbox.queue.is_empty() {
+            mailbox.state = State::Scheduled;
+            self.ready.send(index).ok();
+            return;
+        }
+        mailbox.generation += 1;
+        mailbox.state = State::Waiting;
+        if let Some(timeout) = timeout {
+            let deadline = Instant::now() + timeout;
+            let mut timer = self.timer.lock().unwrap();
+            timer.insert(deadline, index, mailbox.generation);
+        }
+    }
+
+    fn terminate(&self, index: usize, outcome: Outcome<I>) {
+        {
+            let mut mailbox = self.mailboxes[index].mailbox.lock().unwrap();
+            mailbox.state = State::Terminated;
+            mailbox.queue.clear();
+        }
+        self.results.send((index, outcome)).ok();
+    }
+}
+
+impl<K: Clone, M> Clone for PoolSender<K, M> {
+    fn clone(&self) -> Self {
+        PoolSender {
+            out: self.out.clone(),
+            mailboxes: self.mailboxes.clone(),
+            ready: self.ready.clone(),
+        }
+    }
+}
+
+impl<K: Debug, M> Debug for PoolSender<K, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoolSender")
+            .field("out", &self.out.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<K, M> Sender for PoolSender<K, M>
+where
+    K: Debug + Send + 'static + Clone + std::hash::Hash + Eq + PartialEq,
+    M: Debug + Send + 'static + Clone,
+{
+    type Key = K;
+    type Message = M;
+
+    fn send(
+        &mut self,
+        key: &Self::Key,
+        message: Self::Message,
+    ) -> SendResult<Self::Key, Self::Message> {
+        let index = match self.out.get(key) {
+            Some(index) => *index,
+            None => return Err(SendError::UnknownKey((key.clone(), message))),
+        };
+        let slot = &self.mailboxes[index];
+        let mut mailbox = slot.mailbox.lock().unwrap();
+
+        if mailbox.state == State::Terminated {
+            return Err(SendError::Disconnected((key.clone(), message)));
+        }
+        if slot.capacity.is_some_and(|c| mailbox.queue.len() >= c) {
+            match slot.overflow {
+                OverflowPolicy::DropNewest => {
+                    slot.stats.record_drop();
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    mailbox.queue.pop_front();
+                    slot.stats.record_drop();
+                }
+                // Blocking would stall a worker, so full mailboxes reject the message instead,
+                // see `PoolSystem`.
+                OverflowPolicy::Block | OverflowPolicy::Error => {
+                    slot.stats.record_rejected();
+                    return Err(SendError::Full((key.clone(), message)));
+                }
+            }
+        }
+
+        mailbox.queue.push_back(message);
+        slot.stats.record_len(mailbox.queue.len());
+        if mailbox.state == State::Waiting {
+            mailbox.state = State::Scheduled;
+            self.ready.send(index).ok();
+        }
+        Ok(())
+    }
+}
+
+impl<I: ActorInternal> System for PoolSystem<I> {
+    type Internal = I;
+    type ActorParameters = SyncParameters;
+    type Error = PoolSystemError<I>;
+
+    fn add_actor(&mut self, key: I::Key, internal: I, parameters: Option<SyncParameters>) {
+        let parameters = parameters.unwrap_or_default();
+        assert!(
+            parameters.capacity != Some(0) || parameters.overflow != OverflowPolicy::DropOldest,
+            "a mailbox dropping its oldest messages needs a positive capacity"
+        );
+        self.graph.add_actor(key.clone(), parameters.kind);
+        let actor = PoolActor {
+            key: key.clone(),
+            internal,
+            out: HashMap::new(),
+            parameters,
+            stats: Arc::new(MailboxStats::new(parameters.capacity)),
+        };
+        match self.keys.get(&key) {
+            Some(&index) => self.actors[index] = actor,
+            None => {
+                self.keys.insert(key, self.actors.len());
+                self.actors.push(actor);
+            }
+        }
+    }
+
+    fn add_channel(&mut self, sender: &I::Key, reciever: &I::Key) {
+        let reciever_index = self.keys[reciever];
+        self.graph.add_channel(sender, reciever);
+
+        if let Some(&index) = self.keys.get(sender) {
+            let actor = &mut self.actors[index];
+            actor.internal.new_outgoing_key(reciever);
+            actor.out.insert(reciever.clone(), reciever_index);
+        }
+
+        self.actors[reciever_index].internal.new_incoming_key(sender);
+    }
+
+    fn add_terminal(&mut self, key: I::Key) {
+        self.graph.add_terminal(key.clone());
+        self.terminals.insert(key);
+    }
+
+    fn graph(&self) -> &Graph<I::Key> {
+        &self.graph
+    }
+
+    fn shutdown(&self) -> Option<Shutdown> {
+        Some(self.shutdown.clone())
+    }
+
+    fn run(self) -> Result<TerminalValues<I>, Self::Error> {
+        PoolSystem::run(self)
+    }
+}
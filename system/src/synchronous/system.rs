@@ -1,9 +1,17 @@
 use super::actor::*;
-use super::channel::{InChannel, OutChannels};
+use super::channel::{InChannel, MailboxReport, MailboxStats, OutChannels, OverflowPolicy};
 use crate::internal::*;
-use crate::System;
+use crate::shutdown::Shutdown;
+use crate::tokio::sync::ActorType;
+use crate::topology::Graph;
+use crate::{System, TerminalValues};
+use crossbeam_channel as cb;
+use std::any::Any;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
 use std::thread;
 
 
@@ -11,6 +19,8 @@ use std::thread;
 pub struct SyncSystem<I: ActorInterface> {
     actors: HashMap<I::Key, Actor<I>>,
     terminals: HashSet<I::Key>,
+    graph: Graph<I::Key>,
+    shutdown: Shutdown,
 }
 
 /// An error that can occur when running a system.
@@ -18,68 +28,221 @@ pub struct SyncSystem<I: ActorInterface> {
 pub enum SystemError<I, S> {
     ActorError(I),
     ThreadError(S),
+    /// A terminal was added for a key with no actor, so the run could never end.
+    UnknownTerminal,
 }
 
+/// The error returned by [`SyncSystem::run`].
+///
+/// A thread error carries the payload of the panic of the actor's thread.
+pub type SyncSystemError<I> = SystemError<<I as ActorInterface>::Error, Box<dyn Any + Send>>;
+
 /// An implementation that assumes keys match with Actor identifiers.
 impl<I: ActorInterface> SyncSystem<I> {
     pub fn new() -> Self {
         SyncSystem {
             actors: HashMap::new(),
             terminals: HashSet::new(),
+            graph: Graph::new(),
+            shutdown: Shutdown::new(),
         }
     }
 
-    pub fn run(self) -> Option<HashMap<I::Key, Option<I::Message>>> {
-        let mut terminal_handles = HashMap::new();
+    /// A monitor of the incoming channels of all the actors currently in the system.
+    ///
+    /// The monitor remains valid after the system is run, and can be used to study the
+    /// growth of mailboxes during and after a run.
+    pub fn mailbox_monitor(&self) -> MailboxMonitor<I::Key> {
+        self.actors
+            .iter()
+            .map(|(key, actor)| (key.clone(), actor.in_channel.stats()))
+            .collect()
+    }
+
+    /// Run the system, return the termination messages of all terminal actors.
+    ///
+    /// Each actor is run on a dedicated thread. The method blocks until all terminals are done.
+    /// The run fails as soon as a terminal fails or panics, and the other actors are then
+    /// stopped. A terminal with no actor is an [`SystemError::UnknownTerminal`] error, returned
+    /// before any actor starts.
+    ///
+    /// Once the system's [`Shutdown`] signal is triggered, the run returns after the threads of
+    /// all the actors exit, with no termination message for the terminals which did not
+    /// terminate.
+    pub fn run(self) -> Result<TerminalValues<I::Internal>, SyncSystemError<I>> {
+        if !self.terminals.iter().all(|key| self.actors.contains_key(key)) {
+            return Err(SystemError::UnknownTerminal);
+        }
+
+        let (results, results_rx) = cb::unbounded();
+        let mut handles = Vec::new();
         for (key, mut actor) in self.actors {
-            let handle = thread::spawn(move || actor.run());
+            let shutdown = self.shutdown.clone();
+            let results = self.terminals.contains(&key).then(|| results.clone());
+            handles.push(thread::spawn(move || {
+                let outcome = panic::catch_unwind(AssertUnwindSafe(|| actor.run_until(&shutdown)));
+                if let Some(results) = results {
+                    results.send((key, outcome)).ok();
+                }
+            }));
+        }
+        // Only the terminals hold on to the results channel.
+        drop(results);
 
-            if self.terminals.contains(&key) {
-                terminal_handles.insert(key, handle);
+        let mut terminal_values = HashMap::new();
+        let mut result = Ok(());
+        while terminal_values.len() < self.terminals.len() {
+            let (key, outcome) = results_rx.recv().expect("terminals report before they exit");
+            match outcome {
+                Ok(Ok(value)) => {
+                    terminal_values.insert(key, value);
+                }
+                Ok(Err(err)) => {
+                    result = Err(SystemError::ActorError(err));
+                    break;
+                }
+                Err(payload) => {
+                    result = Err(SystemError::ThreadError(payload));
+                    break;
+                }
             }
         }
 
-        let mut terminal_values = HashMap::new();
+        if result.is_err() {
+            self.shutdown.trigger();
+        }
+        // Stopped actors exit their threads as soon as they are done with their current step.
+        if self.shutdown.is_triggered() {
+            for handle in handles {
+                handle.join().ok();
+            }
+        }
+
+        result.map(|_| terminal_values)
+    }
+}
 
-        for (k, h) in terminal_handles {
-            let value = h.join().unwrap().unwrap();
-            terminal_values.insert(k, value);
+impl<I: ActorInterface> Default for SyncSystem<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The parameters needed to initialize an actor's incoming channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SyncParameters {
+    /// The maximal number of messages waiting in the channel, `None` for an unbounded channel.
+    pub capacity: Option<usize>,
+    /// The handling of messages sent when the channel is at capacity.
+    pub overflow: OverflowPolicy,
+    /// The type the actor would have in a tokio system, only used to describe the actor, as in
+    /// [`System::to_dot`].
+    pub kind: Option<ActorType>,
+}
+
+impl SyncParameters {
+    pub fn new(capacity: Option<usize>, overflow: OverflowPolicy) -> Self {
+        SyncParameters {
+            capacity,
+            overflow,
+            kind: None,
         }
+    }
 
-        Some(terminal_values)
+    /// Parameters of a channel bounded by `capacity`, blocking senders when full.
+    pub fn bounded(capacity: usize) -> Self {
+        SyncParameters::new(Some(capacity), OverflowPolicy::Block)
     }
 }
 
-pub struct SyncParameters;
+/// Parameters given as `(kind, buffer, internal_buffer)`, as for the tokio system.
+///
+/// The buffer is used as the capacity of the channel and the kind is kept to describe the actor,
+/// the internal buffer is ignored. Messages sent to a full channel are returned to their sender
+/// under [`OverflowPolicy::Error`], as blocking senders on a thread could deadlock actors sending
+/// to each other.
+impl<R> From<(ActorType, usize, R)> for SyncParameters {
+    fn from((kind, buffer, _): (ActorType, usize, R)) -> Self {
+        SyncParameters {
+            kind: Some(kind),
+            ..SyncParameters::new(Some(buffer), OverflowPolicy::Error)
+        }
+    }
+}
+
+/// A handle to the statistics of the incoming channels of a system's actors.
+#[derive(Debug, Clone)]
+pub struct MailboxMonitor<K> {
+    stats: HashMap<K, Arc<MailboxStats>>,
+}
+
+impl<K: Eq + Hash> FromIterator<(K, Arc<MailboxStats>)> for MailboxMonitor<K> {
+    fn from_iter<T: IntoIterator<Item = (K, Arc<MailboxStats>)>>(iter: T) -> Self {
+        MailboxMonitor {
+            stats: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> MailboxMonitor<K> {
+    /// A snapshot of the statistics of the actor identified by `key`.
+    pub fn get(&self, key: &K) -> Option<MailboxReport> {
+        self.stats.get(key).map(|stats| stats.report())
+    }
 
-impl<T, S, R> From<(T, S, R)> for SyncParameters {
-    fn from(_: (T, S, R)) -> Self {
-        SyncParameters {}
+    /// A snapshot of the statistics of all the actors.
+    pub fn report(&self) -> HashMap<K, MailboxReport> {
+        self.stats
+            .iter()
+            .map(|(key, stats)| (key.clone(), stats.report()))
+            .collect()
     }
 }
 
 impl<I: ActorInterface> System for SyncSystem<I> {
     type Internal = I::Internal;
     type ActorParameters = SyncParameters;
-
-    fn add_actor(&mut self, key: I::Key, internal: I::Internal, _: Option<SyncParameters>) {
-        self.actors.insert(key, Actor::new(internal));
+    type Error = SyncSystemError<I>;
+
+    fn add_actor(
+        &mut self,
+        key: I::Key,
+        internal: I::Internal,
+        parameters: Option<SyncParameters>,
+    ) {
+        let parameters = parameters.unwrap_or_default();
+        self.graph.add_actor(key.clone(), parameters.kind);
+        self.actors.insert(key, Actor::with_parameters(internal, parameters));
     }
 
     fn add_channel(&mut self, sender: &I::Key, reciever: &I::Key) {
         let tx = self.actors.get(reciever).unwrap().in_channel.tx();
+        self.graph.add_channel(sender, reciever);
 
-        self.actors.entry(*sender).and_modify(|s| {
+        if let Some(s) = self.actors.get_mut(sender) {
             s.internal.new_outgoing_key(reciever);
-            s.out_channels.insert(*reciever, tx);
-        });
+            s.out_channels.insert(reciever.clone(), tx);
+        }
 
-        self.actors
-            .entry(*reciever)
-            .and_modify(|a| a.internal.new_incoming_key(sender));
+        if let Some(a) = self.actors.get_mut(reciever) {
+            a.internal.new_incoming_key(sender);
+        }
     }
 
     fn add_terminal(&mut self, key: I::Key) {
+        self.graph.add_terminal(key.clone());
         self.terminals.insert(key);
     }
+
+    fn graph(&self) -> &Graph<I::Key> {
+        &self.graph
+    }
+
+    fn shutdown(&self) -> Option<Shutdown> {
+        Some(self.shutdown.clone())
+    }
+
+    fn run(self) -> Result<TerminalValues<I::Internal>, Self::Error> {
+        SyncSystem::run(self)
+    }
 }
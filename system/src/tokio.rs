@@ -1,8 +1,10 @@
 //! Implementations of systems using the [tokio](https://tokio.rs) runtime.
 //!
-//! So far, we have an implementation of actors as different tokio tasks in the module [`sync`].
+//! So far, we have an implementation of actors as different tokio tasks in the module [`sync`],
+//! and a single-threaded implementation for actors that are not [`Send`] in the module [`local`].
 //!
 //!
 
+pub mod local;
 pub mod net;
 pub mod sync;
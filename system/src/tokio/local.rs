@@ -0,0 +1,443 @@
+//! A single-threaded system running actors as tasks of a tokio [`LocalSet`].
+//!
+//! All the actors of a [`LocalSystem`] run on the current thread, so neither the actors nor
+//! their messages have to be [`Send`]. Actors may hold `Rc` or `RefCell` state shared with other
+//! actors of the system, and sending a message involves no synchronization between threads.
+//!
+//! The internal logic of such actors is expressed by implementing the [`LocalInternal`] trait.
+//! Every [`ActorInternal`] is also a [`LocalInternal`], so all actors can run on a local system.
+//! ```
+//! use std::cell::RefCell;
+//! use std::rc::Rc;
+//! use system::internal::{NextState, SendError};
+//! use system::tokio::local::{LocalInternal, LocalSender, LocalSystem};
+//!
+//! // Every actor appends the messages it gets to a log shared by the whole system.
+//! #[derive(Debug)]
+//! struct Logger {
+//!     log: Rc<RefCell<Vec<usize>>>,
+//!     peer: Option<usize>,
+//!     starter: bool,
+//! }
+//!
+//! impl LocalInternal for Logger {
+//!     type Message = usize;
+//!     type Key = usize;
+//!     type Error = SendError<(usize, usize)>;
+//!
+//!     fn new_incoming_key(&mut self, _: &usize) {}
+//!
+//!     fn new_outgoing_key(&mut self, key: &usize) {
+//!         self.peer = Some(*key);
+//!     }
+//!
+//!     fn start(
+//!         &mut self,
+//!         tx: &mut LocalSender<usize, usize>,
+//!     ) -> Result<NextState<usize>, Self::Error> {
+//!         if self.starter {
+//!             tx.send(&self.peer.unwrap(), 0)?;
+//!         }
+//!         Ok(NextState::Get)
+//!     }
+//!
+//!     fn process_message(
+//!         &mut self,
+//!         message: Option<usize>,
+//!         tx: &mut LocalSender<usize, usize>,
+//!     ) -> Result<NextState<usize>, Self::Error> {
+//!         let value = message.unwrap();
+//!         self.log.borrow_mut().push(value);
+//!         tx.send(&self.peer.unwrap(), value + 1).ok();
+//!         Ok(NextState::Terminate(Some(value + 1)))
+//!     }
+//! }
+//!
+//! let log = Rc::new(RefCell::new(Vec::new()));
+//! let mut system = LocalSystem::new();
+//! for i in 0..3 {
+//!     let actor = Logger { log: log.clone(), peer: None, starter: i == 0 };
+//!     system.add_actor(i, actor, None);
+//! }
+//! for i in 0..3 {
+//!     system.add_channel(&i, &((i + 1) % 3));
+//! }
+//! system.add_terminal(0);
+//!
+//! let values = system.run_blocking().unwrap();
+//! assert_eq!(values[&0], Some(3));
+//! assert_eq!(*log.borrow(), vec![0, 1, 2]);
+//! ```
+
+use super::sync::SystemError;
+use crate::internal::*;
+use crate::shutdown::Shutdown;
+use crate::topology::Graph;
+use crate::{System, TerminalValues};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+use tokio::runtime::Builder;
+use tokio::sync::mpsc;
+use tokio::task::{self, LocalSet};
+
+/// The internal logic of an actor run by a [`LocalSystem`].
+///
+/// Unlike [`ActorInternal`], neither the actor nor its messages need to be [`Send`]. Since the
+/// actor never leaves the thread of the system, it sends messages through the concrete
+/// [`LocalSender`] instead of a generic [`Sender`].
+pub trait LocalInternal: Debug + 'static {
+    /// Messages that are sent between actors
+    type Message: Debug + Clone + 'static;
+    /// Identifier for an actor
+    type Key: Hash + Clone + Debug + Eq + PartialEq + 'static;
+
+    /// The error type for an actor's internal system
+    type Error: Debug + From<SendError<(Self::Key, Self::Message)>>;
+
+    /// Get an incoming channel from the system to an actor with identifier given by Key.
+    fn new_incoming_key(&mut self, key: &Self::Key);
+    /// Get an outgoing channel from the system to an actor with identifier given by Key.
+    fn new_outgoing_key(&mut self, key: &Self::Key);
+
+    /// Starting operations of the actor.
+    fn start(
+        &mut self,
+        tx: &mut LocalSender<Self::Key, Self::Message>,
+    ) -> Result<NextState<Self::Message>, Self::Error>;
+
+    /// Process a potential message
+    ///
+    /// The message is None if either the timeout has elapsed or the channel has disconnected.
+    fn process_message(
+        &mut self,
+        message: Option<Self::Message>,
+        tx: &mut LocalSender<Self::Key, Self::Message>,
+    ) -> Result<NextState<Self::Message>, Self::Error>;
+}
+
+/// A sender for actors of a [`LocalSystem`].
+///
+/// Mailboxes are unbounded, so messages are delivered as soon as they are sent.
+#[derive(Debug, Clone)]
+pub struct LocalSender<K, M> {
+    out: HashMap<K, mpsc::UnboundedSender<M>>,
+}
+
+/// The parameters of an actor of a [`LocalSystem`].
+///
+/// Mailboxes of a local system are unbounded and all actors are light, so there is nothing to
+/// set. The conversion from a tuple allows using a local system wherever the tokio parameters are
+/// used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalParameters;
+
+/// A system of actors running as tasks on a single thread.
+#[derive(Debug)]
+pub struct LocalSystem<I: LocalInternal> {
+    actors: HashMap<I::Key, LocalActor<I>>,
+    terminals: HashSet<I::Key>,
+    #[cfg(feature = "paused-time")]
+    start_paused: bool,
+    graph: Graph<I::Key>,
+    shutdown: Shutdown,
+}
+
+/// The termination messages of the terminals of a run.
+type Values<I> = HashMap<<I as LocalInternal>::Key, Option<<I as LocalInternal>::Message>>;
+
+/// The termination message of an actor, or the error it failed with.
+type Outcome<I> =
+    Result<Option<<I as LocalInternal>::Message>, SystemError<<I as LocalInternal>::Error>>;
+
+/// An actor before the system starts running.
+#[derive(Debug)]
+struct LocalActor<I: LocalInternal> {
+    internal: I,
+    tx: LocalSender<I::Key, I::Message>,
+    mailbox: mpsc::UnboundedSender<I::Message>,
+    rx: mpsc::UnboundedReceiver<I::Message>,
+}
+
+impl<K: Hash + Eq + Clone, M> LocalSender<K, M> {
+    fn new() -> Self {
+        LocalSender {
+            out: HashMap::new(),
+        }
+    }
+
+    /// Send a message to the actor identified by `key`.
+    pub fn send(&mut self, key: &K, message: M) -> SendResult<K, M> {
+        match self.out.get(key) {
+            Some(tx) => tx
+                .send(message)
+                .map_err(|err| SendError::Disconnected((key.clone(), err.0))),
+            None => Err(SendError::UnknownKey((key.clone(), message))),
+        }
+    }
+
+    /// Send a message to every actor in `keys`, see [`Sender::broadcast`].
+    pub fn broadcast<'a, I>(&mut self, keys: I, message: &M) -> SendResult<K, M>
+    where
+        I: IntoIterator<Item = &'a K>,
+        K: 'a,
+        M: Clone,
+    {
+        let mut result = Ok(());
+        for key in keys {
+            let sent = self.send(key, message.clone());
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+}
+
+impl<K, M> Sender for LocalSender<K, M>
+where
+    K: Debug + Send + 'static + Clone + Hash + Eq + PartialEq,
+    M: Debug + Send + 'static + Clone,
+{
+    type Key = K;
+    type Message = M;
+
+    fn send(&mut self, key: &K, message: M) -> SendResult<K, M> {
+        LocalSender::send(self, key, message)
+    }
+}
+
+impl<I: ActorInternal> LocalInternal for I {
+    type Message = I::Message;
+    type Key = I::Key;
+    type Error = I::Error;
+
+    fn new_incoming_key(&mut self, key: &Self::Key) {
+        ActorInternal::new_incoming_key(self, key)
+    }
+
+    fn new_outgoing_key(&mut self, key: &Self::Key) {
+        ActorInternal::new_outgoing_key(self, key)
+    }
+
+    fn start(
+        &mut self,
+        tx: &mut LocalSender<Self::Key, Self::Message>,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        ActorInternal::start(self, tx)
+    }
+
+    fn process_message(
+        &mut self,
+        message: Option<Self::Message>,
+        tx: &mut LocalSender<Self::Key, Self::Message>,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        ActorInternal::process_message(self, message, tx)
+    }
+}
+
+impl<T, R> From<(T, usize, R)> for LocalParameters {
+    fn from(_: (T, usize, R)) -> Self {
+        LocalParameters
+    }
+}
+
+impl<I: LocalInternal> LocalActor<I> {
+    fn new(internal: I) -> Self {
+        let (mailbox, rx) = mpsc::unbounded_channel();
+        LocalActor {
+            internal,
+            tx: LocalSender::new(),
+            mailbox,
+            rx,
+        }
+    }
+
+    /// Run the actor until it terminates, returning its termination message.
+    async fn run(self) -> Result<Option<I::Message>, I::Error> {
+        let LocalActor {
+            mut internal,
+            mut tx,
+            mailbox,
+            mut rx,
+        } = self;
+        // Only other actors hold on to the mailbox, so it closes once they are all done.
+        drop(mailbox);
+
+        let mut next_state = internal.start(&mut tx)?;
+        loop {
+            let message = match next_state {
+                NextState::Get => rx.recv().await,
+                NextState::GetTimeout(timeout) => tokio::time::timeout(timeout, rx.recv())
+                    .await
+                    .ok()
+                    .flatten(),
+                NextState::Terminate(msg) => return Ok(msg),
+            };
+            next_state = internal.process_message(message, &mut tx)?;
+        }
+    }
+}
+
+impl<I: LocalInternal> LocalSystem<I> {
+    pub fn new() -> Self {
+        LocalSystem {
+            actors: HashMap::new(),
+            terminals: HashSet::new(),
+            #[cfg(feature = "paused-time")]
+            start_paused: false,
+            graph: Graph::new(),
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    /// Start the runtime built by [`LocalSystem::run_blocking`] with a paused clock.
+    ///
+    /// The clock only advances when all actors are waiting, so timeouts fire as soon as the
+    /// system is idle. Requires the `paused-time` feature.
+    #[cfg(feature = "paused-time")]
+    pub fn set_start_paused(&mut self, paused: bool) {
+        self.start_paused = paused;
+    }
+
+    /// Add a new actor to the system, with a given internal core and identifying key.
+    pub fn add_actor(&mut self, key: I::Key, internal: I, _parameters: Option<LocalParameters>) {
+        // All actors run as tasks on the same thread, they have no type
+        self.graph.add_actor(key.clone(), None);
+        self.actors.insert(key, LocalActor::new(internal));
+    }
+
+    /// Add a channel between the actors identified by `sender` and `reciever`.
+    pub fn add_channel(&mut self, sender: &I::Key, reciever: &I::Key) {
+        let tx = self.actors.get(reciever).unwrap().mailbox.clone();
+        self.graph.add_channel(sender, reciever);
+
+        if let Some(actor) = self.actors.get_mut(sender) {
+            actor.tx.out.insert(reciever.clone(), tx);
+            actor.internal.new_outgoing_key(reciever);
+        }
+
+        if let Some(actor) = self.actors.get_mut(reciever) {
+            actor.internal.new_incoming_key(sender);
+        }
+    }
+
+    /// Add an actor to the set of terminals.
+    pub fn add_terminal(&mut self, key: I::Key) {
+        self.graph.add_terminal(key.clone());
+        self.terminals.insert(key);
+    }
+
+    /// The actors, channels and terminals added to the system so far.
+    pub fn graph(&self) -> &Graph<I::Key> {
+        &self.graph
+    }
+
+    /// Run the system, return the termination messages of all terminal actors.
+    ///
+    /// The actors are spawned on a [`LocalSet`] driven by the returned future, so they all run
+    /// on the thread polling it.
+    ///
+    /// The run fails as soon as a terminal actor fails or panics. A terminal with no actor is
+    /// an [`SystemError::UnknownTerminal`] error, returned before any actor starts.
+    ///
+    /// Once the system's [`Shutdown`] signal is triggered, the actors are dropped and the run
+    /// returns, with no termination message for the terminals which did not terminate.
+    pub async fn run(self) -> Result<Values<I>, SystemError<I::Error>> {
+        if !self.terminals.iter().all(|key| self.actors.contains_key(key)) {
+            return Err(SystemError::UnknownTerminal);
+        }
+        let (tx_term, mut rx_term) = mpsc::unbounded_channel::<(I::Key, Outcome<I>)>();
+        let terminals_size = self.terminals.len();
+
+        LocalSet::new()
+            .run_until(async move {
+                for (key, actor) in self.actors {
+                    let task = task::spawn_local(actor.run());
+                    if self.terminals.contains(&key) {
+                        let tx = tx_term.clone();
+                        task::spawn_local(async move {
+                            let outcome = match task.await {
+                                Ok(value) => value.map_err(SystemError::ActorError),
+                                Err(_) => Err(SystemError::AgentError),
+                            };
+                            tx.send((key, outcome)).ok();
+                        });
+                    }
+                }
+                // Only the terminals hold on to the termination channel.
+                drop(tx_term);
+
+                // Collect all the terminal messages
+                let mut terminal_values = HashMap::new();
+                while terminal_values.len() < terminals_size {
+                    let next = tokio::select! {
+                        next = rx_term.recv() => next,
+                        _ = self.shutdown.triggered() => break,
+                    };
+                    match next {
+                        Some((key, outcome)) => terminal_values.insert(key, outcome?),
+                        None => return Err(SystemError::AgentError),
+                    };
+                }
+                // The actors still running are dropped together with the local set.
+                for key in self.terminals {
+                    terminal_values.entry(key).or_insert(None);
+                }
+                Ok(terminal_values)
+            })
+            .await
+    }
+
+    /// Run the system on a new current thread runtime, blocking the current thread until all
+    /// the terminals are done.
+    ///
+    /// # Panics
+    /// This method panics if called from within an asynchronous execution context.
+    pub fn run_blocking(self) -> Result<Values<I>, SystemError<I::Error>> {
+        let mut builder = Builder::new_current_thread();
+        #[cfg(feature = "paused-time")]
+        builder.start_paused(self.start_paused);
+        let runtime = builder
+            .enable_all()
+            .build()
+            .map_err(|_| SystemError::ThreadError)?;
+        runtime.block_on(self.run())
+    }
+}
+
+impl<I: LocalInternal> Default for LocalSystem<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: ActorInternal> System for LocalSystem<I> {
+    type Internal = I;
+    type ActorParameters = LocalParameters;
+    type Error = SystemError<I::Error>;
+
+    fn add_actor(&mut self, key: I::Key, internal: I, parameters: Option<LocalParameters>) {
+        LocalSystem::add_actor(self, key, internal, parameters)
+    }
+
+    fn add_channel(&mut self, sender: &I::Key, reciever: &I::Key) {
+        LocalSystem::add_channel(self, sender, reciever)
+    }
+
+    fn add_terminal(&mut self, key: I::Key) {
+        LocalSystem::add_terminal(self, key)
+    }
+
+    fn graph(&self) -> &Graph<I::Key> {
+        LocalSystem::graph(self)
+    }
+
+    fn shutdown(&self) -> Option<Shutdown> {
+        Some(self.shutdown.clone())
+    }
+
+    fn run(self) -> Result<TerminalValues<I>, SystemError<I::Error>> {
+        self.run_blocking()
+    }
+}
@@ -1,23 +1,74 @@
 //! Implementation of actors as tokio threads sending messages to each other. 
 //! 
-//! The [`TokioSystem`] 
+//! The [`TokioSystem`] runs each actor as a collection of tokio tasks. 
 //! 
 //! The user can signify the type of actor using the [`ActorType`]. 
 //! 
 //! 
-//! To start a system
+//! To start a system, add actors and channels as with any other [`System`](crate::System) and
+//! then run it, either asynchronously using [`TokioSystem::run`] or from synchronous code using
+//! [`TokioSystem::run_blocking`], which builds its own runtime from the [`RuntimeSettings`] of
+//! the system.
 //! ```
-//! let system = TokioSystem::new()
+//! use system::tokio::sync::{ActorType, RuntimeSettings, TokioSystem};
+//! # use system::internal::*;
+//! # use system::System;
+//! # #[derive(Debug)]
+//! # pub struct Echo {
+//! #     output_key: Option<usize>,
+//! #     starter: bool,
+//! # }
+//! # impl ActorInternal for Echo {
+//! #     type Message = usize;
+//! #     type Error = SendError<(usize, usize)>;
+//! #     type Key = usize;
+//! #
+//! #     fn new_incoming_key(&mut self, _: &Self::Key) {}
+//! #
+//! #     fn new_outgoing_key(&mut self, key: &Self::Key) {
+//! #         self.output_key = Some(*key);
+//! #     }
+//! #
+//! #     fn start<S: Sender<Key = Self::Key, Message = Self::Message>>(
+//! #         &mut self,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<Self::Message>, Self::Error> {
+//! #         if self.starter {
+//! #             tx.send(&self.output_key.unwrap(), 0)?;
+//! #         }
+//! #         Ok(NextState::Get)
+//! #     }
+//! #
+//! #     fn process_message<S: Sender<Key = Self::Key, Message = Self::Message>>(
+//! #         &mut self,
+//! #         message: Option<Self::Message>,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<Self::Message>, Self::Error> {
+//! #         let value = message.unwrap() + 1;
+//! #         tx.send(&self.output_key.unwrap(), value).ok();
+//! #         Ok(NextState::Terminate(Some(value)))
+//! #     }
+//! # }
+//! let mut system = TokioSystem::new(1);
+//! system.set_runtime(RuntimeSettings::current_thread());
+//!
+//! system.add_actor(0, Echo { output_key: None, starter: true }, Some((ActorType::Light, 10, 10).into()));
+//! system.add_actor(1, Echo { output_key: None, starter: false }, Some((ActorType::Light, 10, 10).into()));
+//! system.add_channel(&0, &1);
+//! system.add_channel(&1, &0);
+//! system.add_terminal(0);
+//!
+//! let values = system.run_blocking().unwrap();
+//! assert_eq!(values[&0], Some(2));
 //! ```
 //! 
-//! //TODO: docs here
-//! 
 
 
 mod actor;
 mod actor_core;
 mod channel;
+mod heavy_pool;
 mod system;
 
-pub use self::system::TokioSystem;
-pub use actor_core::ActorType;
+pub use self::system::{RuntimeSettings, SystemError, TokioSystem};
+pub use actor_core::{ActorType, BlockingSender, LightSender, TokioInternal};
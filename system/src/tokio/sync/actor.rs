@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use super::actor_core::*;
-use super::channel::{Channels, SendError};
+use super::channel::Channels;
 use crate::internal::*;
 use std::fmt::Debug;
 use tokio;
@@ -19,22 +19,29 @@ impl<I: TokioInternal> Actor<I, Channels<I::Key, I::Message>> {
     /// Creats a new actor for a tokio based implementaion from an internal instance. 
     /// 
     /// See [`ActorType`] for information about setting the kind variable. 
+    /// Light actors run in a single task together with their channels, so `internal_buffer` is
+    /// only used by blocking and heavy actors.
     pub fn new(internal: I, kind: ActorType, buffer: usize, internal_buffer: usize) -> Self {
+        if kind == ActorType::Light {
+            return Actor {
+                core: ActorCore::Light(LightCore::new(internal)),
+                interface: AgentInterface::new(None, buffer),
+            };
+        }
         let (tx, rx) = mpsc::channel(buffer);
         let (tx_inst, rx_inst) = mpsc::channel(internal_buffer);
+        let core = HeavyCore::new(internal, tx_inst, rx);
         Actor {
-            core: ActorCore::new(internal, kind, tx_inst, rx),
-            interface: AgentInterface::new(tx, rx_inst, buffer),
+            core: match kind {
+                ActorType::Heavy => ActorCore::Heavy(core),
+                _ => ActorCore::Blocking(core),
+            },
+            interface: AgentInterface::new(Some((tx, rx_inst)), buffer),
         }
     }
 
     /// Split the actor into its core and interface.
-    pub fn split(
-        self,
-    ) -> (
-        ActorCore<I>,
-        AgentInterface<I, Channels<I::Key, I::Message>>,
-    ) {
+    pub fn split(self) -> (ActorCore<I>, ChannelsInterface<I>) {
         (self.core, self.interface)
     }
 
@@ -60,13 +67,27 @@ impl<I: TokioInternal> Actor<I, Channels<I::Key, I::Message>> {
     }
 }
 
+/// An actor communicating through [`Channels`].
+pub type ChannelsActor<I> =
+    Actor<I, Channels<<I as TokioInternal>::Key, <I as TokioInternal>::Message>>;
+
+/// An interface communicating through [`Channels`].
+pub type ChannelsInterface<I> =
+    AgentInterface<I, Channels<<I as TokioInternal>::Key, <I as TokioInternal>::Message>>;
+
+/// The link between an interface and a core running outside of the interface's task.
+type CoreLink<I> = (
+    mpsc::Sender<Option<<I as TokioInternal>::Message>>,
+    mpsc::Receiver<Instruction<<I as TokioInternal>::Key, <I as TokioInternal>::Message>>,
+);
+
 /// The interface for an actor in the tokio based implementation.
 /// 
-/// This is the interface that is used to communicate with the actor. 
+/// This is the interface that is used to communicate with the actor. Light actors use the
+/// channels of the interface directly and have no link to their core.
 #[derive(Debug)]
 pub struct AgentInterface<I: TokioInternal, C> {
-    tx: mpsc::Sender<Option<I::Message>>,
-    rx_inst: mpsc::Receiver<Instruction<I::Key, I::Message>>,
+    link: Option<CoreLink<I>>,
     pub channels: C,
     _phantom: PhantomData<I>,
 }
@@ -74,12 +95,12 @@ pub struct AgentInterface<I: TokioInternal, C> {
 #[derive(Debug)]
 pub enum AgentError<I: TokioInternal> {
     InterfaceError(I::Error),
-    SendError(SendError<I::Message>),
+    SendError(SendError<(I::Key, I::Message)>),
     ExitedWithoutValue,
 }
 
-impl<I: TokioInternal> From<SendError<I::Message>> for AgentError<I> {
-    fn from(e: SendError<I::Message>) -> Self {
+impl<I: TokioInternal> From<SendError<(I::Key, I::Message)>> for AgentError<I> {
+    fn from(e: SendError<(I::Key, I::Message)>) -> Self {
         AgentError::SendError(e)
     }
 }
@@ -87,16 +108,9 @@ impl<I: TokioInternal> From<SendError<I::Message>> for AgentError<I> {
 //pub type SyncAgent<I, K, T> = Agent<I, Option<T>, Instruction<K, T>, Channels<K, T>>;
 
 impl<I: TokioInternal> AgentInterface<I, Channels<I::Key, I::Message>> {
-    pub fn new(
-        tx: mpsc::Sender<Option<<I as TokioInternal>::Message>>,
-        rx_inst: mpsc::Receiver<
-            Instruction<<I as TokioInternal>::Key, <I as TokioInternal>::Message>,
-        >,
-        buffer: usize,
-    ) -> Self {
+    fn new(link: Option<CoreLink<I>>, buffer: usize) -> Self {
         AgentInterface {
-            tx,
-            rx_inst,
+            link,
             channels: Channels::new(buffer),
             _phantom: PhantomData,
         }
@@ -104,43 +118,46 @@ impl<I: TokioInternal> AgentInterface<I, Channels<I::Key, I::Message>> {
     }
 
     async fn run_command(
-        &mut self,
+        channels: &mut Channels<I::Key, I::Message>,
+        tx: &mpsc::Sender<Option<I::Message>>,
         command: Instruction<I::Key, I::Message>,
-    ) -> Result<Option<I::Message>, AgentError<I>> {
+    ) -> Result<(), AgentError<I>> {
         match command {
             Instruction::Send(k, msg) => {
-                let tx = self.channels.get(&k).unwrap();
-                tx.send(msg).await.ok();
+                // The core only sends to known keys, so an error means the reciever is done.
+                channels.send(k, msg).await.ok();
             }
             Instruction::Get => {
-                let message = self.channels.recv().await;
-                self.tx.send(message).await.ok();
+                let message = channels.recv().await;
+                tx.send(message).await.ok();
             }
             Instruction::GetTimeout(timeout) => {
-                let message = tokio::time::timeout(timeout, self.channels.recv())
+                let message = tokio::time::timeout(timeout, channels.recv())
                     .await
                     .ok()
                     .flatten();
-                self.tx.send(message).await.ok();
+                tx.send(message).await.ok();
             }
-            Instruction::Terminate(msg) => return Ok(msg),
+            // termination is handled by the run loop
+            Instruction::Terminate(_) => {}
         };
-        Ok(None)
+        Ok(())
     }
 
-    /// Run the actor until it terminates.
-    pub async fn run(
-        &mut self,
-        termination: Option<mpsc::Sender<I::Message>>,
-    ) -> Result<(), AgentError<I>> {
-        while let Some(command) = self.rx_inst.recv().await {
-            let return_value = self.run_command(command).await?;
-            if let Some(msg) = return_value {
-                if let Some(tx) = termination {
-                    tx.send(msg).await.unwrap();
-                }
-                return Ok(());
+    /// Run the interface of a blocking or heavy actor until it terminates.
+    ///
+    /// Returns the termination message of the actor, or an error if its core stops without
+    /// terminating.
+    pub async fn run(&mut self) -> Result<Option<I::Message>, AgentError<I>> {
+        let (tx, rx_inst) = match &mut self.link {
+            Some((tx, rx_inst)) => (tx, rx_inst),
+            None => return Err(AgentError::ExitedWithoutValue),
+        };
+        while let Some(command) = rx_inst.recv().await {
+            if let Instruction::Terminate(msg) = command {
+                return Ok(msg);
             }
+            Self::run_command(&mut self.channels, tx, command).await?;
         }
         Err(AgentError::ExitedWithoutValue)
     }
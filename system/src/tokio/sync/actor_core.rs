@@ -1,6 +1,9 @@
+use super::channel::Channels;
+use super::heavy_pool::HeavyPool;
 use crate::internal::*;
-use std::collections::VecDeque;
-use std::fmt::Debug;
+use serde::Deserialize;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{self, Debug};
 use std::hash::Hash;
 use tokio;
 use tokio::sync::mpsc;
@@ -11,20 +14,34 @@ use tokio::sync::mpsc;
 /// The actor can be of the following types:
 /// * Light - the actor's internal code is not blocking and can be run within an asynchronous function.
 /// * Blocking - the actor's internal code is blocking but not cpu heavy so it can be places within tokio::spawn_blocking task.
-/// * Heavy - the actor internal code is performing cpu heavy operations. A dedicated std::thread will be spawned for performing the internal operations of the actor.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// * Heavy - the actor internal code is performing cpu heavy operations. The internal operations of the actor are run on a bounded pool of threads shared by all heavy actors, see [`TokioSystem::set_heavy_workers`](super::TokioSystem::set_heavy_workers).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ActorType {
     Light,
     Blocking,
     Heavy,
 }
 
+impl fmt::Display for ActorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActorType::Light => f.write_str("light"),
+            ActorType::Blocking => f.write_str("blocking"),
+            ActorType::Heavy => f.write_str("heavy"),
+        }
+    }
+}
+
 /// Functionalities of actor internal specialized to a system based on the tokio runtime
 /// 
-/// This trait can be used idependently from the [`ActorInternal`] trait. 
+/// This trait can be used idependently from the [`ActorInternal`] trait. Every [`ActorInternal`]
+/// is a `TokioInternal`, running the same code whether it is light or blocking, while a direct
+/// implementation can take distinct paths for the two. Enums whose variants hold a
+/// `TokioInternal` can derive it with `system_derive::TokioInternal`.
 pub trait TokioInternal: Send + 'static {
     type Message: Send + Clone + Debug + 'static;
-    type Key: Hash + Send + Copy + Debug + Eq + PartialEq;
+    type Key: Hash + Send + Clone + Debug + Eq + PartialEq;
 
     type Error: Send + Debug;
 
@@ -33,33 +50,54 @@ pub trait TokioInternal: Send + 'static {
 
     fn start_light(
         &mut self,
-        tx: &mut VecDeque<Instruction<Self::Key, Self::Message>>,
+        tx: &mut LightSender<Self::Key, Self::Message>,
     ) -> Result<NextState<Self::Message>, Self::Error>;
 
     fn process_message_light(
         &mut self,
         message: Option<Self::Message>,
-        tx: &mut VecDeque<Instruction<Self::Key, Self::Message>>,
+        tx: &mut LightSender<Self::Key, Self::Message>,
     ) -> Result<NextState<Self::Message>, Self::Error>;
 
     fn start_blocking(
         &mut self,
-        tx: &mut mpsc::Sender<Instruction<Self::Key, Self::Message>>,
+        tx: &mut BlockingSender<Self::Key, Self::Message>,
     ) -> Result<NextState<Self::Message>, Self::Error>;
 
     fn process_message_blocking(
         &mut self,
         message: Option<Self::Message>,
-        tx: &mut mpsc::Sender<Instruction<Self::Key, Self::Message>>,
+        tx: &mut BlockingSender<Self::Key, Self::Message>,
     ) -> Result<NextState<Self::Message>, Self::Error>;
 }
 
+/// A sender for light actors.
+///
+/// Messages are queued and sent by the actor's task once the actor is done with its current
+/// step. Sending to a key with no outgoing channel fails immediately.
+#[derive(Debug, Clone)]
+pub struct LightSender<K, T> {
+    keys: HashSet<K>,
+    instructions: VecDeque<Instruction<K, T>>,
+}
+
+/// A sender for blocking and heavy actors, passing instructions directly to the actor's interface.
+///
+/// Sending to a key with no outgoing channel fails immediately.
+#[derive(Debug, Clone)]
+pub struct BlockingSender<K, T> {
+    keys: HashSet<K>,
+    tx_inst: mpsc::Sender<Instruction<K, T>>,
+}
+
 /// A core containing an actor who performs synchronous code which is not blocking 
+///
+/// The core runs in the same task as the actor's mailbox, so no instructions are passed
+/// between them.
 #[derive(Debug)]
 pub struct LightCore<I: TokioInternal> {
     core: I,
-    rx: mpsc::Receiver<Option<I::Message>>,
-    tx_inst: mpsc::Sender<Instruction<I::Key, I::Message>>,
+    tx: LightSender<I::Key, I::Message>,
 }
 
 /// A core containing an actor who performs synchrnous code that might be blocking. 
@@ -67,7 +105,7 @@ pub struct LightCore<I: TokioInternal> {
 pub struct HeavyCore<I: TokioInternal> {
     core: I,
     rx: mpsc::Receiver<Option<I::Message>>,
-    tx_inst: mpsc::Sender<Instruction<I::Key, I::Message>>,
+    tx: BlockingSender<I::Key, I::Message>,
 }
 
 
@@ -110,18 +148,30 @@ impl<I: TokioInternal> CoreError<I> {
 }
 
 
-impl<K, T> From<mpsc::error::SendError<Instruction<K, T>>> for SendError<(K, T)> {
-    fn from(err: mpsc::error::SendError<Instruction<K, T>>) -> Self {
-        if let Instruction::Send(key, message) = err.0 {
-            return SendError((key, message));
-        }
-        panic!("Not a message!")
+impl<K, T> Sender for mpsc::Sender<Instruction<K, T>>
+where
+    K: Debug + Send + 'static + Clone + Hash + Eq + PartialEq,
+    T: Debug + Send + 'static + Clone,
+{
+    type Key = K;
+    type Message = T;
+
+    fn send(
+        &mut self,
+        key: &Self::Key,
+        message: Self::Message,
+    ) -> SendResult<Self::Key, Self::Message> {
+        self.blocking_send(Instruction::Send(key.clone(), message))
+            .map_err(|err| match err.0 {
+                Instruction::Send(key, message) => SendError::Disconnected((key, message)),
+                _ => unreachable!("only send instructions are passed"),
+            })
     }
 }
 
-impl<K, T> Sender for mpsc::Sender<Instruction<K, T>>
+impl<K, T> Sender for VecDeque<Instruction<K, T>>
 where
-    K: Debug + Send + 'static + Clone + Copy + Hash + Eq + PartialEq,
+    K: Debug + Send + 'static + Clone + Hash + Eq + PartialEq,
     T: Debug + Send + 'static + Clone,
 {
     type Key = K;
@@ -131,14 +181,45 @@ where
         &mut self,
         key: &Self::Key,
         message: Self::Message,
-    ) -> Result<(), SendError<(Self::Key, Self::Message)>> {
-        Ok(self.blocking_send(Instruction::Send(*key, message))?)
+    ) -> SendResult<Self::Key, Self::Message> {
+        self.push_back(Instruction::Send(key.clone(), message));
+        Ok(())
     }
 }
 
-impl<K, T> Sender for VecDeque<Instruction<K, T>>
+impl<K: Hash + Eq, T> LightSender<K, T> {
+    pub fn new() -> Self {
+        LightSender {
+            keys: HashSet::new(),
+            instructions: VecDeque::new(),
+        }
+    }
+
+    /// Allow sending messages to the channel identified by `key`.
+    pub fn insert_key(&mut self, key: K) {
+        self.keys.insert(key);
+    }
+
+    /// Take the next queued instruction.
+    pub fn pop_front(&mut self) -> Option<Instruction<K, T>> {
+        self.instructions.pop_front()
+    }
+
+    /// Queue an instruction after all the messages sent so far.
+    pub fn push_back(&mut self, instruction: Instruction<K, T>) {
+        self.instructions.push_back(instruction);
+    }
+}
+
+impl<K: Hash + Eq, T> Default for LightSender<K, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, T> Sender for LightSender<K, T>
 where
-    K: Debug + Send + 'static + Clone + Copy + Hash + Eq + PartialEq,
+    K: Debug + Send + 'static + Clone + Hash + Eq + PartialEq,
     T: Debug + Send + 'static + Clone,
 {
     type Key = K;
@@ -148,12 +229,57 @@ where
         &mut self,
         key: &Self::Key,
         message: Self::Message,
-    ) -> Result<(), SendError<(Self::Key, Self::Message)>> {
-        self.push_back(Instruction::Send(*key, message));
+    ) -> SendResult<Self::Key, Self::Message> {
+        if !self.keys.contains(key) {
+            return Err(SendError::UnknownKey((key.clone(), message)));
+        }
+        self.instructions.push_back(Instruction::Send(key.clone(), message));
         Ok(())
     }
 }
 
+impl<K: Hash + Eq, T> BlockingSender<K, T> {
+    pub fn new(tx_inst: mpsc::Sender<Instruction<K, T>>) -> Self {
+        BlockingSender {
+            keys: HashSet::new(),
+            tx_inst,
+        }
+    }
+
+    /// Allow sending messages to the channel identified by `key`.
+    pub fn insert_key(&mut self, key: K) {
+        self.keys.insert(key);
+    }
+
+    /// Pass an instruction to the actor's interface, blocking the current thread.
+    pub fn blocking_send(
+        &self,
+        instruction: Instruction<K, T>,
+    ) -> Result<(), mpsc::error::SendError<Instruction<K, T>>> {
+        self.tx_inst.blocking_send(instruction)
+    }
+}
+
+impl<K, T> Sender for BlockingSender<K, T>
+where
+    K: Debug + Send + 'static + Clone + Hash + Eq + PartialEq,
+    T: Debug + Send + 'static + Clone,
+{
+    type Key = K;
+    type Message = T;
+
+    fn send(
+        &mut self,
+        key: &Self::Key,
+        message: Self::Message,
+    ) -> SendResult<Self::Key, Self::Message> {
+        if !self.keys.contains(key) {
+            return Err(SendError::UnknownKey((key.clone(), message)));
+        }
+        Sender::send(&mut self.tx_inst, key, message)
+    }
+}
+
 impl<I: ActorInternal> TokioInternal for I {
     type Message = I::Message;
     type Key = I::Key;
@@ -168,7 +294,7 @@ impl<I: ActorInternal> TokioInternal for I {
 
     fn start_light(
         &mut self,
-        tx: &mut VecDeque<Instruction<Self::Key, Self::Message>>,
+        tx: &mut LightSender<Self::Key, Self::Message>,
     ) -> Result<NextState<Self::Message>, Self::Error> {
         self.start(tx)
     }
@@ -176,14 +302,14 @@ impl<I: ActorInternal> TokioInternal for I {
     fn process_message_light(
         &mut self,
         message: Option<Self::Message>,
-        tx: &mut VecDeque<Instruction<Self::Key, Self::Message>>,
+        tx: &mut LightSender<Self::Key, Self::Message>,
     ) -> Result<NextState<Self::Message>, Self::Error> {
         self.process_message(message, tx)
     }
 
     fn start_blocking(
         &mut self,
-        tx: &mut mpsc::Sender<Instruction<Self::Key, Self::Message>>,
+        tx: &mut BlockingSender<Self::Key, Self::Message>,
     ) -> Result<NextState<Self::Message>, Self::Error> {
         self.start(tx)
     }
@@ -191,52 +317,35 @@ impl<I: ActorInternal> TokioInternal for I {
     fn process_message_blocking(
         &mut self,
         message: Option<Self::Message>,
-        tx: &mut mpsc::Sender<Instruction<Self::Key, Self::Message>>,
+        tx: &mut BlockingSender<Self::Key, Self::Message>,
     ) -> Result<NextState<Self::Message>, Self::Error> {
         self.process_message(message, tx)
     }
 }
 
 impl<I: TokioInternal> ActorCore<I> {
-    pub fn new(
-        internal: I,
-        kind: ActorType,
-        tx_inst: mpsc::Sender<Instruction<I::Key, I::Message>>,
-        rx: mpsc::Receiver<Option<I::Message>>,
-    ) -> Self {
-        match kind {
-            ActorType::Light => ActorCore::Light(LightCore::new(internal, tx_inst, rx)),
-            ActorType::Blocking => ActorCore::Blocking(HeavyCore::new(internal, tx_inst, rx)),
-            ActorType::Heavy => ActorCore::Heavy(HeavyCore::new(internal, tx_inst, rx)),
-        }
-    }
     pub fn new_incoming_key(&mut self, key: &I::Key) {
         match self {
-            ActorCore::Light(core) => core.new_incoming_key(key.into()),
-            ActorCore::Blocking(core) => core.new_incoming_key(key.into()),
-            ActorCore::Heavy(core) => core.new_incoming_key(key.into()),
+            ActorCore::Light(core) => core.new_incoming_key(key),
+            ActorCore::Blocking(core) => core.new_incoming_key(key),
+            ActorCore::Heavy(core) => core.new_incoming_key(key),
         }
     }
 
     pub fn new_outgoing_key(&mut self, key: &I::Key) {
         match self {
-            ActorCore::Light(core) => core.new_outgoing_key(key.into()),
-            ActorCore::Blocking(core) => core.new_outgoing_key(key.into()),
-            ActorCore::Heavy(core) => core.new_outgoing_key(key.into()),
+            ActorCore::Light(core) => core.new_outgoing_key(key),
+            ActorCore::Blocking(core) => core.new_outgoing_key(key),
+            ActorCore::Heavy(core) => core.new_outgoing_key(key),
         }
     }
 }
 
 impl<I: TokioInternal> LightCore<I> {
-    fn new(
-        internal: I,
-        tx_inst: mpsc::Sender<Instruction<I::Key, I::Message>>,
-        rx: mpsc::Receiver<Option<I::Message>>,
-    ) -> Self {
+    pub fn new(internal: I) -> Self {
         LightCore {
             core: internal,
-            rx,
-            tx_inst,
+            tx: LightSender::new(),
         }
     }
 
@@ -245,52 +354,56 @@ impl<I: TokioInternal> LightCore<I> {
     }
 
     pub fn new_outgoing_key(&mut self, key: &I::Key) {
+        self.tx.insert_key(key.clone());
         self.core.new_outgoing_key(key)
     }
 
-    pub async fn start(&mut self) -> Result<(), CoreError<I>> {
-        let mut instructions = VecDeque::new();
-
-        let next_state = 
-            self.core.start_light(&mut instructions)
-            .map_err(CoreError::from_internal)?;
-        instructions.push_back(next_state.into());
-
-        while let Some(inst) = instructions.pop_front() {
-            self.tx_inst.send(inst).await?;
+    /// Send the messages queued during the last step.
+    async fn flush(&mut self, channels: &mut Channels<I::Key, I::Message>) {
+        while let Some(inst) = self.tx.pop_front() {
+            if let Instruction::Send(k, msg) = inst {
+                // The sender only queues known keys, so an error means the reciever is done.
+                channels.send(k, msg).await.ok();
+            }
         }
-        Ok(())
     }
 
-    pub async fn process_message(&mut self, message: Option<I::Message>) -> Result<(), CoreError<I>> {
-        let mut instructions = VecDeque::new();
-
-        let next_state = self
+    /// Run the actor until it terminates, recieving from and sending to the given channels.
+    ///
+    /// Returns the termination message of the actor.
+    pub async fn run(
+        &mut self,
+        channels: &mut Channels<I::Key, I::Message>,
+    ) -> Result<Option<I::Message>, CoreError<I>> {
+        let mut next_state = self
             .core
-            .process_message_light(message, &mut instructions)
+            .start_light(&mut self.tx)
             .map_err(CoreError::from_internal)?;
-        instructions.push_back(next_state.into());
 
-        while let Some(inst) = instructions.pop_front() {
-            self.tx_inst.send(inst).await?;
+        loop {
+            self.flush(channels).await;
+            let message = match next_state {
+                NextState::Get => channels.recv().await,
+                NextState::GetTimeout(timeout) => {
+                    tokio::time::timeout(timeout, channels.recv())
+                        .await
+                        .ok()
+                        .flatten()
+                }
+                NextState::Terminate(msg) => return Ok(msg),
+            };
+            next_state = self
+                .core
+                .process_message_light(message, &mut self.tx)
+                .map_err(CoreError::from_internal)?;
         }
-        Ok(())
-    }
-
-    pub async fn run(&mut self) -> Result<(), CoreError<I>> {
-        self.start().await?;
-
-        while let Some(message) = self.rx.recv().await {
-            self.process_message(message).await?;
-        }
-        Ok(())
     }
 }
 
 
 
 impl<I: TokioInternal> HeavyCore<I> {
-    fn new(
+    pub fn new(
         internal: I,
         tx_inst: mpsc::Sender<Instruction<I::Key, I::Message>>,
         rx: mpsc::Receiver<Option<I::Message>>,
@@ -298,7 +411,7 @@ impl<I: TokioInternal> HeavyCore<I> {
         HeavyCore {
             core: internal,
             rx,
-            tx_inst,
+            tx: BlockingSender::new(tx_inst),
         }
     }
 
@@ -308,27 +421,28 @@ impl<I: TokioInternal> HeavyCore<I> {
     }
 
     pub fn new_outgoing_key(&mut self, key: &I::Key) {
+        self.tx.insert_key(key.clone());
         self.core.new_outgoing_key(key)
     }
 
     pub fn start(&mut self) -> Result<(), CoreError<I>> {
         let next_state = 
-            self.core.start_blocking(&mut self.tx_inst)
+            self.core.start_blocking(&mut self.tx)
             .map_err(CoreError::from_internal)?;
         
         // send instructions to the interface
-        self.tx_inst.blocking_send(next_state.into())?;
+        self.tx.blocking_send(next_state.into())?;
         Ok(())
     }
 
     pub fn process_message(&mut self, message: Option<I::Message>) -> Result<(), CoreError<I>> {
         let next_state = self
             .core
-            .process_message_blocking(message, &mut self.tx_inst)
+            .process_message_blocking(message, &mut self.tx)
             .map_err(CoreError::from_internal)?;
 
         // send instructions to the interface
-        self.tx_inst.blocking_send(next_state.into())?;
+        self.tx.blocking_send(next_state.into())?;
         Ok(())
     }
 
@@ -340,4 +454,31 @@ impl<I: TokioInternal> HeavyCore<I> {
         }
         Ok(())
     }
+
+    /// Run the actor, handing each of its steps to the given pool.
+    ///
+    /// Messages are awaited outside the pool, so an actor waiting for a message does not hold
+    /// on to a thread.
+    pub async fn run_on(mut self, pool: HeavyPool) -> Result<(), CoreError<I>> {
+        let (core, result) = pool
+            .run(move || {
+                let result = self.start();
+                (self, result)
+            })
+            .await;
+        self = core;
+        result?;
+
+        while let Some(message) = self.rx.recv().await {
+            let (core, result) = pool
+                .run(move || {
+                    let result = self.process_message(message);
+                    (self, result)
+                })
+                .await;
+            self = core;
+            result?;
+        }
+        Ok(())
+    }
 }
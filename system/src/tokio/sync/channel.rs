@@ -1,9 +1,9 @@
+use crate::internal::{SendError, SendResult};
 use std::collections::HashMap;
+use std::future::Future;
 use std::hash::Hash;
 use tokio::sync::mpsc;
 
-pub type SendError<T> = mpsc::error::SendError<T>;
-
 #[derive(Debug)]
 pub struct Channels<K, M> {
     tx: mpsc::Sender<M>,
@@ -13,7 +13,7 @@ pub struct Channels<K, M> {
 
 impl<K, M> Channels<K, M>
 where
-    K: Eq + Hash + Copy,
+    K: Eq + Hash,
 {
     pub fn new(buffer: usize) -> Self {
         let (tx, rx) = mpsc::channel(buffer);
@@ -38,8 +38,20 @@ where
         self.out_channels.remove(key)
     }
 
-    pub async fn send(&self, key: &K, message: M) -> Result<(), SendError<M>> {
-        self.out_channels.get(key).unwrap().send(message).await
+    /// Send a message in the outgoing channel marked by key.
+    ///
+    /// The returned future does not borrow the channels.
+    pub fn send(&self, key: K, message: M) -> impl Future<Output = SendResult<K, M>> {
+        let tx = self.get(&key);
+        async move {
+            match tx {
+                Some(tx) => tx
+                    .send(message)
+                    .await
+                    .map_err(|err| SendError::Disconnected((key, err.0))),
+                None => Err(SendError::UnknownKey((key, message))),
+            }
+        }
     }
 
     pub fn get(&self, key: &K) -> Option<mpsc::Sender<M>> {
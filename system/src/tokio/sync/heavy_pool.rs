@@ -0,0 +1,69 @@
+//! A bounded pool of threads running the steps of heavy actors.
+//!
+//! A heavy actor does not own a thread. Its task waits for the next message asynchronously and
+//! then hands the step (the start of the actor, or the processing of a single message) to the
+//! pool, so at most as many heavy steps run at once as the pool has threads.
+//!
+//! The threads enter the runtime the pool is spawned from, so that steps read its clock, which
+//! may be paused, rather than the wall clock.
+
+use crossbeam_channel as cb;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+use tokio::runtime::Handle;
+use tokio::sync::oneshot;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A handle to a pool of threads running blocking jobs.
+///
+/// The threads exit once all the handles to the pool are dropped.
+#[derive(Debug, Clone)]
+pub struct HeavyPool {
+    jobs: cb::Sender<Job>,
+}
+
+impl HeavyPool {
+    /// Spawn a pool of `workers` threads, in the context of the current runtime if any.
+    ///
+    /// # Panics
+    /// Panics if `workers` is zero.
+    pub fn new(workers: usize) -> Self {
+        assert!(workers > 0, "a heavy pool needs at least one worker");
+        let (tx, rx) = cb::unbounded::<Job>();
+        let runtime = Handle::try_current().ok();
+        for _ in 0..workers {
+            let rx = rx.clone();
+            let runtime = runtime.clone();
+            thread::spawn(move || {
+                let _guard = runtime.as_ref().map(Handle::enter);
+                for job in rx {
+                    job();
+                }
+            });
+        }
+        HeavyPool { jobs: tx }
+    }
+
+    /// Run `f` on one of the threads of the pool and wait for its result.
+    ///
+    /// A panic in `f` does not bring down the thread, it is resumed in the caller instead.
+    pub async fn run<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = move || {
+            tx.send(panic::catch_unwind(AssertUnwindSafe(f))).ok();
+        };
+        self.jobs
+            .send(Box::new(job))
+            .expect("the pool threads live as long as a handle");
+
+        match rx.await.expect("every job sends its result") {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
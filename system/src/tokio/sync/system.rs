@@ -1,11 +1,16 @@
 use super::actor::*;
-use super::actor_core::{ActorCore, ActorType, TokioInternal};
-use super::channel::Channels;
+use super::actor_core::{ActorCore, ActorType, CoreError, TokioInternal};
+use super::heavy_pool::HeavyPool;
 use crate::internal::*;
-use crate::System;
+use crate::shutdown::Shutdown;
+use crate::topology::Graph;
+use crate::{System, TerminalValues};
 use std::collections::{HashMap, HashSet};
+use std::thread;
 use tokio;
+use tokio::runtime::{Builder, Runtime};
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 use std::fmt::Debug;
 
@@ -13,17 +18,65 @@ use std::fmt::Debug;
 #[derive(Debug)]
 pub struct TokioSystem<I: TokioInternal> {
     //pub interfaces: HashMap<I::Key, Interface<I>>,
-    pub agents: HashMap<I::Key, Actor<I, Channels<I::Key, I::Message>>>,
+    pub agents: HashMap<I::Key, ChannelsActor<I>>,
     pub terminals: HashSet<I::Key>,
-    tx_term: mpsc::Sender<I::Message>,
-    rx_term: mpsc::Receiver<I::Message>,
+    tx_term: TerminationSender<I>,
+    rx_term: mpsc::Receiver<(I::Key, Outcome<I>)>,
+    heavy_workers: usize,
+    runtime: RuntimeSettings,
+    graph: Graph<I::Key>,
+    shutdown: Shutdown,
 }
 
 
+/// The error returned when a run of a tokio based system fails.
 #[derive(Debug)]
-pub enum SystemError {
+pub enum SystemError<E> {
+    /// A terminal agent failed with the error of its actor.
+    ActorError(E),
+    /// A terminal agent panicked, or stopped without terminating.
     AgentError,
+    /// The runtime could not be built.
     ThreadError,
+    /// A terminal was added for a key with no agent, so the run could never end.
+    UnknownTerminal,
+}
+
+/// The termination messages of the terminals of a run.
+type Values<I> = HashMap<<I as TokioInternal>::Key, Option<<I as TokioInternal>::Message>>;
+
+/// The termination message of an agent, or the error it failed with.
+type Outcome<I> =
+    Result<Option<<I as TokioInternal>::Message>, SystemError<<I as TokioInternal>::Error>>;
+
+/// The sending end of the channel collecting the outcomes of terminal agents.
+type TerminationSender<I> = mpsc::Sender<(<I as TokioInternal>::Key, Outcome<I>)>;
+
+impl<I: TokioInternal> From<CoreError<I>> for SystemError<I::Error> {
+    fn from(err: CoreError<I>) -> Self {
+        match err {
+            CoreError::InternalError(err) => SystemError::ActorError(err),
+            // The interface of the agent is gone
+            CoreError::InstructionChannelError(_) => SystemError::AgentError,
+        }
+    }
+}
+
+/// Run the interface of a blocking or heavy agent whose core runs in the given task.
+///
+/// Returns the termination message passed by the core, or the error it stopped with.
+async fn run_interface<I: TokioInternal>(
+    mut interface: ChannelsInterface<I>,
+    core: JoinHandle<Result<(), CoreError<I>>>,
+) -> Outcome<I> {
+    if let Ok(value) = interface.run().await {
+        return Ok(value);
+    }
+    // The core dropped its link to the interface, so it is done
+    match core.await {
+        Ok(Err(err)) => Err(err.into()),
+        _ => Err(SystemError::AgentError),
+    }
 }
 
 impl<I: TokioInternal> TokioSystem<I> {
@@ -34,54 +87,243 @@ impl<I: TokioInternal> TokioSystem<I> {
             terminals: HashSet::new(),
             tx_term: tx,
             rx_term: rx,
+            heavy_workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            runtime: RuntimeSettings::default(),
+            graph: Graph::new(),
+            shutdown: Shutdown::new(),
+        }
+    }
+
+    /// Set the settings of the runtime built by [`TokioSystem::run_blocking`].
+    pub fn set_runtime(&mut self, settings: RuntimeSettings) {
+        self.runtime = settings;
+    }
+
+    /// Set the number of threads running the steps of [`ActorType::Heavy`] actors.
+    ///
+    /// At most this many heavy actors process messages at the same time. By default, the pool
+    /// has one thread for each available core.
+    ///
+    /// # Panics
+    /// Panics if `workers` is zero.
+    pub fn set_heavy_workers(&mut self, workers: usize) {
+        assert!(workers > 0, "a heavy pool needs at least one worker");
+        self.heavy_workers = workers;
+    }
+
+    /// Add a terminal agent, whose termination message is returned by the run of the system.
+    pub fn add_terminal(&mut self, key: I::Key) {
+        self.graph.add_terminal(key.clone());
+        self.terminals.insert(key);
+    }
+
+    /// Add an agent running `internal`, as in [`System::add_actor`].
+    ///
+    /// Unlike the [`System`] implementation, which requires an [`ActorInternal`], this accepts
+    /// any [`TokioInternal`].
+    ///
+    /// # Panics
+    /// Panics if no parameters are given.
+    pub fn add_actor(&mut self, key: I::Key, internal: I, parameters: Option<Parameters>) {
+        let param = parameters.unwrap();
+        let (kind, buffer, internal_buffer) = (param.kind, param.buffer, param.internal_buffer);
+        let agent = Actor::new(internal, kind, buffer, internal_buffer);
+
+        self.graph.add_actor(key.clone(), Some(kind));
+        self.agents.insert(key, agent);
+    }
+
+    /// Add a channel from `sender` to `reciever`.
+    pub fn add_channel(&mut self, sender: &I::Key, reciever: &I::Key) {
+        let tx = self.agents.get(reciever).unwrap().tx_channel();
+        self.graph.add_channel(sender, reciever);
+
+        if let Some(agent) = self.agents.get_mut(sender) {
+            agent.insert_outgoing_channel(reciever.clone(), tx);
+            agent.new_outgoing_key(reciever);
+        }
+
+        if let Some(interface) = self.agents.get_mut(reciever) {
+            interface.new_incoming_key(sender);
         }
     }
 
-    /// Run the system, return the termination messages of all terminal agents. 
-    pub async fn run(mut self) -> Result<Vec<I::Message>, SystemError> {
+    /// The agents, channels and terminals added to the system so far.
+    pub fn graph(&self) -> &Graph<I::Key> {
+        &self.graph
+    }
+
+    /// Run the system, return the termination messages of all terminal agents.
+    ///
+    /// The run fails as soon as a terminal agent fails or panics. A terminal with no agent is
+    /// an [`SystemError::UnknownTerminal`] error, returned before any agent starts.
+    ///
+    /// Once the system's [`Shutdown`] signal is triggered, the tasks of the agents are dropped
+    /// and the run returns, with no termination message for the terminals which did not
+    /// terminate.
+    pub async fn run(mut self) -> Result<Values<I>, SystemError<I::Error>> {
+        if !self.terminals.iter().all(|key| self.agents.contains_key(key)) {
+            return Err(SystemError::UnknownTerminal);
+        }
+
+        // The pool of heavy actors is only spawned if there are any
+        let mut heavy_pool = None;
+
         // Spawn threads for agents
         for (key, agent) in self.agents {
             let (core, mut interface) = agent.split();
+            let shutdown = self.shutdown.clone();
+
             // Spawn a thread for each agent
-            match core {
-                ActorCore::Light(mut core) => {
-                    tokio::spawn(async move { core.run().await.ok() });
-                }
+            let task = match core {
+                // light actors run in a single task together with their channels
+                ActorCore::Light(mut core) => tokio::spawn(shutdown.unless_triggered(async move {
+                    core.run(&mut interface.channels).await.map_err(SystemError::from)
+                })),
+                // blocking cores return once their interface is dropped
                 ActorCore::Blocking(mut core) => {
-                    tokio::task::spawn_blocking(move || {
-                        core.run().ok();
-                    });
+                    let core = tokio::task::spawn_blocking(move || core.run());
+                    tokio::spawn(shutdown.unless_triggered(run_interface(interface, core)))
                 }
-                // spawn a new thread for heavy actors
-                ActorCore::Heavy(mut core) => {
-                    std::thread::spawn(move || core.run().ok());
+                // heavy actors share a bounded pool of threads
+                ActorCore::Heavy(core) => {
+                    let pool = heavy_pool
+                        .get_or_insert_with(|| HeavyPool::new(self.heavy_workers))
+                        .clone();
+                    let core = tokio::spawn(core.run_on(pool));
+                    tokio::spawn(shutdown.unless_triggered(run_interface(interface, core)))
                 }
-            }
-
-            let tx = match self.terminals.contains(&key) {
-                true => Some(self.tx_term.clone()),
-                false => None,
             };
 
-            tokio::spawn(async move { interface.run(tx).await });
+            // Report the outcome of terminals, unless they were stopped by the shutdown
+            if self.terminals.contains(&key) {
+                let tx = self.tx_term.clone();
+                tokio::spawn(async move {
+                    let outcome = match task.await {
+                        Ok(Some(outcome)) => outcome,
+                        Ok(None) => return,
+                        Err(_) => Err(SystemError::AgentError),
+                    };
+                    tx.send((key, outcome)).await.ok();
+                });
+            }
         }
+        // Only the terminals hold on to the termination channel.
+        drop(self.tx_term);
 
         // Collect all the terminal messages
-        let mut terminal_values = Vec::new();
+        let mut terminal_values = HashMap::new();
         let terminals_size = self.terminals.len();
-        let mut counter = 0;
 
-    
         // wait for all the terminal messages
-        while let Some(msg) = self.rx_term.recv().await {
-            terminal_values.push(msg);
-            counter += 1;
-            if counter >= terminals_size {
-                break;
-            }
+        while terminal_values.len() < terminals_size {
+            let next = tokio::select! {
+                next = self.rx_term.recv() => next,
+                _ = self.shutdown.triggered() => break,
+            };
+            match next {
+                Some((key, outcome)) => terminal_values.insert(key, outcome?),
+                None => return Err(SystemError::AgentError),
+            };
+        }
+        for key in self.terminals {
+            terminal_values.entry(key).or_insert(None);
         }
         Ok(terminal_values)
     }
+
+    /// Run the system on a new tokio runtime, blocking the current thread until all the
+    /// terminals are done.
+    ///
+    /// The runtime is built from the system's [`RuntimeSettings`], see
+    /// [`TokioSystem::set_runtime`].
+    ///
+    /// # Panics
+    /// This method panics if called from within an asynchronous execution context.
+    pub fn run_blocking(self) -> Result<Values<I>, SystemError<I::Error>> {
+        let runtime = self.runtime.build().map_err(|_| SystemError::ThreadError)?;
+        runtime.block_on(self.run())
+    }
+}
+
+/// The settings of the tokio runtime a [`TokioSystem`] builds when run with
+/// [`TokioSystem::run_blocking`].
+///
+/// The default settings give a multi-threaded runtime with tokio's default number of threads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeSettings {
+    /// The number of worker threads of a multi-threaded runtime, one per core if not set.
+    pub worker_threads: Option<usize>,
+    /// The maximal number of threads running [`ActorType::Blocking`] actors, tokio's default
+    /// if not set.
+    pub max_blocking_threads: Option<usize>,
+    /// Run all the asynchronous tasks on the current thread.
+    pub current_thread: bool,
+    /// Start the runtime with a paused clock, implies `current_thread`.
+    ///
+    /// The clock only advances when all tasks are waiting, so timeouts fire as soon as the
+    /// system is idle. Steps of [`ActorType::Heavy`] actors run on threads outside of the
+    /// runtime, which read its clock but do not hold it back. Building the runtime fails
+    /// without the `paused-time` feature.
+    pub start_paused: bool,
+}
+
+impl RuntimeSettings {
+    /// Settings for a runtime running all tasks on the current thread.
+    pub fn current_thread() -> Self {
+        RuntimeSettings {
+            current_thread: true,
+            ..Default::default()
+        }
+    }
+
+    /// Settings for a multi-threaded runtime with the given number of worker threads.
+    pub fn multi_thread(worker_threads: usize) -> Self {
+        RuntimeSettings {
+            worker_threads: Some(worker_threads),
+            ..Default::default()
+        }
+    }
+
+    /// Settings for a current thread runtime whose clock starts paused.
+    ///
+    /// Useful for tests and reproducible simulations involving timeouts. Requires the
+    /// `paused-time` feature.
+    #[cfg(feature = "paused-time")]
+    pub fn paused() -> Self {
+        RuntimeSettings {
+            current_thread: true,
+            start_paused: true,
+            ..Default::default()
+        }
+    }
+
+    /// Build a new runtime from the settings.
+    ///
+    /// Fails if `start_paused` is set without the `paused-time` feature.
+    pub fn build(&self) -> std::io::Result<Runtime> {
+        #[cfg(not(feature = "paused-time"))]
+        if self.start_paused {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "starting paused requires the `paused-time` feature",
+            ));
+        }
+
+        let mut builder = match self.current_thread || self.start_paused {
+            true => Builder::new_current_thread(),
+            false => Builder::new_multi_thread(),
+        };
+        if let Some(threads) = self.worker_threads {
+            builder.worker_threads(threads);
+        }
+        if let Some(threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(threads);
+        }
+        #[cfg(feature = "paused-time")]
+        builder.start_paused(self.start_paused);
+        builder.enable_all().build()
+    }
 }
 
 /// The parameters needed to initialize an agent. 
@@ -116,32 +358,29 @@ impl From<(ActorType, usize, usize)> for Parameters {
 impl<I: ActorInternal> System for TokioSystem<I> {
     type Internal = I;
     type ActorParameters = Parameters;
+    type Error = SystemError<I::Error>;
 
     fn add_terminal(&mut self, key: I::Key) {
-        self.terminals.insert(key);
+        TokioSystem::add_terminal(self, key)
     }
 
     fn add_actor(&mut self, key: I::Key, internal: I, parameters: Option<Parameters>) {
-        let param = parameters.unwrap();
-        let (kind, buffer, internal_buffer) = (param.kind, param.buffer, param.internal_buffer);
-        let agent = Actor::new(internal, kind, buffer, internal_buffer);
-
-        self.agents.insert(key, agent);
+        TokioSystem::add_actor(self, key, internal, parameters)
     }
 
     fn add_channel(&mut self, sender: &I::Key, reciever: &I::Key) {
-        let tx = self.agents.get(reciever).unwrap().tx_channel();
+        TokioSystem::add_channel(self, sender, reciever)
+    }
 
-        self.agents.entry(*sender).and_modify(|agent| {
-            agent.insert_outgoing_channel(*reciever, tx);
-        });
+    fn graph(&self) -> &Graph<I::Key> {
+        TokioSystem::graph(self)
+    }
 
-        self.agents.entry(*sender).and_modify(|interface| {
-            interface.new_outgoing_key(reciever);
-        });
+    fn shutdown(&self) -> Option<Shutdown> {
+        Some(self.shutdown.clone())
+    }
 
-        self.agents
-            .entry(*reciever)
-            .and_modify(|interface| interface.new_incoming_key(sender));
+    fn run(self) -> Result<TerminalValues<I>, SystemError<I::Error>> {
+        self.run_blocking()
     }
 }
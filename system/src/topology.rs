@@ -0,0 +1,287 @@
+//! Common shapes of channels between the actors of a system.
+//!
+//! Each function adds the channels of a shape between actors which were already added to the
+//! system, identified by their keys. The [`Direction`] decides which way the channels of the
+//! shape go:
+//! ```
+//! # use system::internal::*;
+//! use system::topology::{self, Direction};
+//! use system::{CrossbeamSystem, System};
+//! # #[derive(Debug)]
+//! # pub struct Relay {
+//! #     output_key: Option<usize>,
+//! #     starter: bool,
+//! # }
+//! # impl ActorInternal for Relay {
+//! #     type Message = usize;
+//! #     type Error = SendError<(usize, usize)>;
+//! #     type Key = usize;
+//! #
+//! #     fn new_incoming_key(&mut self, _: &usize) {}
+//! #     fn new_outgoing_key(&mut self, key: &usize) {
+//! #         self.output_key = Some(*key);
+//! #     }
+//! #
+//! #     fn start<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         if self.starter {
+//! #             tx.send(&self.output_key.unwrap(), 0)?;
+//! #         }
+//! #         Ok(NextState::Get)
+//! #     }
+//! #
+//! #     fn process_message<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         message: Option<usize>,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         let value = message.unwrap() + 1;
+//! #         tx.send(&self.output_key.unwrap(), value).ok();
+//! #         Ok(NextState::Terminate(Some(value)))
+//! #     }
+//! # }
+//!
+//! let keys: Vec<usize> = (0..5).collect();
+//!
+//! let mut system = CrossbeamSystem::new();
+//! for &key in &keys {
+//!     system.add_actor(key, Relay { output_key: None, starter: key == 0 }, None);
+//! }
+//! // 0 -> 1 -> 2 -> 3 -> 4 -> 0
+//! topology::ring(&mut system, &keys, Direction::Forward);
+//! system.add_terminal(0);
+//!
+//! let values = system.run().unwrap();
+//! assert_eq!(values[&0], Some(5));
+//! ```
+//!
+//! Every system keeps a [`Graph`] of the actors, channels and terminals added to it, which can be
+//! rendered as a [Graphviz](https://graphviz.org) DOT document using [`System::to_dot`].
+
+use crate::internal::ActorInternal;
+use crate::tokio::sync::ActorType;
+use crate::System;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Write};
+use std::hash::Hash;
+
+/// The key type of the actors of a system.
+type Key<S> = <<S as System>::Internal as ActorInternal>::Key;
+
+/// The direction of the channels added for each edge of a shape.
+///
+/// Every shape orders the two ends of its edges, and the documentation of each shape says which
+/// end comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// A channel from the first end of each edge to the second.
+    Forward,
+    /// A channel from the second end of each edge to the first.
+    Backward,
+    /// Channels in both directions.
+    Both,
+}
+
+/// Add the channels of the edge between `first` and `second`.
+pub fn connect<S: System>(system: &mut S, first: &Key<S>, second: &Key<S>, direction: Direction) {
+    if direction != Direction::Backward {
+        system.add_channel(first, second);
+    }
+    if direction != Direction::Forward {
+        system.add_channel(second, first);
+    }
+}
+
+/// Connect the actors in a ring, each key to the one following it and the last one to the first.
+///
+/// The edge from the last key back to the first is left out when it is not a new edge: a ring of
+/// a single actor has no channels, and a ring of two actors in both directions has one channel
+/// each way.
+pub fn ring<S: System>(system: &mut S, keys: &[Key<S>], direction: Direction) {
+    let n = keys.len();
+    for window in keys.windows(2) {
+        connect(system, &window[0], &window[1], direction);
+    }
+    let closes = match direction {
+        Direction::Both => n > 2,
+        Direction::Forward | Direction::Backward => n > 1,
+    };
+    if closes {
+        connect(system, &keys[n - 1], &keys[0], direction);
+    }
+}
+
+/// Connect every pair of actors, the key appearing first in `keys` being the first end.
+///
+/// With [`Direction::Both`], every actor has a channel to every other actor.
+pub fn complete<S: System>(system: &mut S, keys: &[Key<S>], direction: Direction) {
+    for (i, first) in keys.iter().enumerate() {
+        for second in &keys[i + 1..] {
+            connect(system, first, second, direction);
+        }
+    }
+}
+
+/// Connect the `center` to each of the `leaves`, the center being the first end.
+pub fn star<S: System>(system: &mut S, center: &Key<S>, leaves: &[Key<S>], direction: Direction) {
+    for leaf in leaves {
+        connect(system, center, leaf, direction);
+    }
+}
+
+/// Connect every actor of `left` to every actor of `right`, the left actor being the first end.
+///
+/// For example, the proposers and acceptors of Paxos form a bipartite graph in both directions.
+pub fn bipartite<S: System>(
+    system: &mut S,
+    left: &[Key<S>],
+    right: &[Key<S>],
+    direction: Direction,
+) {
+    for first in left {
+        for second in right {
+            connect(system, first, second, direction);
+        }
+    }
+}
+
+/// Connect each pair of actors with probability `p`, the key appearing first in `keys` being the
+/// first end.
+///
+/// The graph only depends on the number of keys, `p` and the `seed`, so runs can be repeated.
+///
+/// # Panics
+/// Panics if `p` is not between 0 and 1.
+pub fn random<S: System>(
+    system: &mut S,
+    keys: &[Key<S>],
+    p: f64,
+    seed: u64,
+    direction: Direction,
+) {
+    assert!((0.0..=1.0).contains(&p), "the probability of an edge must be between 0 and 1");
+    let mut rng = StdRng::seed_from_u64(seed);
+    for (i, first) in keys.iter().enumerate() {
+        for second in &keys[i + 1..] {
+            if rng.gen_bool(p) {
+                connect(system, first, second, direction);
+            }
+        }
+    }
+}
+
+/// Connect the actors in a grid of rows of `width` keys, filled from `keys` row by row.
+///
+/// Each actor is connected to its right and bottom neighbours, being the first end of both edges.
+/// The last row may be shorter than the others.
+///
+/// # Panics
+/// Panics if `width` is zero.
+pub fn grid<S: System>(system: &mut S, keys: &[Key<S>], width: usize, direction: Direction) {
+    assert!(width > 0, "a grid needs a positive width");
+    for (i, key) in keys.iter().enumerate() {
+        if (i + 1) % width != 0 && i + 1 < keys.len() {
+            connect(system, key, &keys[i + 1], direction);
+        }
+        if i + width < keys.len() {
+            connect(system, key, &keys[i + width], direction);
+        }
+    }
+}
+
+/// The actors, channels and terminals added to a system, in the order they were added.
+#[derive(Debug, Clone)]
+pub struct Graph<K> {
+    actors: Vec<(K, Option<ActorType>)>,
+    index: HashMap<K, usize>,
+    channels: Vec<(K, K)>,
+    terminals: HashSet<K>,
+}
+
+impl<K: Hash + Eq + Clone + Debug> Graph<K> {
+    pub fn new() -> Self {
+        Graph {
+            actors: Vec::new(),
+            index: HashMap::new(),
+            channels: Vec::new(),
+            terminals: HashSet::new(),
+        }
+    }
+
+    /// Add an actor of the given type, if known, replacing the type of an actor with the same key.
+    pub fn add_actor(&mut self, key: K, kind: Option<ActorType>) {
+        match self.index.get(&key) {
+            Some(&i) => self.actors[i].1 = kind,
+            None => {
+                self.index.insert(key.clone(), self.actors.len());
+                self.actors.push((key, kind));
+            }
+        }
+    }
+
+    pub fn add_channel(&mut self, sender: &K, reciever: &K) {
+        self.channels.push((sender.clone(), reciever.clone()));
+    }
+
+    pub fn add_terminal(&mut self, key: K) {
+        self.terminals.insert(key);
+    }
+
+    /// The actors with their type, if known.
+    pub fn actors(&self) -> &[(K, Option<ActorType>)] {
+        &self.actors
+    }
+
+    /// The channels as pairs of sender and reciever.
+    pub fn channels(&self) -> &[(K, K)] {
+        &self.channels
+    }
+
+    pub fn is_terminal(&self, key: &K) -> bool {
+        self.terminals.contains(key)
+    }
+
+    /// Render the graph as a [Graphviz](https://graphviz.org) DOT document.
+    ///
+    /// Actors are identified by the debug representation of their key and labelled with their
+    /// [`ActorType`]. Terminals are drawn as filled double circles.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph system {\n    node [shape=circle];\n");
+        for (key, kind) in &self.actors {
+            let name = escape(&format!("{:?}", key));
+            let label = match kind {
+                Some(kind) => format!("{}\\n{}", name, kind),
+                None => name.clone(),
+            };
+            write!(dot, "    \"{}\" [label=\"{}\"", name, label).unwrap();
+            if self.is_terminal(key) {
+                dot.push_str(", shape=doublecircle, style=filled, fillcolor=lightblue");
+            }
+            dot.push_str("];\n");
+        }
+        for (sender, reciever) in &self.channels {
+            let sender = escape(&format!("{:?}", sender));
+            let reciever = escape(&format!("{:?}", reciever));
+            writeln!(dot, "    \"{}\" -> \"{}\";", sender, reciever).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<K: Hash + Eq + Clone + Debug> Default for Graph<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Escape a string to be quoted in a DOT document.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
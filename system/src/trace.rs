@@ -0,0 +1,730 @@
+//! Recording the events of a run and rendering them as message sequence charts.
+//!
+//! A [`Recorder`] wraps actors into [`Recorded`] actors, which can be run by any system. Every
+//! message sent or received by a recorded actor is logged, together with timeouts and
+//! terminations, in the order they happened. The events can then be rendered as a
+//! [`SequenceChart`], either as text or as a [Mermaid](https://mermaid.js.org) or
+//! [PlantUML](https://plantuml.com) sequence diagram:
+//! ```
+//! # use system::internal::*;
+//! use system::trace::{Recorder, SequenceChart};
+//! use system::{CrossbeamSystem, System};
+//! # #[derive(Debug)]
+//! # pub struct Relay {
+//! #     output_key: Option<usize>,
+//! #     starter: bool,
+//! # }
+//! # impl ActorInternal for Relay {
+//! #     type Message = usize;
+//! #     type Error = SendError<(usize, usize)>;
+//! #     type Key = usize;
+//! #
+//! #     fn new_incoming_key(&mut self, _: &usize) {}
+//! #     fn new_outgoing_key(&mut self, key: &usize) {
+//! #         self.output_key = Some(*key);
+//! #     }
+//! #
+//! #     fn start<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         if self.starter {
+//! #             tx.send(&self.output_key.unwrap(), 0)?;
+//! #         }
+//! #         Ok(NextState::Get)
+//! #     }
+//! #
+//! #     fn process_message<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         message: Option<usize>,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         let value = message.unwrap() + 1;
+//! #         if !self.starter {
+//! #             tx.send(&self.output_key.unwrap(), value)?;
+//! #         }
+//! #         Ok(NextState::Terminate(Some(value)))
+//! #     }
+//! # }
+//!
+//! let recorder = Recorder::new();
+//!
+//! // A cycle 0 -> 1 -> 2 -> 0 passing on a counter.
+//! let mut system = CrossbeamSystem::new();
+//! for key in 0..3 {
+//!     let relay = Relay { output_key: None, starter: key == 0 };
+//!     system.add_actor(key, recorder.wrap(key, relay), None);
+//! }
+//! for key in 0..3 {
+//!     system.add_channel(&key, &((key + 1) % 3));
+//! }
+//! system.add_terminal(0);
+//! system.run().unwrap();
+//!
+//! // The messages sent or received by actor 0.
+//! let events = recorder.events();
+//! let chart = SequenceChart::new(&events).actors([0]);
+//! assert_eq!(
+//!     chart.to_mermaid(),
+//!     "sequenceDiagram
+//!     participant a0 as 0
+//!     participant a1 as 1
+//!     participant a2 as 2
+//!     a0->>a1: 0
+//!     a2->>a0: 2
+//!     Note over a0: terminated with Some(3)
+//! "
+//! );
+//! ```
+//!
+//! Events of actors which run concurrently are ordered by the time they were recorded. A send is
+//! recorded before the message is passed to the system, so it always precedes its delivery.
+//!
+//! # Vector clocks
+//! The order in which events are recorded depends on the scheduling of the run. To see which
+//! events actually happened before others, a [`ClockLog`] wraps actors into [`Clocked`] actors,
+//! which keep a [`VectorClock`](crate::clocks::VectorClock) and piggyback it on every message they send. The clock is merged
+//! when the message is delivered, and every event is logged with the clock of its actor. The log
+//! can be written in the format read by [ShiViz](https://bestchai.bitbucket.io/shiviz/), using
+//! [`SHIVIZ_REGEX`] as the parsing expression.
+//!
+//! The actors wrapped by a clock log are unchanged, but their messages are [`Stamped`] with the
+//! clock of the sender, and so are the termination messages collected by the system. Running the
+//! system with [`ClockLog::run`] instead returns the termination messages of the actors as they
+//! are, with the clocks left in the log.
+
+use crate::clocks::VectorClock;
+use crate::internal::*;
+use crate::{System, TerminalValues};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Debug, Write};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// Something that happened to an actor during a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event<K, M> {
+    /// The position of the event among all the events of the run.
+    pub seq: usize,
+    /// The actor the event happened to.
+    pub actor: K,
+    pub kind: EventKind<K, M>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind<K, M> {
+    /// The actor sent a message, whether or not it reached its destination.
+    Send { to: K, message: M },
+    /// The actor got a message.
+    Receive { message: M },
+    /// The actor waited for a message but got none, as the timeout elapsed or the channel
+    /// disconnected.
+    Timeout,
+    /// The actor terminated with an optional termination message.
+    Terminate { value: Option<M> },
+}
+
+/// A log of the events of the actors it wraps, shared between all of them.
+#[derive(Debug)]
+pub struct Recorder<K, M> {
+    events: Arc<Mutex<Vec<Event<K, M>>>>,
+}
+
+/// An actor whose events are logged by a [`Recorder`].
+///
+/// A recorded actor behaves exactly as the actor it wraps, with the same key, message and error
+/// types, so it can be run by any system.
+#[derive(Debug)]
+pub struct Recorded<I: ActorInternal> {
+    key: I::Key,
+    internal: I,
+    recorder: Recorder<I::Key, I::Message>,
+}
+
+/// A sender logging the messages sent through it.
+#[derive(Debug)]
+pub struct RecordingSender<'a, S: Sender> {
+    key: S::Key,
+    tx: &'a mut S,
+    recorder: &'a Recorder<S::Key, S::Message>,
+}
+
+impl<K, M> Recorder<K, M> {
+    pub fn new() -> Self {
+        Recorder {
+            events: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Wrap the actor with the given key, logging its events in this recorder.
+    pub fn wrap<I>(&self, key: K, internal: I) -> Recorded<I>
+    where
+        I: ActorInternal<Key = K, Message = M>,
+    {
+        Recorded {
+            key,
+            internal,
+            recorder: self.clone(),
+        }
+    }
+
+    fn record(&self, actor: K, kind: EventKind<K, M>) {
+        let mut events = self.events.lock().unwrap();
+        let seq = events.len();
+        events.push(Event { seq, actor, kind });
+    }
+}
+
+impl<K: Clone, M: Clone> Recorder<K, M> {
+    /// The events recorded so far, in the order they happened.
+    pub fn events(&self) -> Vec<Event<K, M>> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl<K, M> Clone for Recorder<K, M> {
+    fn clone(&self) -> Self {
+        Recorder {
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl<K, M> Default for Recorder<K, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: ActorInternal> Recorded<I> {
+    /// Log the next state of the actor if it is a termination.
+    fn record_next(&self, next: &NextState<I::Message>) {
+        if let NextState::Terminate(value) = next {
+            let kind = EventKind::Terminate {
+                value: value.clone(),
+            };
+            self.recorder.record(self.key.clone(), kind);
+        }
+    }
+}
+
+impl<'a, S: Sender> Sender for RecordingSender<'a, S> {
+    type Key = S::Key;
+    type Message = S::Message;
+
+    fn send(&mut self, key: &S::Key, message: S::Message) -> SendResult<S::Key, S::Message> {
+        let kind = EventKind::Send {
+            to: key.clone(),
+            message: message.clone(),
+        };
+        self.recorder.record(self.key.clone(), kind);
+        self.tx.send(key, message)
+    }
+}
+
+impl<I: ActorInternal> ActorInternal for Recorded<I> {
+    type Message = I::Message;
+    type Key = I::Key;
+    type Error = I::Error;
+
+    fn new_incoming_key(&mut self, key: &I::Key) {
+        self.internal.new_incoming_key(key)
+    }
+
+    fn new_outgoing_key(&mut self, key: &I::Key) {
+        self.internal.new_outgoing_key(key)
+    }
+
+    fn start<S: Sender<Key = I::Key, Message = I::Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<I::Message>, I::Error> {
+        let mut tx = RecordingSender {
+            key: self.key.clone(),
+            tx,
+            recorder: &self.recorder,
+        };
+        let next = self.internal.start(&mut tx)?;
+        self.record_next(&next);
+        Ok(next)
+    }
+
+    fn process_message<S: Sender<Key = I::Key, Message = I::Message>>(
+        &mut self,
+        message: Option<I::Message>,
+        tx: &mut S,
+    ) -> Result<NextState<I::Message>, I::Error> {
+        let kind = match &message {
+            Some(message) => EventKind::Receive {
+                message: message.clone(),
+            },
+            None => EventKind::Timeout,
+        };
+        self.recorder.record(self.key.clone(), kind);
+
+        let mut tx = RecordingSender {
+            key: self.key.clone(),
+            tx,
+            recorder: &self.recorder,
+        };
+        let next = self.internal.process_message(message, &mut tx)?;
+        self.record_next(&next);
+        Ok(next)
+    }
+}
+
+/// The name of the variant of a message, the start of its debug representation.
+///
+/// For a message `Proposal(3, "value")` this is `Proposal`.
+pub fn variant_name<M: Debug>(message: &M) -> String {
+    let repr = format!("{:?}", message);
+    let end = repr.find(['(', '{', ' ']).unwrap_or(repr.len());
+    repr[..end].to_string()
+}
+
+/// A message sequence chart of a run, showing who sent what to whom and in which order.
+///
+/// The chart shows the messages sent and the terminations of actors. Receipts and timeouts are
+/// left out, as the arrow of a message already shows it was sent to the reciever. The chart can
+/// be restricted to some of the actors and some variants or kinds of messages.
+#[derive(Debug, Clone)]
+pub struct SequenceChart<'a, K, M> {
+    events: &'a [Event<K, M>],
+    actors: Option<HashSet<K>>,
+    variants: Option<HashSet<String>>,
+    /// The label of a message matched against the selected variants.
+    label: fn(&M) -> String,
+}
+
+impl<'a, K, M> SequenceChart<'a, K, M>
+where
+    K: Hash + Eq + Clone + Debug,
+    M: Debug,
+{
+    pub fn new(events: &'a [Event<K, M>]) -> Self {
+        SequenceChart {
+            events,
+            actors: None,
+            variants: None,
+            label: variant_name,
+        }
+    }
+
+    /// Only show messages sent or received by one of the given actors, and their terminations.
+    pub fn actors<T: IntoIterator<Item = K>>(mut self, actors: T) -> Self {
+        self.actors = Some(actors.into_iter().collect());
+        self
+    }
+
+    /// Only show messages of the given variants, see [`variant_name`].
+    pub fn variants<T, S>(mut self, variants: T) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.variants = Some(variants.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Only show messages of the given kinds, see [`Classify`], instead of selecting variants.
+    pub fn kinds<T, S>(mut self, kinds: T) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        S: Into<String>,
+        M: Classify,
+    {
+        self.label = |message| message.kind().to_string();
+        self.variants(kinds)
+    }
+
+    /// The events shown in the chart.
+    fn shown(&self) -> impl Iterator<Item = &'a Event<K, M>> + '_ {
+        let selected = move |key: &K| self.actors.as_ref().is_none_or(|a| a.contains(key));
+        self.events.iter().filter(move |event| match &event.kind {
+            EventKind::Send { to, message } => {
+                (selected(&event.actor) || selected(to))
+                    && self
+                        .variants
+                        .as_ref()
+                        .is_none_or(|v| v.contains(&(self.label)(message)))
+            }
+            EventKind::Terminate { .. } => selected(&event.actor),
+            EventKind::Receive { .. } | EventKind::Timeout => false,
+        })
+    }
+
+    /// The actors appearing in the chart, in the order of their first appearance, and their
+    /// position in that order.
+    fn participants(&self) -> (Vec<&'a K>, HashMap<&'a K, usize>) {
+        let mut order = Vec::new();
+        let mut index = HashMap::new();
+        let mut add = |key: &'a K| {
+            if !index.contains_key(key) {
+                index.insert(key, order.len());
+                order.push(key);
+            }
+        };
+        for event in self.shown() {
+            add(&event.actor);
+            if let EventKind::Send { to, .. } = &event.kind {
+                add(to);
+            }
+        }
+        (order, index)
+    }
+
+    /// Render the chart as text, one line per event.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for event in self.shown() {
+            match &event.kind {
+                EventKind::Send { to, message } => writeln!(
+                    text,
+                    "{:>6}  {:?} -> {:?}: {:?}",
+                    event.seq, event.actor, to, message
+                ),
+                EventKind::Terminate { value } => writeln!(
+                    text,
+                    "{:>6}  {:?} terminated with {:?}",
+                    event.seq, event.actor, value
+                ),
+                _ => Ok(()),
+            }
+            .unwrap();
+        }
+        text
+    }
+
+    /// Render the chart as a [Mermaid](https://mermaid.js.org) sequence diagram.
+    pub fn to_mermaid(&self) -> String {
+        // Characters which end or break a Mermaid statement are replaced by HTML entities
+        let escape = |s: String| s.replace(';', "#59;").replace('\n', " ");
+
+        let (participants, index) = self.participants();
+        let mut text = String::from("sequenceDiagram\n");
+        for (i, key) in participants.iter().enumerate() {
+            writeln!(
+                text,
+                "    participant a{} as {}",
+                i,
+                escape(format!("{:?}", key))
+            )
+            .unwrap();
+        }
+        for event in self.shown() {
+            let from = index[&event.actor];
+            match &event.kind {
+                EventKind::Send { to, message } => writeln!(
+                    text,
+                    "    a{}->>a{}: {}",
+                    from,
+                    index[to],
+                    escape(format!("{:?}", message))
+                ),
+                EventKind::Terminate { value } => writeln!(
+                    text,
+                    "    Note over a{}: terminated with {}",
+                    from,
+                    escape(format!("{:?}", value))
+                ),
+                _ => Ok(()),
+            }
+            .unwrap();
+        }
+        text
+    }
+
+    /// Render the chart as a [PlantUML](https://plantuml.com) sequence diagram.
+    pub fn to_plantuml(&self) -> String {
+        let escape = |s: String| s.replace('"', "'").replace('\n', " ");
+
+        let (participants, index) = self.participants();
+        let mut text = String::from("@startuml\n");
+        for (i, key) in participants.iter().enumerate() {
+            writeln!(
+                text,
+                "participant \"{}\" as a{}",
+                escape(format!("{:?}", key)),
+                i
+            )
+            .unwrap();
+        }
+        for event in self.shown() {
+            let from = index[&event.actor];
+            match &event.kind {
+                EventKind::Send { to, message } => writeln!(
+                    text,
+                    "a{} -> a{} : {}",
+                    from,
+                    index[to],
+                    escape(format!("{:?}", message))
+                ),
+                EventKind::Terminate { value } => writeln!(
+                    text,
+                    "note over a{} : terminated with {}",
+                    from,
+                    escape(format!("{:?}", value))
+                ),
+                _ => Ok(()),
+            }
+            .unwrap();
+        }
+        text.push_str("@enduml\n");
+        text
+    }
+}
+
+/// A message carrying the vector clock of its sender at the time it was sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stamped<K: Hash + Eq, M> {
+    pub from: K,
+    pub clock: VectorClock<K>,
+    pub message: M,
+}
+
+/// An event of an actor with the clock of the actor after the event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockEntry<K: Hash + Eq> {
+    pub actor: K,
+    pub clock: VectorClock<K>,
+    pub description: String,
+}
+
+/// The regular expression parsing the log written by [`ClockLog::to_shiviz`] in ShiViz.
+pub const SHIVIZ_REGEX: &str = r"(?<host>\S*) (?<clock>{.*})\n(?<event>.*)";
+
+/// A log of the events of the actors it wraps with their vector clocks, shared between all of
+/// them.
+#[derive(Debug)]
+pub struct ClockLog<K: Hash + Eq> {
+    entries: Arc<Mutex<Vec<ClockEntry<K>>>>,
+}
+
+/// An actor keeping a vector clock, logging its events in a [`ClockLog`].
+///
+/// The actor behaves as the actor it wraps, with the same key and error types, but its messages
+/// are [`Stamped`] with the clock of their sender.
+#[derive(Debug)]
+pub struct Clocked<I: ActorInternal> {
+    key: I::Key,
+    internal: I,
+    clock: VectorClock<I::Key>,
+    log: ClockLog<I::Key>,
+}
+
+/// The error of a [`Clocked`] actor, which is the error of the actor it wraps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockedError<E>(pub E);
+
+/// A sender stamping the messages sent through it with the clock of the sending actor.
+#[derive(Debug)]
+pub struct ClockedSender<'a, S: Sender, M> {
+    key: S::Key,
+    clock: &'a mut VectorClock<S::Key>,
+    tx: &'a mut S,
+    log: &'a ClockLog<S::Key>,
+    message: PhantomData<fn() -> M>,
+}
+
+impl<K: Hash + Eq + Clone> ClockLog<K> {
+    pub fn new() -> Self {
+        ClockLog {
+            entries: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Wrap the actor with the given key, logging its events in this log.
+    pub fn wrap<I: ActorInternal<Key = K>>(&self, key: K, internal: I) -> Clocked<I> {
+        Clocked {
+            key,
+            internal,
+            clock: VectorClock::new(),
+            log: self.clone(),
+        }
+    }
+
+    /// Count a new event of the actor in its clock and log it.
+    fn record(&self, actor: &K, clock: &mut VectorClock<K>, description: String) {
+        clock.tick(actor);
+        self.entries.lock().unwrap().push(ClockEntry {
+            actor: actor.clone(),
+            clock: clock.clone(),
+            description,
+        });
+    }
+
+    /// The events logged so far, in the order they were logged.
+    pub fn entries(&self) -> Vec<ClockEntry<K>> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Run the system, returning the termination messages of the terminals without their
+    /// stamps.
+    pub fn run<S, I>(&self, system: S) -> Result<TerminalValues<I>, S::Error>
+    where
+        S: System<Internal = Clocked<I>>,
+        I: ActorInternal<Key = K>,
+        K: Debug + Send,
+    {
+        let values = system.run()?;
+        Ok(values
+            .into_iter()
+            .map(|(key, value)| (key, value.map(|stamped| stamped.message)))
+            .collect())
+    }
+}
+
+impl<K: Hash + Eq + Clone + Debug> ClockLog<K> {
+    /// Write the log in the format read by ShiViz, parsed by [`SHIVIZ_REGEX`].
+    ///
+    /// Each event takes two lines, the first with the actor and its clock as a JSON object, the
+    /// second describing the event. Actors are named by the debug representation of their key,
+    /// with white space replaced by underscores.
+    pub fn to_shiviz(&self) -> String {
+        let host = |key: &K| format!("{:?}", key).replace(char::is_whitespace, "_");
+
+        let mut text = String::new();
+        for entry in self.entries.lock().unwrap().iter() {
+            let clock: BTreeMap<String, u64> = entry
+                .clock
+                .iter()
+                .map(|(key, count)| (host(key), count))
+                .collect();
+            let clock = serde_json::to_string(&clock).unwrap();
+            let description = entry.description.replace('\n', " ");
+            writeln!(text, "{} {}\n{}", host(&entry.actor), clock, description).unwrap();
+        }
+        text
+    }
+}
+
+impl<K: Hash + Eq> Clone for ClockLog<K> {
+    fn clone(&self) -> Self {
+        ClockLog {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for ClockLog<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: ActorInternal> Clocked<I> {
+    /// Stamp the termination message of the actor, if it terminates, and log the termination.
+    fn stamp_next(
+        &mut self,
+        next: NextState<I::Message>,
+    ) -> NextState<Stamped<I::Key, I::Message>> {
+        match next {
+            NextState::Get => NextState::Get,
+            NextState::GetTimeout(duration) => NextState::GetTimeout(duration),
+            NextState::Terminate(value) => {
+                let description = format!("terminate with {:?}", value);
+                self.log.record(&self.key, &mut self.clock, description);
+                NextState::Terminate(value.map(|message| Stamped {
+                    from: self.key.clone(),
+                    clock: self.clock.clone(),
+                    message,
+                }))
+            }
+        }
+    }
+}
+
+impl<'a, S, M> Sender for ClockedSender<'a, S, M>
+where
+    S: Sender<Message = Stamped<<S as Sender>::Key, M>>,
+    M: Send + Clone + Debug + 'static,
+{
+    type Key = S::Key;
+    type Message = M;
+
+    fn send(&mut self, key: &S::Key, message: M) -> SendResult<S::Key, M> {
+        let description = format!("send {:?} to {:?}", message, key);
+        self.log.record(&self.key, self.clock, description);
+        let stamped = Stamped {
+            from: self.key.clone(),
+            clock: self.clock.clone(),
+            message,
+        };
+        self.tx
+            .send(key, stamped)
+            .map_err(|err| err.map(|(key, stamped)| (key, stamped.message)))
+    }
+}
+
+impl<K, M, E> From<SendError<(K, Stamped<K, M>)>> for ClockedError<E>
+where
+    K: Hash + Eq,
+    E: From<SendError<(K, M)>>,
+{
+    fn from(err: SendError<(K, Stamped<K, M>)>) -> Self {
+        ClockedError(err.map(|(key, stamped)| (key, stamped.message)).into())
+    }
+}
+
+impl<I: ActorInternal> ActorInternal for Clocked<I> {
+    type Message = Stamped<I::Key, I::Message>;
+    type Key = I::Key;
+    type Error = ClockedError<I::Error>;
+
+    fn new_incoming_key(&mut self, key: &I::Key) {
+        self.internal.new_incoming_key(key)
+    }
+
+    fn new_outgoing_key(&mut self, key: &I::Key) {
+        self.internal.new_outgoing_key(key)
+    }
+
+    fn start<S: Sender<Key = I::Key, Message = Self::Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        let mut tx = ClockedSender {
+            key: self.key.clone(),
+            clock: &mut self.clock,
+            tx,
+            log: &self.log,
+            message: PhantomData,
+        };
+        let next = self.internal.start(&mut tx).map_err(ClockedError)?;
+        Ok(self.stamp_next(next))
+    }
+
+    fn process_message<S: Sender<Key = I::Key, Message = Self::Message>>(
+        &mut self,
+        message: Option<Self::Message>,
+        tx: &mut S,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        let message = match message {
+            Some(stamped) => {
+                self.clock.merge(&stamped.clock);
+                let description = format!("receive {:?} from {:?}", stamped.message, stamped.from);
+                self.log.record(&self.key, &mut self.clock, description);
+                Some(stamped.message)
+            }
+            None => {
+                let description = String::from("timeout");
+                self.log.record(&self.key, &mut self.clock, description);
+                None
+            }
+        };
+
+        let mut tx = ClockedSender {
+            key: self.key.clone(),
+            clock: &mut self.clock,
+            tx,
+            log: &self.log,
+            message: PhantomData,
+        };
+        let next = self
+            .internal
+            .process_message(message, &mut tx)
+            .map_err(ClockedError)?;
+        Ok(self.stamp_next(next))
+    }
+}
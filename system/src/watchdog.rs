@@ -0,0 +1,647 @@
+//! Detecting runs which can never finish, and ending runs before all their terminals are done.
+//!
+//! A run is stuck once every actor which did not terminate waits for a message with
+//! [`NextState::Get`] and no message is on its way. Systems can not tell this apart from a
+//! slow run, so they keep waiting for their terminals forever.
+//!
+//! A [`Watchdog`] wraps actors into [`Watched`] actors, which can be run by any system. It counts
+//! the actors which are busy, or waiting with a timeout, together with the messages sent and not
+//! yet processed. When this count drops to zero before all the terminals are done,
+//! [`Watchdog::run`] returns a [`Deadlock`] naming the blocked actors and the unfinished
+//! terminals:
+//! ```
+//! # use system::internal::*;
+//! use system::watchdog::{Watchdog, WatchdogError};
+//! use system::CrossbeamSystem;
+//! # #[derive(Debug)]
+//! # pub struct Relay {
+//! #     output_key: Option<usize>,
+//! #     starter: bool,
+//! # }
+//! # impl ActorInternal for Relay {
+//! #     type Message = usize;
+//! #     type Error = SendError<(usize, usize)>;
+//! #     type Key = usize;
+//! #
+//! #     fn new_incoming_key(&mut self, _: &usize) {}
+//! #     fn new_outgoing_key(&mut self, key: &usize) {
+//! #         self.output_key = Some(*key);
+//! #     }
+//! #
+//! #     fn start<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         if self.starter {
+//! #             tx.send(&self.output_key.unwrap(), 0)?;
+//! #         }
+//! #         Ok(NextState::Get)
+//! #     }
+//! #
+//! #     fn process_message<S: Sender<Key = usize, Message = usize>>(
+//! #         &mut self,
+//! #         message: Option<usize>,
+//! #         tx: &mut S,
+//! #     ) -> Result<NextState<usize>, Self::Error> {
+//! #         let value = message.unwrap() + 1;
+//! #         if !self.starter {
+//! #             tx.send(&self.output_key.unwrap(), value)?;
+//! #         }
+//! #         Ok(NextState::Terminate(Some(value)))
+//! #     }
+//! # }
+//! use system::System;
+//!
+//! let watchdog = Watchdog::new();
+//!
+//! // A cycle 0 -> 1 -> 2 -> 0 in which no actor sends the first message.
+//! let mut system = CrossbeamSystem::new();
+//! for key in 0..3 {
+//!     let relay = Relay { output_key: None, starter: false };
+//!     system.add_actor(key, watchdog.wrap(key, relay), None);
+//! }
+//! for key in 0..3 {
+//!     system.add_channel(&key, &((key + 1) % 3));
+//! }
+//! system.add_terminal(0);
+//!
+//! match watchdog.run(system) {
+//!     Err(WatchdogError::Deadlock(deadlock)) => {
+//!         assert_eq!(deadlock.blocked, vec![0, 1, 2]);
+//!         assert_eq!(deadlock.unfinished, vec![0]);
+//!     }
+//!     _ => panic!("the cycle never starts"),
+//! }
+//! ```
+//!
+//! Actors waiting with [`NextState::GetTimeout`] are never blocked, as the timeout wakes them
+//! up, so runs in which actors keep timing out are not detected. Neither are runs in which
+//! messages are dropped by a full channel, as these messages are counted until processed.
+//!
+//! Runs which keep going without finishing, such as a livelock of actors retrying after
+//! timeouts, are ended by a [`Deadline`] given with [`Watchdog::deadline`]. Once it passes, the
+//! watched actors are stopped and [`Watchdog::run`] returns [`Expired`], holding the termination
+//! messages of the terminals done by then and the actors which did not terminate. The deadline
+//! is either in wall-clock time or on the clock of the actors, which is virtual when the time of
+//! a tokio runtime is paused.
+//!
+//! A run need not wait for all of its terminals either. With [`Watchdog::quorum`] it ends once
+//! any `k` of them are done, and with [`Watchdog::until`] once the termination messages of the
+//! terminals done so far satisfy a predicate, for example once a majority of them agree. The
+//! remaining actors are then stopped. With [`Watchdog::first_error`], the run is stopped at the
+//! first error of an actor, terminal or not, which [`Watchdog::run`] returns.
+//!
+//! Whenever a run ends early, the system is stopped by its [`Shutdown`] signal, and
+//! [`Watchdog::run`] returns once its actors are dropped. **Note**: a system without a signal,
+//! whose [`System::shutdown`] is `None`, can not be stopped. It keeps running on a separate
+//! thread, with its blocked actors waiting forever, until the program exits.
+//!
+//! [`Shutdown`]: crate::shutdown::Shutdown
+
+use crate::internal::*;
+use crate::{System, TerminalValues};
+use crossbeam_channel as cb;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// A collector of the activity of actors, telling when none of them can make progress.
+#[derive(Debug)]
+pub struct Watchdog<I: ActorInternal> {
+    state: Arc<Mutex<State<I>>>,
+    // Wakes up the run when the state may need a look: no actor is active or the run is stopped.
+    wake_up: cb::Sender<()>,
+    wake_up_rx: cb::Receiver<()>,
+}
+
+#[derive(Debug)]
+struct State<I: ActorInternal> {
+    actors: HashMap<I::Key, Activity<I::Message>>,
+    // The number of active actors plus the number of messages sent and not yet processed.
+    pending: usize,
+    deadline: Option<Deadline>,
+    // The time the first actor started, on the clock of the actors.
+    started: Option<tokio::time::Instant>,
+    condition: Option<Condition<I>>,
+    first_error: bool,
+    stop: Option<Stop>,
+    failure: Option<(I::Key, I::Error)>,
+}
+
+#[derive(Debug, Clone)]
+struct Activity<M> {
+    status: Status,
+    terminal: bool,
+    // The number of messages sent to the actor and not yet processed.
+    queued: usize,
+    value: Option<M>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Active,
+    Blocked,
+    Terminated,
+}
+
+/// The reason a run was stopped before all of its terminals were done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    Deadline,
+    Condition,
+    Failure,
+}
+
+/// A condition on the termination messages of the terminals done so far.
+struct Condition<I: ActorInternal>(Box<Predicate<I>>);
+
+type Predicate<I> = dyn Fn(&TerminalValues<I>) -> bool + Send;
+
+/// The time after which a run is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deadline {
+    /// A duration of wall-clock time, from the call to [`Watchdog::run`].
+    Wall(Duration),
+    /// A duration of time on the tokio clock of the actors, from the start of the first actor.
+    ///
+    /// In a runtime whose time is paused, as with [`RuntimeSettings::paused`] under the
+    /// `paused-time` feature, this is virtual time. Outside of a tokio runtime, the clock of the
+    /// actors is the wall clock.
+    ///
+    /// [`RuntimeSettings::paused`]: crate::tokio::sync::RuntimeSettings
+    Virtual(Duration),
+}
+
+/// The diagnostic of a run which can never finish.
+///
+/// Actors are listed in the order they were added to the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deadlock<K> {
+    /// The actors waiting for a message which will never come.
+    pub blocked: Vec<K>,
+    /// The terminals which did not terminate.
+    pub unfinished: Vec<K>,
+}
+
+/// The partial results of a run stopped at its deadline.
+#[derive(Debug, Clone)]
+pub struct Expired<K, M> {
+    /// The termination messages of the terminals which terminated before the deadline.
+    pub values: HashMap<K, Option<M>>,
+    /// The actors which did not terminate before the deadline, terminals or not, in the order
+    /// they were added to the system.
+    pub running: Vec<K>,
+}
+
+/// The error returned by [`Watchdog::run`] for a system of watched actors of type `I`.
+#[derive(Debug)]
+pub enum WatchdogError<E, I: ActorInternal> {
+    /// The error returned by the system.
+    System(E),
+    /// The run can never finish.
+    Deadlock(Deadlock<I::Key>),
+    /// The run did not finish before the deadline.
+    Expired(Expired<I::Key, I::Message>),
+    /// An actor failed, and the run was stopped at its error, see [`Watchdog::first_error`].
+    Failed(I::Key, I::Error),
+}
+
+/// An actor whose activity is followed by a [`Watchdog`].
+///
+/// The actor behaves exactly as the actor it wraps, with the same message, key and error types,
+/// until the run is stopped. It then terminates, without a termination message, the next time
+/// it starts or gets a message or a timeout.
+#[derive(Debug)]
+pub struct Watched<I: ActorInternal> {
+    key: I::Key,
+    internal: I,
+    watchdog: Watchdog<I>,
+}
+
+/// A sender counting the messages sent through it.
+#[derive(Debug)]
+pub struct WatchedSender<'a, S, I: ActorInternal> {
+    tx: &'a mut S,
+    watchdog: &'a Watchdog<I>,
+}
+
+impl<I: ActorInternal> Watchdog<I> {
+    pub fn new() -> Self {
+        let (wake_up, wake_up_rx) = cb::unbounded();
+        Watchdog {
+            state: Arc::new(Mutex::new(State {
+                actors: HashMap::new(),
+                pending: 0,
+                deadline: None,
+                started: None,
+                condition: None,
+                first_error: false,
+                stop: None,
+                failure: None,
+            })),
+            wake_up,
+            wake_up_rx,
+        }
+    }
+
+    /// Stop the run at the given deadline.
+    pub fn deadline(self, deadline: Deadline) -> Self {
+        self.state.lock().unwrap().deadline = Some(deadline);
+        self
+    }
+
+    /// Stop the run once any `k` of the terminals are done, as with [`Watchdog::until`].
+    pub fn quorum(self, k: usize) -> Self {
+        self.until(move |values| values.len() >= k)
+    }
+
+    /// Stop the run once the termination messages of the terminals done so far satisfy the
+    /// predicate, which is checked each time a terminal terminates.
+    ///
+    /// Once it holds, the actors still running are stopped together with the system, see
+    /// [`Watchdog::run`].
+    pub fn until<F>(self, predicate: F) -> Self
+    where
+        F: Fn(&TerminalValues<I>) -> bool + Send + 'static,
+    {
+        self.state.lock().unwrap().condition = Some(Condition(Box::new(predicate)));
+        self
+    }
+
+    /// Stop the run at the first error of an actor, terminal or not.
+    ///
+    /// The failing actor terminates without a termination message, and [`Watchdog::run`]
+    /// returns its error.
+    pub fn first_error(self) -> Self {
+        self.state.lock().unwrap().first_error = true;
+        self
+    }
+
+    /// Wrap the actor with the given key, following its activity in this watchdog.
+    ///
+    /// A watchdog follows a single run, all the actors of the system should be wrapped by it.
+    pub fn wrap(&self, key: I::Key, internal: I) -> Watched<I> {
+        let mut state = self.state.lock().unwrap();
+        let activity = Activity {
+            status: Status::Active,
+            terminal: false,
+            queued: 0,
+            value: None,
+        };
+        if state.actors.insert(key.clone(), activity).is_none() {
+            state.pending += 1;
+        }
+        Watched {
+            key,
+            internal,
+            watchdog: self.clone(),
+        }
+    }
+
+    fn update<T, F: FnOnce(&mut State<I>) -> T>(&self, f: F) -> T {
+        let mut state = self.state.lock().unwrap();
+        let value = f(&mut state);
+        if state.pending == 0 || state.stop.is_some() {
+            let _ = self.wake_up.send(());
+        }
+        value
+    }
+
+    /// Whether the run is stopped, stopping it if the virtual deadline has passed.
+    fn is_stopped(&self) -> bool {
+        self.update(|state| {
+            let now = tokio::time::Instant::now();
+            let started = *state.started.get_or_insert(now);
+            if let Some(Deadline::Virtual(deadline)) = state.deadline {
+                if now.duration_since(started) >= deadline {
+                    state.stop.get_or_insert(Stop::Deadline);
+                }
+            }
+            state.stop.is_some()
+        })
+    }
+
+    /// Count a message about to be sent, returning whether it was counted.
+    fn record_send(&self, to: &I::Key) -> bool {
+        self.update(|state| match state.actors.get_mut(to) {
+            Some(activity) if activity.status != Status::Terminated => {
+                activity.queued += 1;
+                state.pending += 1;
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Forget a message which could not be sent.
+    fn record_failed(&self, to: &I::Key) {
+        self.update(|state| {
+            if let Some(activity) = state.actors.get_mut(to) {
+                if activity.queued > 0 {
+                    activity.queued -= 1;
+                    state.pending -= 1;
+                }
+            }
+        })
+    }
+
+    /// Wake the actor up, processing a message if it got one.
+    fn record_wake_up(&self, key: &I::Key, message: bool) {
+        self.update(|state| {
+            let activity = state.actors.get_mut(key).unwrap();
+            if activity.status == Status::Blocked {
+                activity.status = Status::Active;
+                state.pending += 1;
+            }
+            if message && activity.queued > 0 {
+                activity.queued -= 1;
+                state.pending -= 1;
+            }
+        })
+    }
+
+    /// Follow the actor into its next state, returning the error of a failed actor unless the
+    /// run is stopped by it.
+    fn record_next(
+        &self,
+        key: &I::Key,
+        next: Result<NextState<I::Message>, I::Error>,
+    ) -> Result<NextState<I::Message>, I::Error> {
+        self.update(|state| {
+            let activity = state.actors.get_mut(key).unwrap();
+            let next = match next {
+                Ok(NextState::GetTimeout(timeout)) => return Ok(NextState::GetTimeout(timeout)),
+                Ok(NextState::Get) => {
+                    activity.status = Status::Blocked;
+                    state.pending -= 1;
+                    return Ok(NextState::Get);
+                }
+                Ok(NextState::Terminate(value)) => {
+                    activity.value = value.clone();
+                    Ok(NextState::Terminate(value))
+                }
+                Err(err) if state.first_error && state.stop.is_none() => {
+                    state.stop = Some(Stop::Failure);
+                    state.failure = Some((key.clone(), err));
+                    Ok(NextState::Terminate(None))
+                }
+                Err(err) => Err(err),
+            };
+
+            activity.status = Status::Terminated;
+            state.pending -= 1 + activity.queued;
+            activity.queued = 0;
+            let terminal = activity.terminal && next.is_ok();
+            if terminal && state.stop.is_none() {
+                if let Some(Condition(predicate)) = &state.condition {
+                    if predicate(&state.values()) {
+                        state.stop = Some(Stop::Condition);
+                    }
+                }
+            }
+            next
+        })
+    }
+
+    /// Run the system, returning the termination messages of the terminals.
+    ///
+    /// The run ends early with a [`Deadlock`] as soon as it can no longer make progress, or
+    /// with the partial results of the run once the deadline, if any, has passed. If a
+    /// condition is given with [`Watchdog::quorum`] or [`Watchdog::until`], the run ends as
+    /// soon as it holds, returning the termination messages of the terminals done so far. The
+    /// run ends in any case once all the terminals are done.
+    ///
+    /// The system is run on a separate thread. When the run ends early, the system is stopped
+    /// by its [`System::shutdown`] signal and the thread is joined, unless the system has no
+    /// signal, in which case it is left running.
+    pub fn run<S>(&self, system: S) -> Result<TerminalValues<I>, WatchdogError<S::Error, I>>
+    where
+        S: System<Internal = Watched<I>> + Send + 'static,
+        S::Error: Send,
+    {
+        let graph = system.graph();
+        let actors: Vec<I::Key> = graph.actors().iter().map(|(key, _)| key.clone()).collect();
+        let terminals: Vec<I::Key> = actors
+            .iter()
+            .filter(|key| graph.is_terminal(key))
+            .cloned()
+            .collect();
+
+        let timer = {
+            let mut state = self.state.lock().unwrap();
+            for key in terminals.iter() {
+                if let Some(activity) = state.actors.get_mut(key) {
+                    activity.terminal = true;
+                }
+            }
+            match state.deadline {
+                Some(Deadline::Wall(deadline)) => cb::after(deadline),
+                _ => cb::never(),
+            }
+        };
+
+        let shutdown = system.shutdown();
+        let (tx, rx) = cb::bounded(1);
+        let handle = thread::spawn(move || {
+            let _ = tx.send(system.run());
+        });
+
+        let outcome = loop {
+            cb::select! {
+                recv(rx) -> result => {
+                    let mut state = self.state.lock().unwrap();
+                    if state.stop.is_some() {
+                        // Stopped actors terminate without a message, the values are not theirs.
+                        break state.stopped(&actors);
+                    }
+                    let values = result
+                        .expect("the system thread panicked")
+                        .map_err(WatchdogError::System);
+                    break values;
+                }
+                recv(self.wake_up_rx) -> _ => {
+                    let mut state = self.state.lock().unwrap();
+                    if state.stop.is_some() {
+                        break state.stopped(&actors);
+                    }
+                    // Once no actor is active, the state of the actors can no longer change.
+                    if state.pending > 0 {
+                        continue;
+                    }
+                    let status = |key: &I::Key| state.actors.get(key).map(|a| a.status);
+                    let unfinished: Vec<I::Key> = terminals
+                        .iter()
+                        .filter(|key| status(key) != Some(Status::Terminated))
+                        .cloned()
+                        .collect();
+                    if unfinished.is_empty() {
+                        // The terminals are done, the system is about to return.
+                        continue;
+                    }
+                    break Err(WatchdogError::Deadlock(Deadlock {
+                        blocked: actors
+                            .iter()
+                            .filter(|key| status(key) == Some(Status::Blocked))
+                            .cloned()
+                            .collect(),
+                        unfinished,
+                    }));
+                }
+                recv(timer) -> _ => {
+                    let mut state = self.state.lock().unwrap();
+                    state.stop.get_or_insert(Stop::Deadline);
+                    break state.stopped(&actors);
+                }
+            }
+        };
+
+        // Stop whatever is left of the run, the actors need the state to finish their steps.
+        if let Some(shutdown) = shutdown {
+            shutdown.trigger();
+            handle.join().ok();
+        }
+        outcome
+    }
+}
+
+impl<I: ActorInternal> State<I> {
+    /// The termination messages of the terminals done so far.
+    fn values(&self) -> TerminalValues<I> {
+        self.actors
+            .iter()
+            .filter(|(_, activity)| activity.terminal && activity.status == Status::Terminated)
+            .map(|(key, activity)| (key.clone(), activity.value.clone()))
+            .collect()
+    }
+
+    /// The outcome of a run stopped by its condition, its deadline or the failure of an actor.
+    fn stopped<E>(&mut self, actors: &[I::Key]) -> Result<TerminalValues<I>, WatchdogError<E, I>> {
+        match self.stop {
+            Some(Stop::Condition) => Ok(self.values()),
+            Some(Stop::Failure) => {
+                let (key, err) = self.failure.take().unwrap();
+                Err(WatchdogError::Failed(key, err))
+            }
+            _ => Err(WatchdogError::Expired(Expired {
+                values: self.values(),
+                running: actors
+                    .iter()
+                    .filter(|key| {
+                        self.actors
+                            .get(*key)
+                            .is_some_and(|activity| activity.status != Status::Terminated)
+                    })
+                    .cloned()
+                    .collect(),
+            })),
+        }
+    }
+}
+
+impl<I: ActorInternal> Clone for Watchdog<I> {
+    fn clone(&self) -> Self {
+        Watchdog {
+            state: self.state.clone(),
+            wake_up: self.wake_up.clone(),
+            wake_up_rx: self.wake_up_rx.clone(),
+        }
+    }
+}
+
+impl<I: ActorInternal> Default for Watchdog<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: ActorInternal> Debug for Condition<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Condition")
+    }
+}
+
+impl<K: Debug> Display for Deadlock<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deadlock: actors {:?} wait for messages that never come, terminals {:?} never finish",
+            self.blocked, self.unfinished
+        )
+    }
+}
+
+impl<K: Debug, M> Display for Expired<K, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deadline expired: actors {:?} did not terminate",
+            self.running
+        )
+    }
+}
+
+impl<'a, S, I> Sender for WatchedSender<'a, S, I>
+where
+    S: Sender<Key = I::Key, Message = I::Message>,
+    I: ActorInternal,
+{
+    type Key = I::Key;
+    type Message = I::Message;
+
+    fn send(&mut self, key: &I::Key, message: I::Message) -> SendResult<I::Key, I::Message> {
+        let counted = self.watchdog.record_send(key);
+        let result = self.tx.send(key, message);
+        if counted && result.is_err() {
+            self.watchdog.record_failed(key);
+        }
+        result
+    }
+}
+
+impl<I: ActorInternal> ActorInternal for Watched<I> {
+    type Message = I::Message;
+    type Key = I::Key;
+    type Error = I::Error;
+
+    fn new_incoming_key(&mut self, key: &I::Key) {
+        self.internal.new_incoming_key(key)
+    }
+
+    fn new_outgoing_key(&mut self, key: &I::Key) {
+        self.internal.new_outgoing_key(key)
+    }
+
+    fn start<S: Sender<Key = I::Key, Message = I::Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<I::Message>, I::Error> {
+        if self.watchdog.is_stopped() {
+            return Ok(NextState::Terminate(None));
+        }
+        let mut tx = WatchedSender {
+            tx,
+            watchdog: &self.watchdog,
+        };
+        let next = self.internal.start(&mut tx);
+        self.watchdog.record_next(&self.key, next)
+    }
+
+    fn process_message<S: Sender<Key = I::Key, Message = I::Message>>(
+        &mut self,
+        message: Option<I::Message>,
+        tx: &mut S,
+    ) -> Result<NextState<I::Message>, I::Error> {
+        if self.watchdog.is_stopped() {
+            return Ok(NextState::Terminate(None));
+        }
+        self.watchdog.record_wake_up(&self.key, message.is_some());
+        let mut tx = WatchedSender {
+            tx,
+            watchdog: &self.watchdog,
+        };
+        let next = self.internal.process_message(message, &mut tx);
+        self.watchdog.record_next(&self.key, next)
+    }
+}
@@ -0,0 +1,119 @@
+use quote::quote;
+
+/// The options given by `#[classify(...)]` attributes.
+#[derive(Default)]
+struct Options {
+    kind: Option<syn::LitStr>,
+    size: Option<syn::ExprPath>,
+}
+
+/// Read the options given by `#[classify(kind = "...", size = "...")]` attributes, if any.
+///
+/// The size function applies to the whole message, so it is only accepted when `with_size`.
+fn parse_options(attrs: &[syn::Attribute], with_size: bool) -> syn::Result<Options> {
+    let expected = match with_size {
+        true => "expected `kind = \"...\"` or `size = \"...\"`",
+        false => "expected `kind = \"...\"`",
+    };
+    let mut options = Options::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("classify")) {
+        let list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            meta => {
+                return Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `classify(kind = \"...\")`",
+                ))
+            }
+        };
+        for nested in list.nested {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit),
+                    ..
+                })) if path.is_ident("kind") => {
+                    if options.kind.is_some() {
+                        return Err(syn::Error::new_spanned(path, "the kind is given twice"));
+                    }
+                    options.kind = Some(lit);
+                }
+                syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit),
+                    ..
+                })) if with_size && path.is_ident("size") => {
+                    if options.size.is_some() {
+                        return Err(syn::Error::new_spanned(path, "the size is given twice"));
+                    }
+                    options.size = Some(lit.parse()?);
+                }
+                nested => return Err(syn::Error::new_spanned(nested, expected)),
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Deriving Classify for an enum or a struct
+///
+/// The kind of a message is the name of its variant, or the name of the struct, unless another
+/// kind is given with `#[classify(kind = "...")]`. The size estimate is the default one, unless
+/// the type is given a function computing it with `#[classify(size = "path::to::function")]`.
+pub fn impl_classify(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let options = parse_options(&ast.attrs, true)?;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let body = match &ast.data {
+        syn::Data::Enum(data_enum) => {
+            let arms = data_enum
+                .variants
+                .iter()
+                .map(|variant| {
+                    let ident = &variant.ident;
+                    let kind = parse_options(&variant.attrs, false)?
+                        .kind
+                        .unwrap_or_else(|| syn::LitStr::new(&ident.to_string(), ident.span()));
+                    Ok(quote!(#name::#ident { .. } => #kind,))
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        syn::Data::Struct(_) => {
+            let kind = options
+                .kind
+                .clone()
+                .unwrap_or_else(|| syn::LitStr::new(&name.to_string(), name.span()));
+            quote!(#kind)
+        }
+        syn::Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "`Classify` can only be derived for enums and structs",
+            ))
+        }
+    };
+
+    let size_hint = options.size.map(|size| {
+        quote! {
+            fn size_hint(&self) -> usize {
+                #size(self)
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::system::internal::Classify for #name #ty_generics #where_clause {
+            fn kind(&self) -> &'static str {
+                #body
+            }
+
+            #size_hint
+        }
+    })
+}
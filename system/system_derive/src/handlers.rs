@@ -0,0 +1,235 @@
+use quote::{format_ident, quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+
+/// The methods of `ActorInternal`, which stay in the trait impl.
+const TRAIT_METHODS: [&str; 4] = ["new_incoming_key", "new_outgoing_key", "start", "process_message"];
+
+/// The arguments of the `message_handlers` attribute.
+pub struct HandlersArgs {
+    fallback: Option<syn::Ident>,
+}
+
+impl Parse for HandlersArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = HandlersArgs { fallback: None };
+        while !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            match key.to_string().as_str() {
+                "fallback" => args.fallback = Some(input.parse()?),
+                _ => return Err(syn::Error::new_spanned(key, "expected `fallback`")),
+            }
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// What a handler is called for.
+enum Handled {
+    /// A message of the given variant, with the names of its fields if they are named.
+    Variant(syn::Path, Option<NamedFields>),
+    /// No message, either because the timeout elapsed or the channel disconnected.
+    Nothing,
+}
+
+/// The named fields of a variant passed to its handler, as in `#[handle(Message::Move { x, y })]`.
+struct NamedFields {
+    brace: syn::token::Brace,
+    names: syn::punctuated::Punctuated<syn::Ident, syn::Token![,]>,
+}
+
+/// The argument of a `#[handle(..)]` attribute.
+struct HandleArgs {
+    path: syn::Path,
+    fields: Option<NamedFields>,
+}
+
+impl Parse for HandleArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let fields = match input.peek(syn::token::Brace) {
+            true => {
+                let content;
+                let brace = syn::braced!(content in input);
+                let names = content.parse_terminated(syn::Ident::parse)?;
+                Some(NamedFields { brace, names })
+            }
+            false => None,
+        };
+        Ok(HandleArgs { path, fields })
+    }
+}
+
+/// A method annotated with `#[handle(..)]`.
+struct Handler {
+    handled: Handled,
+    method: syn::Ident,
+    // The number of fields of the handled variant, the arguments between `self` and `tx`.
+    n_fields: usize,
+}
+
+/// Take the `#[handle(..)]` attribute off a method, if it has one.
+fn take_handler(method: &mut syn::ImplItemMethod) -> syn::Result<Option<Handler>> {
+    let position = match method.attrs.iter().position(|attr| attr.path.is_ident("handle")) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+    let attr = method.attrs.remove(position);
+    let HandleArgs { path, fields } = attr.parse_args()?;
+    let handled = match (path.is_ident("None"), fields) {
+        (true, None) => Handled::Nothing,
+        (true, Some(fields)) => {
+            return Err(syn::Error::new(fields.brace.span, "`None` has no fields"))
+        }
+        (false, fields) => Handled::Variant(path, fields),
+    };
+
+    let sig = &method.sig;
+    let has_receiver = matches!(sig.inputs.first(), Some(syn::FnArg::Receiver(_)));
+    if !has_receiver || sig.inputs.len() < 2 {
+        return Err(syn::Error::new_spanned(
+            sig,
+            "a handler takes `&mut self`, the fields of the message and the sender `tx`",
+        ));
+    }
+    let n_fields = sig.inputs.len() - 2;
+    match &handled {
+        Handled::Nothing if n_fields > 0 => {
+            return Err(syn::Error::new_spanned(
+                sig,
+                "a handler of `None` only takes `&mut self` and the sender `tx`",
+            ));
+        }
+        Handled::Variant(_, Some(fields)) if fields.names.len() != n_fields => {
+            return Err(syn::Error::new(
+                fields.brace.span,
+                format!(
+                    "the handler takes {} fields of the message between `&mut self` and `tx`",
+                    n_fields
+                ),
+            ));
+        }
+        _ => {}
+    }
+
+    Ok(Some(Handler {
+        handled,
+        method: sig.ident.clone(),
+        n_fields,
+    }))
+}
+
+/// Generate `process_message` for an impl of `ActorInternal` from its handler methods.
+///
+/// The handlers, and all other methods that are not part of `ActorInternal`, are moved to an
+/// inherent impl of the same type.
+pub fn impl_message_handlers(
+    args: HandlersArgs,
+    mut item: syn::ItemImpl,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if item.trait_.is_none() {
+        return Err(syn::Error::new_spanned(
+            &item.self_ty,
+            "`message_handlers` must be placed on an impl of `ActorInternal`",
+        ));
+    }
+
+    let mut handlers = Vec::new();
+    let mut trait_items = Vec::new();
+    let mut inherent_items = Vec::new();
+    for mut impl_item in item.items.drain(..) {
+        let method = match &mut impl_item {
+            syn::ImplItem::Method(method) => method,
+            _ => {
+                trait_items.push(impl_item);
+                continue;
+            }
+        };
+        if let Some(handler) = take_handler(method)? {
+            handlers.push(handler);
+            inherent_items.push(impl_item);
+            continue;
+        }
+        let name = method.sig.ident.to_string();
+        if name == "process_message" {
+            return Err(syn::Error::new_spanned(
+                &method.sig.ident,
+                "`process_message` is generated from the handlers and cannot be defined",
+            ));
+        }
+        match TRAIT_METHODS.contains(&name.as_str()) {
+            true => trait_items.push(impl_item),
+            false => inherent_items.push(impl_item),
+        }
+    }
+
+    let mut arms = Vec::new();
+    let mut nothing_arm = None;
+    for handler in &handlers {
+        let method = &handler.method;
+        match &handler.handled {
+            Handled::Variant(path, named) => {
+                let fields: Vec<_> = (0..handler.n_fields)
+                    .map(|i| format_ident!("field_{}", i))
+                    .collect();
+                let pattern = match (named, fields.is_empty()) {
+                    (Some(named), _) => {
+                        let names = named.names.iter();
+                        quote!(#path { #(#names: #fields,)* .. })
+                    }
+                    (None, true) => quote!(#path { .. }),
+                    // Spanned on the variant, where a variant with named fields is reported
+                    (None, false) => quote_spanned!(path.span()=> #path(#(#fields),*)),
+                };
+                arms.push(quote!(Some(#pattern) => self.#method(#(#fields,)* tx),));
+            }
+            Handled::Nothing => {
+                if nothing_arm.is_some() {
+                    return Err(syn::Error::new(
+                        method.span(),
+                        "only one handler can handle `None`",
+                    ));
+                }
+                nothing_arm = Some(quote!(None => self.#method(tx),));
+            }
+        }
+    }
+    let nothing_arm =
+        nothing_arm.unwrap_or_else(|| quote!(None => Ok(::system::NextState::Get),));
+    // Without a fallback the handlers must cover all the variants, which the compiler checks.
+    let fallback_arm = args
+        .fallback
+        .as_ref()
+        .map(|fallback| quote!(Some(message) => self.#fallback(message, tx),));
+
+    let process_message: syn::ImplItem = syn::parse_quote! {
+        #[allow(unreachable_patterns)]
+        fn process_message<SenderGenericName: ::system::Sender<Key = Self::Key, Message = Self::Message>>(
+            &mut self,
+            message: Option<Self::Message>,
+            tx: &mut SenderGenericName,
+        ) -> Result<::system::NextState<Self::Message>, Self::Error> {
+            match message {
+                #(#arms)*
+                #nothing_arm
+                #fallback_arm
+            }
+        }
+    };
+    trait_items.push(process_message);
+    item.items = trait_items;
+
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+    let self_ty = &item.self_ty;
+    Ok(quote! {
+        #item
+
+        impl #impl_generics #self_ty #where_clause {
+            #(#inherent_items)*
+        }
+    })
+}
@@ -1,179 +1,270 @@
-use proc_macro::TokenStream;
-use quote::quote;
-
-/// A function fo automaticaly producing match arms for a method
-fn match_arms(
-    name: &syn::Ident,
-    data_enum: &syn::DataEnum,
-    method_token: proc_macro2::TokenStream,
-) -> proc_macro2::TokenStream {
-    let mut match_arms = proc_macro2::TokenStream::new();
-
-    match_arms.extend(
-        data_enum
-            .variants
-            .iter()
-            .map(|variant| &variant.ident)
-            .map(|variant| {
-                quote!(
-                    #name::#variant(internal) => internal.#method_token,
-                )
-            }),
-    );
-
-    match_arms
-}
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
 
-/// Deriving ActorInternal for an enum of types implementing Internal
-///
-/// This macto assumes Message, Key, Channel are joint for all the enum variants
-pub fn impl_actor_internal(ast: &syn::DeriveInput) -> TokenStream {
-    let name = &ast.ident;
+/// A variant of an enum of actors, holding a single actor in either an unnamed or a named field.
+struct ActorVariant<'a> {
+    ident: &'a syn::Ident,
+    field: Option<&'a syn::Ident>,
+    ty: &'a syn::Type,
+}
 
-    let generics = &ast.generics.params;
-    let where_clause = &ast.generics.where_clause;
+impl<'a> ActorVariant<'a> {
+    /// A pattern binding the actor held by the variant to `internal`.
+    fn pattern(&self, name: &syn::Ident) -> proc_macro2::TokenStream {
+        let variant = self.ident;
+        match self.field {
+            Some(field) => quote!(#name::#variant { #field: internal }),
+            None => quote!(#name::#variant(internal)),
+        }
+    }
+}
 
+/// Read the variants of an enum deriving `derive`, each of which must hold a single actor.
+fn actor_variants<'a>(ast: &'a syn::DeriveInput, derive: &str) -> syn::Result<Vec<ActorVariant<'a>>> {
     let data_enum = match &ast.data {
         syn::Data::Enum(data_enum) => data_enum,
-        _ => panic!("Can't match structure"),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &ast.ident,
+                format!("`{}` can only be derived for enums whose variants hold an actor", derive),
+            ))
+        }
     };
 
-    let first_field = match &data_enum.variants.first().unwrap().fields {
-        syn::Fields::Unnamed(field) => field.unnamed.first().unwrap(),
-        _ => unreachable!(),
-    };
+    if data_enum.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            format!("`{}` cannot be derived for an enum without variants", derive),
+        ));
+    }
 
-    // Tokens for methods
-    let outgoint_key_token = quote!(new_outgoing_key(key));
-    let incoming_key_token = quote!(new_incoming_key(key));
-    let start_token = quote!(start(tx));
-    let process_message_token = quote!(process_message(message, tx));
+    data_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let fields = match &variant.fields {
+                syn::Fields::Unnamed(fields) => &fields.unnamed,
+                syn::Fields::Named(fields) => &fields.named,
+                syn::Fields::Unit => {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        format!("variants of a `{}` enum must hold an actor", derive),
+                    ))
+                }
+            };
+            if fields.len() != 1 {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    format!("variants of a `{}` enum must hold exactly one actor", derive),
+                ));
+            }
+            let field = fields.first().unwrap();
+            Ok(ActorVariant {
+                ident: &variant.ident,
+                field: field.ident.as_ref(),
+                ty: &field.ty,
+            })
+        })
+        .collect()
+}
 
-    let make_arms = |token| match_arms(name, data_enum, token);
+/// Deriving ActorInternal for an enum of types implementing ActorInternal
+///
+/// Each variant holds a single actor, in either an unnamed or a named field. All the actors must
+/// have the same Message, Key and Error types, otherwise an error is reported at the variant.
+pub fn impl_actor_internal(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let variants = actor_variants(ast, "ActorInternal")?;
+    let first = variants[0].ty;
+
+    let make_arms = |method: proc_macro2::TokenStream| {
+        variants.iter().map(move |variant| {
+            let pattern = variant.pattern(name);
+            quote!(#pattern => internal.#method,)
+        })
+    };
 
     // Match arms for methods
-    let outgoint_key_arms = make_arms(outgoint_key_token);
-    let incoming_key_arms = make_arms(incoming_key_token);
-    let start_arms = make_arms(start_token);
-    let process_message_arms = make_arms(process_message_token);
+    let outgoing_key_arms = make_arms(quote!(new_outgoing_key(key)));
+    let incoming_key_arms = make_arms(quote!(new_incoming_key(key)));
+    let start_arms = make_arms(quote!(start(tx)));
+    let process_message_arms = make_arms(quote!(process_message(message, tx)));
 
-    let mut trypl = proc_macro2::TokenStream::new();
-    trypl.extend(vec![quote!(let x :u32 = 5; )]);
+    // Check all the actors agree with the first one, pointing errors to the disagreeing variant
+    let checks = variants.iter().skip(1).map(|variant| {
+        let ty = variant.ty;
+        quote_spanned!(ty.span()=> same_types::<#first, #ty>();)
+    });
 
     let gen = quote! {
-        impl<#generics> ActorInternal for #name<#generics>
-        #where_clause {
-            type Message = <#first_field as ActorInternal>::Message;
-            type Key = <#first_field as ActorInternal>::Key;
-            type Error = <#first_field as ActorInternal>::Error;
+        impl #impl_generics ::system::ActorInternal for #name #ty_generics #where_clause {
+            type Message = <#first as ::system::ActorInternal>::Message;
+            type Key = <#first as ::system::ActorInternal>::Key;
+            type Error = <#first as ::system::ActorInternal>::Error;
 
             fn new_outgoing_key(&mut self, key: &Self::Key) {
-                #trypl
                 match self {
-                    #outgoint_key_arms
+                    #(#outgoing_key_arms)*
                 }
             }
 
             fn new_incoming_key(&mut self, key: &Self::Key) {
                 match self {
-                    #incoming_key_arms
+                    #(#incoming_key_arms)*
                 }
             }
 
-            fn start<SenderGenericName: Sender<Key = Self::Key, Message = Self::Message>>(
+            fn start<SenderGenericName: ::system::Sender<Key = Self::Key, Message = Self::Message>>(
                 &mut self,
                 tx: &mut SenderGenericName,
-            ) -> Result<NextState<Self::Message>, Self::Error> {
+            ) -> Result<::system::NextState<Self::Message>, Self::Error> {
                 match self {
-                    #start_arms
+                    #(#start_arms)*
                 }
             }
 
-            fn process_message<SenderGenericName: Sender<Key = Self::Key, Message = Self::Message>>(
+            fn process_message<SenderGenericName: ::system::Sender<Key = Self::Key, Message = Self::Message>>(
                 &mut self,
                 message: Option<Self::Message>,
                 tx: &mut SenderGenericName,
-            ) -> Result<NextState<Self::Message>, Self::Error> {
+            ) -> Result<::system::NextState<Self::Message>, Self::Error> {
                 match self {
-                    #process_message_arms
+                    #(#process_message_arms)*
                 }
             }
         }
+
+        const _: () = {
+            #[allow(dead_code)]
+            fn check_variants #impl_generics () #where_clause {
+                fn same_types<A, B>()
+                where
+                    A: ::system::ActorInternal,
+                    B: ::system::ActorInternal<Message = A::Message, Key = A::Key, Error = A::Error>,
+                {
+                }
+                #(#checks)*
+            }
+        };
     };
 
-    gen.into()
+    Ok(gen)
 }
 
-/// Deriving Internal for an enum of types implementing Internal
+/// Deriving TokioInternal for an enum of types implementing TokioInternal
 ///
-/// This macto assumes Message, Key, Channel are joint for all the enum variants
-pub fn impl_internal(ast: &syn::DeriveInput) -> TokenStream {
+/// The light and blocking methods of the enum call the same methods of the actor held by the
+/// variant, so each actor keeps its own light and blocking paths. All the actors must have the
+/// same Message, Key and Error types, otherwise an error is reported at the variant.
+pub fn impl_tokio_internal(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-    let generics = &ast.generics.params;
-    let where_clause = &ast.generics.where_clause;
+    let variants = actor_variants(ast, "TokioInternal")?;
+    let first = variants[0].ty;
 
-    let data_enum = match &ast.data {
-        syn::Data::Enum(data_enum) => data_enum,
-        _ => panic!("Can't match structure"),
-    };
+    let tokio_internal = quote!(::system::tokio::sync::TokioInternal);
 
-    let first_field = match &data_enum.variants.first().unwrap().fields {
-        syn::Fields::Unnamed(field) => field.unnamed.first().unwrap(),
-        _ => unreachable!(),
+    // The methods are called through the trait, as actors which are also an `ActorInternal` have
+    // methods of the same name from both traits
+    let make_arms = |method: proc_macro2::TokenStream, args: proc_macro2::TokenStream| {
+        let tokio_internal = &tokio_internal;
+        variants.iter().map(move |variant| {
+            let pattern = variant.pattern(name);
+            quote!(#pattern => #tokio_internal::#method(internal, #args),)
+        })
     };
 
-    // Tokens for methods
-    let outgoint_key_token = quote!(new_outgoing_key(key));
-    let incoming_key_token = quote!(new_incoming_key(key));
-    let start_token = quote!(start());
-    let process_message_token = quote!(process_message(message));
-
-    let make_arms = |token| match_arms(name, data_enum, token);
-
     // Match arms for methods
-    let outgoint_key_arms = make_arms(outgoint_key_token);
-    let incoming_key_arms = make_arms(incoming_key_token);
-    let start_arms = make_arms(start_token);
-    let process_message_arms = make_arms(process_message_token);
+    let outgoing_key_arms = make_arms(quote!(new_outgoing_key), quote!(key));
+    let incoming_key_arms = make_arms(quote!(new_incoming_key), quote!(key));
+    let start_light_arms = make_arms(quote!(start_light), quote!(tx));
+    let process_message_light_arms = make_arms(quote!(process_message_light), quote!(message, tx));
+    let start_blocking_arms = make_arms(quote!(start_blocking), quote!(tx));
+    let process_message_blocking_arms =
+        make_arms(quote!(process_message_blocking), quote!(message, tx));
 
-    let mut trypl = proc_macro2::TokenStream::new();
-    trypl.extend(vec![quote!(let x :u32 = 5; )]);
+    // Check all the actors agree with the first one, pointing errors to the disagreeing variant
+    let checks = variants.iter().skip(1).map(|variant| {
+        let ty = variant.ty;
+        quote_spanned!(ty.span()=> same_types::<#first, #ty>();)
+    });
+
+    let light_sender = quote!(::system::tokio::sync::LightSender<Self::Key, Self::Message>);
+    let blocking_sender = quote!(::system::tokio::sync::BlockingSender<Self::Key, Self::Message>);
 
     let gen = quote! {
-        impl<#generics> Internal for #name<#generics>
-        #where_clause {
-            type Message = <#first_field as Internal>::Message;
-            type Key = <#first_field as Internal>::Key;
-            type Queue = <#first_field as Internal>::Queue;
-            type Error = <#first_field as Internal>::Error;
+        impl #impl_generics #tokio_internal for #name #ty_generics #where_clause {
+            type Message = <#first as #tokio_internal>::Message;
+            type Key = <#first as #tokio_internal>::Key;
+            type Error = <#first as #tokio_internal>::Error;
 
             fn new_outgoing_key(&mut self, key: &Self::Key) {
-                #trypl
                 match self {
-                    #outgoint_key_arms
+                    #(#outgoing_key_arms)*
                 }
             }
 
             fn new_incoming_key(&mut self, key: &Self::Key) {
                 match self {
-                    #incoming_key_arms
+                    #(#incoming_key_arms)*
                 }
             }
 
-            fn start(&mut self) -> Self::Queue {
+            fn start_light(
+                &mut self,
+                tx: &mut #light_sender,
+            ) -> Result<::system::NextState<Self::Message>, Self::Error> {
                 match self {
-                    #start_arms
+                    #(#start_light_arms)*
                 }
             }
 
-            fn process_message( &mut self, message: Option<Self::Message>) -> Self::Queue {
+            fn process_message_light(
+                &mut self,
+                message: Option<Self::Message>,
+                tx: &mut #light_sender,
+            ) -> Result<::system::NextState<Self::Message>, Self::Error> {
                 match self {
-                    #process_message_arms
+                    #(#process_message_light_arms)*
+                }
+            }
+
+            fn start_blocking(
+                &mut self,
+                tx: &mut #blocking_sender,
+            ) -> Result<::system::NextState<Self::Message>, Self::Error> {
+                match self {
+                    #(#start_blocking_arms)*
+                }
+            }
+
+            fn process_message_blocking(
+                &mut self,
+                message: Option<Self::Message>,
+                tx: &mut #blocking_sender,
+            ) -> Result<::system::NextState<Self::Message>, Self::Error> {
+                match self {
+                    #(#process_message_blocking_arms)*
                 }
             }
         }
+
+        const _: () = {
+            #[allow(dead_code)]
+            fn check_variants #impl_generics () #where_clause {
+                fn same_types<A, B>()
+                where
+                    A: #tokio_internal,
+                    B: #tokio_internal<Message = A::Message, Key = A::Key, Error = A::Error>,
+                {
+                }
+                #(#checks)*
+            }
+        };
     };
 
-    gen.into()
+    Ok(gen)
 }
@@ -1,15 +1,129 @@
 use proc_macro::TokenStream;
 
+mod classify;
+mod handlers;
 mod internal;
 
-#[proc_macro_derive(Internal)]
-pub fn internal_derive(input: TokenStream) -> TokenStream {
-    let ast = syn::parse(input).unwrap();
-    internal::impl_internal(&ast)
-}
-
+/// Derive `ActorInternal` for an enum whose variants each hold a single actor.
+///
+/// The variants may hold their actor in an unnamed or a named field, and all the actors must
+/// share the same `Message`, `Key` and `Error` types.
 #[proc_macro_derive(ActorInternal)]
 pub fn sync_internal_queue_derive(input: TokenStream) -> TokenStream {
-    let ast = syn::parse(input).unwrap();
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
     internal::impl_actor_internal(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `TokioInternal` for an enum whose variants each hold a single actor.
+///
+/// The actors implement `TokioInternal` directly, and each keeps its own light and blocking
+/// paths. As with `ActorInternal`, they may be held in an unnamed or a named field and must share
+/// the same `Message`, `Key` and `Error` types.
+#[proc_macro_derive(TokioInternal)]
+pub fn tokio_internal_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    internal::impl_tokio_internal(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derive `Classify` for a message, labelling it with the name of its variant or struct.
+///
+/// The label of a variant or struct can be replaced with `#[classify(kind = "label")]`, and the
+/// size estimate of the message given by a function `fn(&Self) -> usize` with
+/// `#[classify(size = "path::to::function")]` on the enum or struct.
+#[proc_macro_derive(Classify, attributes(classify))]
+pub fn classify_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    classify::impl_classify(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Generate `process_message` of an `ActorInternal` impl from per-variant message handlers.
+///
+/// Methods of the impl annotated with `#[handle(Message::Variant)]` are called with the fields of
+/// a message of that variant followed by the sender, and a method annotated with `#[handle(None)]`
+/// is called with the sender alone when there is no message. Handlers return the same result as
+/// `process_message`. Without a handler for `None` the actor waits for the next message.
+///
+/// The named fields of a variant are passed in the order given in the attribute, as with
+/// `#[handle(Message::Move { x, y })]`, and fields left out are ignored.
+///
+/// Messages of other variants are passed, together with the sender, to the method named by
+/// `#[message_handlers(fallback = method)]`. Without a fallback the handlers must cover every
+/// variant of the message, or the generated `process_message` does not compile.
+///
+/// The handlers and any other methods which are not part of `ActorInternal` are moved to an
+/// inherent impl of the actor, so they should name the message and error types explicitly rather
+/// than through `Self`.
+/// ```
+/// use system::{ActorInternal, NextState, SendError, Sender};
+/// use system_derive::message_handlers;
+///
+/// #[derive(Debug, Clone)]
+/// pub enum Message {
+///     Ping(usize),
+///     Pong(usize),
+///     Stop,
+/// }
+///
+/// #[derive(Debug)]
+/// pub struct Ponger {
+///     pongs: usize,
+/// }
+///
+/// #[message_handlers(fallback = unexpected)]
+/// impl ActorInternal for Ponger {
+///     type Message = Message;
+///     type Key = usize;
+///     type Error = SendError<(usize, Message)>;
+///
+///     fn new_incoming_key(&mut self, _: &usize) {}
+///     fn new_outgoing_key(&mut self, _: &usize) {}
+///
+///     fn start<S: Sender<Key = usize, Message = Message>>(
+///         &mut self,
+///         _: &mut S,
+///     ) -> Result<NextState<Message>, Self::Error> {
+///         Ok(NextState::Get)
+///     }
+///
+///     #[handle(Message::Ping)]
+///     fn on_ping<S: Sender<Key = usize, Message = Message>>(
+///         &mut self,
+///         from: usize,
+///         tx: &mut S,
+///     ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+///         self.pongs += 1;
+///         tx.send(&from, Message::Pong(0))?;
+///         Ok(NextState::Get)
+///     }
+///
+///     #[handle(Message::Stop)]
+///     fn on_stop<S: Sender<Key = usize, Message = Message>>(
+///         &mut self,
+///         _: &mut S,
+///     ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+///         Ok(NextState::Terminate(None))
+///     }
+///
+///     fn unexpected<S: Sender<Key = usize, Message = Message>>(
+///         &mut self,
+///         message: Message,
+///         _: &mut S,
+///     ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+///         panic!("unexpected message {:?}", message)
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn message_handlers(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = syn::parse_macro_input!(args as handlers::HandlersArgs);
+    let item = syn::parse_macro_input!(input as syn::ItemImpl);
+    handlers::impl_message_handlers(args, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
@@ -0,0 +1,10 @@
+use system_derive::Classify;
+
+#[derive(Debug, Clone, Classify)]
+pub enum Message {
+    #[classify(name = "ping")]
+    Ping(usize),
+    Pong(usize),
+}
+
+fn main() {}
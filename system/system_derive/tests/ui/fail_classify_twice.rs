@@ -0,0 +1,10 @@
+use system_derive::Classify;
+
+#[derive(Debug, Clone, Classify)]
+pub enum Message {
+    Ping(usize),
+    #[classify(kind = "pong", kind = "PONG")]
+    Pong(usize),
+}
+
+fn main() {}
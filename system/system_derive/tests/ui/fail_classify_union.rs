@@ -0,0 +1,9 @@
+use system_derive::Classify;
+
+#[derive(Clone, Copy, Classify)]
+pub union Message {
+    ping: u32,
+    pong: f32,
+}
+
+fn main() {}
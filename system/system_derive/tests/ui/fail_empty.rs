@@ -0,0 +1,6 @@
+use system_derive::ActorInternal;
+
+#[derive(Debug, ActorInternal)]
+pub enum Player {}
+
+fn main() {}
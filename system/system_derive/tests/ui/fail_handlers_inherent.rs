@@ -0,0 +1,25 @@
+use system::{ActorInternal, NextState, SendError, Sender};
+use system_derive::message_handlers;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Add(u32, u32),
+    Reset,
+    Other,
+}
+
+#[derive(Debug)]
+pub struct Adder;
+
+#[message_handlers]
+impl Adder {
+    #[handle(Message::Reset)]
+    fn on_reset<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        Ok(NextState::Get)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,48 @@
+use system::{ActorInternal, NextState, SendError, Sender};
+use system_derive::message_handlers;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Add(u32, u32),
+    Move { x: i32, y: i32 },
+}
+
+#[derive(Debug)]
+pub struct Adder;
+
+#[message_handlers(fallback = unexpected)]
+impl ActorInternal for Adder {
+    type Message = Message;
+    type Key = usize;
+    type Error = SendError<(usize, Message)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        Ok(NextState::Get)
+    }
+
+    #[handle(Message::Move)]
+    fn on_move<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: i32,
+        _: i32,
+        _: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        Ok(NextState::Get)
+    }
+
+    fn unexpected<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: Message,
+        _: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        Ok(NextState::Get)
+    }
+}
+
+fn main() {}
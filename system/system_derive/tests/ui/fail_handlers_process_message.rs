@@ -0,0 +1,39 @@
+use system::{ActorInternal, NextState, SendError, Sender};
+use system_derive::message_handlers;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Add(u32, u32),
+    Reset,
+    Other,
+}
+
+#[derive(Debug)]
+pub struct Adder;
+
+#[message_handlers]
+impl ActorInternal for Adder {
+    type Message = Message;
+    type Key = usize;
+    type Error = SendError<(usize, Message)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        Ok(NextState::Get)
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: Option<Message>,
+        _: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        Ok(NextState::Get)
+    }
+}
+
+fn main() {}
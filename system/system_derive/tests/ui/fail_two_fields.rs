@@ -0,0 +1,37 @@
+use system::internal::*;
+use system_derive::ActorInternal;
+
+#[derive(Debug)]
+pub struct Ping;
+
+impl ActorInternal for Ping {
+    type Message = u32;
+    type Key = usize;
+    type Error = SendError<(usize, u32)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = u32>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<u32>, Self::Error> {
+        Ok(NextState::Get)
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = u32>>(
+        &mut self,
+        message: Option<u32>,
+        _: &mut S,
+    ) -> Result<NextState<u32>, Self::Error> {
+        Ok(NextState::Terminate(message))
+    }
+}
+
+#[derive(Debug, ActorInternal)]
+pub enum Player {
+    Ping(Ping),
+    Pair(Ping, Ping),
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+use system::internal::{Classify, Shared};
+use system_derive::Classify;
+
+#[derive(Debug, Clone, Classify)]
+pub enum Message<T> {
+    Proposal(u32, T),
+    #[classify(kind = "vote")]
+    NewVote { round: u32, value: T },
+    Stop,
+}
+
+#[derive(Debug, Clone, Classify)]
+pub struct Heartbeat(u64);
+
+#[derive(Debug, Clone, Classify)]
+#[classify(kind = "batch")]
+pub struct Batch {
+    values: Vec<u64>,
+}
+
+fn main() {
+    assert_eq!(Message::Proposal(1, "a").kind(), "Proposal");
+    assert_eq!(Message::NewVote { round: 1, value: 2 }.kind(), "vote");
+    assert_eq!(Message::<()>::Stop.kind(), "Stop");
+    assert_eq!(Heartbeat(3).kind(), "Heartbeat");
+    assert_eq!(Batch { values: vec![1, 2] }.kind(), "batch");
+
+    assert_eq!(Heartbeat(3).size_hint(), 8);
+    let shared = Shared::new(Message::Proposal(1, 2u64));
+    assert_eq!(shared.kind(), "Proposal");
+    assert_eq!(shared.size_hint(), std::mem::size_of::<Message<u64>>());
+}
@@ -0,0 +1,70 @@
+use system::metrics::Meter;
+use system::{ActorInternal, CrossbeamSystem, NextState, SendError, Sender, System};
+use system_derive::Classify;
+
+#[derive(Debug, Clone, Classify)]
+#[classify(size = "Note::size")]
+pub enum Note {
+    Text(String),
+    Stop,
+}
+
+impl Note {
+    fn size(&self) -> usize {
+        let heap = match self {
+            Note::Text(text) => text.len(),
+            Note::Stop => 0,
+        };
+        std::mem::size_of::<Note>() + heap
+    }
+}
+
+/// Sends a text to actor 1 and stops it, or waits for the stop.
+#[derive(Debug)]
+pub struct Writer(bool);
+
+impl ActorInternal for Writer {
+    type Message = Note;
+    type Key = usize;
+    type Error = SendError<(usize, Note)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = Note>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Note>, Self::Error> {
+        if !self.0 {
+            return Ok(NextState::Get);
+        }
+        tx.send(&1, Note::Text("hello world".to_string()))?;
+        tx.send(&1, Note::Stop)?;
+        Ok(NextState::Terminate(None))
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = Note>>(
+        &mut self,
+        message: Option<Note>,
+        _: &mut S,
+    ) -> Result<NextState<Note>, Self::Error> {
+        match message {
+            Some(Note::Stop) => Ok(NextState::Terminate(None)),
+            _ => Ok(NextState::Get),
+        }
+    }
+}
+
+fn main() {
+    let meter = Meter::new();
+    let mut system = CrossbeamSystem::new();
+    system.add_actor(0, meter.wrap_classified(0, Writer(true)), None);
+    system.add_actor(1, meter.wrap_classified(1, Writer(false)), None);
+    system.add_channel(&0, &1);
+    system.add_terminal(1);
+
+    let (_, metrics) = meter.run(system).unwrap();
+    let size = std::mem::size_of::<Note>() as u64;
+    assert_eq!(metrics.kinds["Text"].bytes, size + 11);
+    assert_eq!(metrics.kinds["Stop"].bytes, size);
+}
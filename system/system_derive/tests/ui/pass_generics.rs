@@ -0,0 +1,47 @@
+use system::internal::*;
+use system_derive::ActorInternal;
+
+#[derive(Debug)]
+pub struct Ping;
+
+impl ActorInternal for Ping {
+    type Message = u32;
+    type Key = usize;
+    type Error = SendError<(usize, u32)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = u32>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<u32>, Self::Error> {
+        Ok(NextState::Get)
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = u32>>(
+        &mut self,
+        message: Option<u32>,
+        _: &mut S,
+    ) -> Result<NextState<u32>, Self::Error> {
+        Ok(NextState::Terminate(message))
+    }
+}
+
+#[derive(Debug)]
+pub struct Wrapper<T>(T);
+
+// Generic parameters with bounds are carried over to the impl.
+#[derive(Debug, ActorInternal)]
+pub enum Generic<T: ActorInternal<Message = u32, Key = usize, Error = SendError<(usize, u32)>>>
+where
+    T: Sync,
+{
+    Ping(Ping),
+    Other(T),
+}
+
+fn main() {
+    let mut actor: Generic<Ping> = Generic::Other(Ping);
+    actor.new_incoming_key(&1);
+}
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+use system::internal::Instruction;
+use system::{ActorInternal, NextState, SendError, Sender};
+use system_derive::message_handlers;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Add(u32, u32),
+    Scale { by: u32, offset: u32 },
+    Reset,
+}
+
+#[derive(Debug, Default)]
+pub struct Adder {
+    sum: u32,
+    timeouts: usize,
+}
+
+#[message_handlers]
+impl ActorInternal for Adder {
+    type Message = Message;
+    type Key = usize;
+    type Error = SendError<(usize, Message)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        Ok(NextState::Get)
+    }
+
+    #[handle(Message::Add)]
+    fn on_add<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        a: u32,
+        b: u32,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        self.sum += a + b;
+        tx.send(&0, Message::Reset)?;
+        Ok(NextState::Get)
+    }
+
+    #[handle(Message::Scale { offset, by })]
+    fn on_scale<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        offset: u32,
+        by: u32,
+        _: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        self.sum = self.sum * by + offset;
+        Ok(NextState::Get)
+    }
+
+    #[handle(Message::Reset)]
+    fn on_reset<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        self.sum = 0;
+        Ok(NextState::Get)
+    }
+
+    #[handle(None)]
+    fn on_timeout<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        self.timeouts += 1;
+        Ok(NextState::Terminate(None))
+    }
+}
+
+fn main() {
+    let mut adder = Adder::default();
+    let mut tx = VecDeque::new();
+
+    let next = adder.process_message(Some(Message::Add(1, 2)), &mut tx).unwrap();
+    assert_eq!((adder.sum, next), (3, NextState::Get));
+    assert_eq!(tx.pop_front(), Some(Instruction::Send(0, Message::Reset)));
+
+    // Named fields are passed in the order of the attribute
+    let scale = Message::Scale { by: 2, offset: 1 };
+    let next = adder.process_message(Some(scale), &mut tx).unwrap();
+    assert_eq!((adder.sum, next), (7, NextState::Get));
+
+    adder.process_message(Some(Message::Reset), &mut tx).unwrap();
+    assert_eq!(adder.sum, 0);
+
+    let next = adder.process_message(None, &mut tx).unwrap();
+    assert_eq!((adder.timeouts, next), (1, NextState::Terminate(None)));
+}
@@ -0,0 +1,93 @@
+use system::internal::*;
+use system::tokio::sync::{ActorType, BlockingSender, LightSender, TokioInternal, TokioSystem};
+use system_derive::TokioInternal;
+
+// Terminates with a value telling which path it was run on.
+#[derive(Debug)]
+pub struct PathReporter;
+
+impl TokioInternal for PathReporter {
+    type Message = &'static str;
+    type Key = usize;
+    type Error = SendError<(usize, &'static str)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start_light(
+        &mut self,
+        _: &mut LightSender<usize, &'static str>,
+    ) -> Result<NextState<&'static str>, Self::Error> {
+        Ok(NextState::Terminate(Some("light")))
+    }
+
+    fn process_message_light(
+        &mut self,
+        _: Option<&'static str>,
+        _: &mut LightSender<usize, &'static str>,
+    ) -> Result<NextState<&'static str>, Self::Error> {
+        unreachable!()
+    }
+
+    fn start_blocking(
+        &mut self,
+        _: &mut BlockingSender<usize, &'static str>,
+    ) -> Result<NextState<&'static str>, Self::Error> {
+        Ok(NextState::Terminate(Some("blocking")))
+    }
+
+    fn process_message_blocking(
+        &mut self,
+        _: Option<&'static str>,
+        _: &mut BlockingSender<usize, &'static str>,
+    ) -> Result<NextState<&'static str>, Self::Error> {
+        unreachable!()
+    }
+}
+
+// Terminates with the first message it gets, on either path.
+#[derive(Debug)]
+pub struct Forwarder;
+
+impl ActorInternal for Forwarder {
+    type Message = &'static str;
+    type Key = usize;
+    type Error = SendError<(usize, &'static str)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = &'static str>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<&'static str>, Self::Error> {
+        Ok(NextState::Get)
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = &'static str>>(
+        &mut self,
+        message: Option<&'static str>,
+        _: &mut S,
+    ) -> Result<NextState<&'static str>, Self::Error> {
+        Ok(NextState::Terminate(message))
+    }
+}
+
+#[derive(Debug, TokioInternal)]
+pub enum Node {
+    Reporter(PathReporter),
+    Forwarder { actor: Forwarder },
+}
+
+fn main() {
+    let mut system = TokioSystem::<Node>::new(2);
+    system.add_actor(0, Node::Reporter(PathReporter), Some((ActorType::Light, 10, 10).into()));
+    system.add_actor(1, Node::Reporter(PathReporter), Some((ActorType::Blocking, 10, 10).into()));
+    system.add_actor(2, Node::Forwarder { actor: Forwarder }, Some((ActorType::Light, 10, 10).into()));
+    system.add_terminal(0);
+    system.add_terminal(1);
+
+    let values = system.run_blocking().unwrap();
+    assert_eq!(values[&0], Some("light"));
+    assert_eq!(values[&1], Some("blocking"));
+}
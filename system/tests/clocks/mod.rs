@@ -0,0 +1,116 @@
+use system::clocks::{HybridClock, LamportClock, VectorClock};
+
+// Each test follows the same exchange between three actors:
+//
+//     a: a1 --send--> .......... a2
+//     b: ....... b1 (receive a1) -- b2 (send) --> .
+//     c: c1 ................................... c2 (receive b2)
+//
+// so that a1 -> b1 -> b2 -> c2 are causally ordered, while a2 and c1 are concurrent with the
+// events of the other actors.
+
+#[test]
+fn test_vector_clock_exchange() {
+    let (mut a, mut b, mut c) = (VectorClock::new(), VectorClock::new(), VectorClock::new());
+
+    a.tick(&'a');
+    let a1 = a.clone();
+    c.tick(&'c');
+    let c1 = c.clone();
+    b.receive(&'b', &a1);
+    let b1 = b.clone();
+    b.tick(&'b');
+    let b2 = b.clone();
+    a.tick(&'a');
+    let a2 = a.clone();
+    c.receive(&'c', &b2);
+    let c2 = c.clone();
+
+    assert!(a1.happened_before(&b1));
+    assert!(b1.happened_before(&b2));
+    assert!(a1.happened_before(&c2));
+    assert!(c1.happened_before(&c2));
+    assert!(!c2.happened_before(&a1));
+
+    assert!(a2.is_concurrent(&b1));
+    assert!(a2.is_concurrent(&c2));
+    assert!(c1.is_concurrent(&a1));
+    assert!(c1.is_concurrent(&b2));
+
+    assert_eq!(a1.partial_cmp(&a1.clone()), Some(std::cmp::Ordering::Equal));
+    assert_eq!((c2.get(&'a'), c2.get(&'b'), c2.get(&'c')), (1, 2, 2));
+}
+
+#[test]
+fn test_vector_clock_merge() {
+    let mut clock = VectorClock::new();
+    clock.tick(&"a");
+    clock.tick(&"a");
+    let mut other = VectorClock::new();
+    other.tick(&"a");
+    other.tick(&"b");
+
+    clock.merge(&other);
+    assert_eq!(
+        (clock.get(&"a"), clock.get(&"b"), clock.get(&"c")),
+        (2, 1, 0)
+    );
+    assert!(other.happened_before(&clock));
+}
+
+#[test]
+fn test_lamport_clock_exchange() {
+    let (mut a, mut b, mut c) = (
+        LamportClock::new(),
+        LamportClock::new(),
+        LamportClock::new(),
+    );
+
+    let a1 = a.tick();
+    let c1 = c.tick();
+    let b1 = b.receive(&a);
+    let b2 = b.tick();
+    let a2 = a.tick();
+    let c2 = c.receive(&b);
+
+    // Causally ordered events have increasing times
+    assert!(a1 < b1 && b1 < b2 && b2 < c2);
+    assert!(c1 < c2);
+    assert_eq!((a1, a2, b1, b2, c1, c2), (1, 2, 2, 3, 1, 4));
+
+    // but equal or ordered times say nothing about concurrent events
+    assert_eq!(a2, b1);
+    assert!(c1 < b2);
+}
+
+#[test]
+fn test_hybrid_clock_exchange() {
+    let (mut a, mut b, mut c) = (HybridClock::new(), HybridClock::new(), HybridClock::new());
+
+    // The physical clock of b is behind the one of a, while c is ahead of both
+    a.tick(100);
+    let a1 = a;
+    c.tick(120);
+    let c1 = c;
+    b.receive(&a1, 90);
+    let b1 = b;
+    b.tick(95);
+    let b2 = b;
+    a.tick(100);
+    let a2 = a;
+    c.receive(&b2, 110);
+    let c2 = c;
+
+    assert!(a1 < b1 && b1 < b2 && b2 < c2);
+    assert!(c1 < c2);
+
+    // b does not go back in time, counting events instead
+    assert_eq!((b1.physical(), b1.logical()), (100, 1));
+    assert_eq!((b2.physical(), b2.logical()), (100, 2));
+    assert_eq!((a2.physical(), a2.logical()), (100, 1));
+    // while c keeps its own physical time
+    assert_eq!((c2.physical(), c2.logical()), (120, 1));
+
+    a.tick(130);
+    assert_eq!((a.physical(), a.logical()), (130, 0));
+}
@@ -0,0 +1,115 @@
+use crate::topology::{Idle, Wiring};
+use std::collections::HashSet;
+use system::config::{ConfigError, SystemConfig};
+use system::tokio::sync::ActorType;
+
+const CONFIG: &str = r#"
+[defaults]
+kind = "heavy"
+buffer = 10
+
+[[actors]]
+role = "hub"
+keys = [0]
+terminal = true
+buffer = 50
+
+[[actors]]
+role = "spoke"
+keys = [1, 2, 3]
+kind = "light"
+
+[[channels]]
+from = 3
+to = 1
+direction = "both"
+
+[[shapes]]
+shape = "star"
+center = 0
+leaves = { role = "spoke" }
+
+[[shapes]]
+shape = "ring"
+keys = [1, 2]
+direction = "backward"
+"#;
+
+fn idle(role: &str, _: &usize) -> Option<Idle> {
+    match role {
+        "hub" | "spoke" => Some(Idle),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_config_toml() {
+    let config = SystemConfig::<usize>::from_toml(CONFIG).unwrap();
+    assert_eq!(config.role_keys("spoke"), vec![1, 2, 3]);
+
+    let mut system = Wiring::default();
+    config.apply(&mut system, idle).unwrap();
+
+    assert_eq!(system.actors[&0], Some((ActorType::Heavy, 50, 100)));
+    assert_eq!(system.actors[&2], Some((ActorType::Light, 10, 100)));
+    assert_eq!(system.terminals, HashSet::from([0]));
+    assert_eq!(
+        system.channels(),
+        [(3, 1), (1, 3), (0, 1), (0, 2), (0, 3), (2, 1), (1, 2)].into()
+    );
+}
+
+#[test]
+fn test_config_json() {
+    let json = r#"{
+        "defaults": { "kind": "heavy", "buffer": 10 },
+        "actors": [
+            { "role": "hub", "keys": [0], "terminal": true, "buffer": 50 },
+            { "role": "spoke", "keys": [1, 2, 3], "kind": "light" }
+        ],
+        "channels": [{ "from": 3, "to": 1, "direction": "both" }],
+        "shapes": [
+            { "shape": "star", "center": 0, "leaves": { "role": "spoke" } },
+            { "shape": "ring", "keys": [1, 2], "direction": "backward" }
+        ]
+    }"#;
+
+    assert_eq!(
+        SystemConfig::<usize>::from_json(json).unwrap(),
+        SystemConfig::from_toml(CONFIG).unwrap()
+    );
+}
+
+#[test]
+fn test_config_errors() {
+    let apply = |source: &str| {
+        let config = SystemConfig::<usize>::from_toml(source)?;
+        config.apply(&mut Wiring::default(), idle)
+    };
+    let actors = "[[actors]]\nrole = \"spoke\"\nkeys = [0, 1]\n";
+
+    assert!(matches!(
+        apply("[[actors]]\nrole = \"spoke\"\nkeys = [0]\ncolor = \"red\"\n"),
+        Err(ConfigError::Toml(_))
+    ));
+    assert!(matches!(
+        apply(&format!("{}[[actors]]\nrole = \"hub\"\nkeys = [1]\n", actors)),
+        Err(ConfigError::DuplicateKey(1))
+    ));
+    assert!(matches!(
+        apply(&format!("{}[[channels]]\nfrom = 0\nto = 2\n", actors)),
+        Err(ConfigError::UnknownKey(2))
+    ));
+    assert!(matches!(
+        apply(&format!("{}[[shapes]]\nshape = \"random\"\nkeys = [0, 1]\np = 2.0\nseed = 0\n", actors)),
+        Err(ConfigError::InvalidShape(_))
+    ));
+    assert!(matches!(
+        apply("[[actors]]\nrole = \"rim\"\nkeys = [0]\n"),
+        Err(ConfigError::UnknownRole(role)) if role == "rim"
+    ));
+    assert!(matches!(
+        apply(&format!("{}[[shapes]]\nshape = \"ring\"\nkeys = {{ role = \"spokes\" }}\n", actors)),
+        Err(ConfigError::UnknownRole(role)) if role == "spokes"
+    ));
+}
@@ -1,38 +1,43 @@
 // An integration test for the message passing system
 
+use std::fmt::Debug;
+use std::hash::Hash;
 use system::internal::*;
 use system::{ActorInternal, NextState, Sender};
 
 #[derive(Debug)]
-pub struct CycleInternal {
-    input_key: Option<usize>,
-    output_key: Option<usize>,
+pub struct CycleInternal<K = usize> {
+    input_key: Option<K>,
+    output_key: Option<K>,
     starter: bool,
 }
 
-impl CycleInternal {
+impl<K> CycleInternal<K> {
     pub fn new(starter: bool) -> Self {
         CycleInternal {
             input_key: None,
             output_key: None,
-            starter: starter,
+            starter,
         }
     }
 }
 
-impl ActorInternal for CycleInternal {
+impl<K> ActorInternal for CycleInternal<K>
+where
+    K: Hash + Send + Clone + Debug + Eq + 'static,
+{
     type Message = usize;
-    type Error = SendError<(usize, usize)>;
-    type Key = usize;
+    type Error = SendError<(K, usize)>;
+    type Key = K;
 
     fn new_incoming_key(&mut self, key: &Self::Key) {
         assert!(self.input_key.is_none());
-        self.input_key = Some(*key);
+        self.input_key = Some(key.clone());
     }
 
     fn new_outgoing_key(&mut self, key: &Self::Key) {
         assert!(self.output_key.is_none());
-        self.output_key = Some(*key);
+        self.output_key = Some(key.clone());
     }
 
     fn start<T: Sender<Key = Self::Key, Message = Self::Message>>(
@@ -40,8 +45,8 @@ impl ActorInternal for CycleInternal {
         tx: &mut T,
     ) -> Result<NextState<Self::Message>, Self::Error> {
         if self.starter {
-            let out = self.output_key.unwrap();
-            tx.send(&out, 0).unwrap();
+            let out = self.output_key.as_ref().unwrap();
+            tx.send(out, 0).unwrap();
         }
         Ok(NextState::Get)
     }
@@ -54,8 +59,8 @@ impl ActorInternal for CycleInternal {
         assert!(message.is_some());
         let value = message.unwrap();
 
-        let out = self.output_key.unwrap();
-        tx.send(&out, value + 1).ok();
+        let out = self.output_key.as_ref().unwrap();
+        tx.send(out, value + 1).ok();
         Ok(NextState::Terminate(Some(value + 1)))
     }
 }
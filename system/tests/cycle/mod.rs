@@ -4,29 +4,74 @@ mod test_tokio;
 
 use actors::CycleInternal;
 use system::tokio::sync::ActorType;
+use system::topology::{self, Direction};
 use system::System;
 
-pub fn setup<S: System<Internal = CycleInternal>>(mut system: S, n: usize) -> S
+pub fn setup<S: System<Internal = CycleInternal>>(system: S, n: usize) -> S
 where
     S::ActorParameters: From<(ActorType, usize, usize)>,
 {
-    system.add_actor(
-        0,
-        CycleInternal::new(true),
-        Some((ActorType::Light, 2 * n, 2 * n).into()),
-    );
+    setup_with_type(system, n, ActorType::Light)
+}
 
-    for i in 1..n {
-        system.add_actor(
-            i,
-            CycleInternal::new(false),
-            Some((ActorType::Light, 2 * n, 2 * n).into()),
-        );
-        system.add_channel(&(i - 1), &i);
+/// Set up a cycle of length `n` whose actors are all of the given type.
+pub fn setup_with_type<S: System<Internal = CycleInternal>>(
+    mut system: S,
+    n: usize,
+    kind: ActorType,
+) -> S
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let keys: Vec<usize> = (0..n).collect();
+    for &i in &keys {
+        system.add_actor(i, CycleInternal::new(i == 0), Some((kind, 2 * n, 2 * n).into()));
     }
 
-    system.add_channel(&(n - 1), &0);
+    topology::ring(&mut system, &keys, Direction::Forward);
     system.add_terminal(0);
 
     system
 }
+
+/// Run a cycle with a terminal which has no actor, returning the error of the run.
+pub fn run_unknown_terminal<S: System<Internal = CycleInternal>>(system: S) -> S::Error
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let mut cycle = setup(system, 10);
+    cycle.add_terminal(10);
+    cycle.run().unwrap_err()
+}
+
+/// Set up a cycle of length `n` on the given system, run it and check the result.
+pub fn run_cycle<S: System<Internal = CycleInternal>>(system: S, n: usize)
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let values = setup(system, n).run().unwrap();
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[&0], Some(n));
+}
+
+/// Run a cycle of length `n` whose actors are identified by human-readable names.
+pub fn run_named_cycle<S: System<Internal = CycleInternal<String>>>(mut system: S, n: usize)
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let name = |i: usize| format!("actor-{}", i);
+
+    for i in 0..n {
+        system.add_actor(
+            name(i),
+            CycleInternal::new(i == 0),
+            Some((ActorType::Light, 2 * n, 2 * n).into()),
+        );
+    }
+    let keys: Vec<String> = (0..n).map(name).collect();
+    topology::ring(&mut system, &keys, Direction::Forward);
+    system.add_terminal(name(0));
+
+    let values = system.run().unwrap();
+    assert_eq!(values[&name(0)], Some(n));
+}
@@ -1,4 +1,6 @@
-use super::setup;
+use super::{run_cycle, run_named_cycle, run_unknown_terminal, setup};
+use system::synchronous::system::SystemError;
+use system::synchronous::PoolSystem;
 use system::CrossbeamSystem;
 
 #[test]
@@ -9,3 +11,27 @@ fn test_sync_cycle() {
     let values = cycle.run().unwrap();
     assert_eq!(values[&0], Some(n));
 }
+
+#[test]
+fn test_sync_cycle_generic() {
+    run_cycle(CrossbeamSystem::new(), 80);
+}
+
+#[test]
+fn test_sync_named_cycle() {
+    run_named_cycle(CrossbeamSystem::new(), 20);
+}
+
+#[test]
+fn test_pool_cycle() {
+    run_cycle(PoolSystem::with_workers(4), 1000);
+    run_named_cycle(PoolSystem::with_workers(1), 20);
+}
+
+#[test]
+fn test_sync_unknown_terminal() {
+    let err = run_unknown_terminal(CrossbeamSystem::new());
+    assert!(matches!(err, SystemError::UnknownTerminal));
+    let err = run_unknown_terminal(PoolSystem::with_workers(2));
+    assert!(matches!(err, SystemError::UnknownTerminal));
+}
@@ -1 +1,11 @@
+mod clocks;
+mod config;
 mod cycle;
+mod local;
+mod mailbox;
+mod metrics;
+mod send_errors;
+mod sharing;
+mod topology;
+mod trace;
+mod watchdog;
@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use system::internal::{NextState, SendError};
+use system::tokio::local::{LocalInternal, LocalSender, LocalSystem};
+
+/// A trail of the actors a message went through, shared by all of its copies.
+type Trail = Rc<RefCell<Vec<usize>>>;
+
+/// An actor appending its key to the trail and passing it on, which is not `Send`.
+#[derive(Debug)]
+struct TrailInternal {
+    key: usize,
+    next: Option<usize>,
+    starter: bool,
+}
+
+impl LocalInternal for TrailInternal {
+    type Message = Trail;
+    type Key = usize;
+    type Error = SendError<(usize, Trail)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+
+    fn new_outgoing_key(&mut self, key: &usize) {
+        self.next = Some(*key);
+    }
+
+    fn start(&mut self, tx: &mut LocalSender<usize, Trail>) -> Result<NextState<Trail>, Self::Error> {
+        if self.starter {
+            tx.send(&self.next.unwrap(), Rc::new(RefCell::new(vec![self.key])))?;
+        }
+        Ok(NextState::Get)
+    }
+
+    fn process_message(
+        &mut self,
+        message: Option<Trail>,
+        tx: &mut LocalSender<usize, Trail>,
+    ) -> Result<NextState<Trail>, Self::Error> {
+        let trail = message.unwrap();
+        if self.starter {
+            return Ok(NextState::Terminate(Some(trail)));
+        }
+        trail.borrow_mut().push(self.key);
+        tx.send(&self.next.unwrap(), trail)?;
+        Ok(NextState::Terminate(None))
+    }
+}
+
+/// An actor waiting for a message that never arrives.
+#[derive(Debug)]
+struct Sleeper;
+
+impl LocalInternal for Sleeper {
+    type Message = Rc<()>;
+    type Key = usize;
+    type Error = SendError<(usize, Rc<()>)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start(&mut self, _: &mut LocalSender<usize, Rc<()>>) -> Result<NextState<Rc<()>>, Self::Error> {
+        Ok(NextState::GetTimeout(Duration::from_secs(3600)))
+    }
+
+    fn process_message(
+        &mut self,
+        message: Option<Rc<()>>,
+        _: &mut LocalSender<usize, Rc<()>>,
+    ) -> Result<NextState<Rc<()>>, Self::Error> {
+        Ok(NextState::Terminate(message))
+    }
+}
+
+#[test]
+fn test_local_trail() {
+    let n = 100;
+    let mut system = LocalSystem::new();
+    for key in 0..n {
+        let actor = TrailInternal { key, next: None, starter: key == 0 };
+        system.add_actor(key, actor, None);
+    }
+    for key in 0..n {
+        system.add_channel(&key, &((key + 1) % n));
+    }
+    system.add_terminal(0);
+
+    let values = system.run_blocking().unwrap();
+    let trail = values[&0].clone().unwrap();
+    assert_eq!(*trail.borrow(), (0..n).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_local_paused_timeout() {
+    let mut system = LocalSystem::new();
+    system.add_actor(0, Sleeper, None);
+    system.add_terminal(0);
+    system.set_start_paused(true);
+
+    let now = Instant::now();
+    let values = system.run_blocking().unwrap();
+    assert_eq!(values[&0], None);
+    assert!(now.elapsed() < Duration::from_secs(60));
+}
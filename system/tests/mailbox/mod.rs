@@ -0,0 +1,210 @@
+// Tests for bounded mailboxes in the synchronous system
+
+use std::thread;
+use std::time::Duration;
+use system::internal::*;
+use system::synchronous::crossbeam::InChannelCB;
+use system::synchronous::{InChannel, OverflowPolicy, PoolSystem, SyncParameters};
+use system::tokio::sync::ActorType;
+use system::{CrossbeamSystem, System};
+
+const N_MESSAGES: usize = 10;
+const CAPACITY: usize = 3;
+
+/// Either a producer sending a burst of messages, or a slow consumer collecting them.
+///
+/// The producer terminates with the number of messages rejected due to a full mailbox,
+/// the consumer terminates with the messages it recieved.
+#[derive(Debug)]
+pub enum BurstInternal {
+    Producer(Option<usize>),
+    Consumer(Vec<usize>),
+}
+
+impl ActorInternal for BurstInternal {
+    type Message = Vec<usize>;
+    type Error = SendError<(usize, Vec<usize>)>;
+    type Key = usize;
+
+    fn new_incoming_key(&mut self, _: &Self::Key) {}
+
+    fn new_outgoing_key(&mut self, key: &Self::Key) {
+        if let BurstInternal::Producer(target) = self {
+            *target = Some(*key);
+        }
+    }
+
+    fn start<T: Sender<Key = Self::Key, Message = Self::Message>>(
+        &mut self,
+        tx: &mut T,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        match self {
+            BurstInternal::Producer(target) => {
+                let target = target.unwrap();
+                let mut rejected = 0;
+                for i in 0..N_MESSAGES {
+                    match tx.send(&target, vec![i]) {
+                        Ok(()) => {}
+                        Err(SendError::Full(_)) => rejected += 1,
+                        Err(err) => return Err(err),
+                    }
+                }
+                Ok(NextState::Terminate(Some(vec![rejected])))
+            }
+            BurstInternal::Consumer(_) => {
+                // let the producer fill the mailbox
+                thread::sleep(Duration::from_millis(200));
+                Ok(NextState::GetTimeout(Duration::from_millis(100)))
+            }
+        }
+    }
+
+    fn process_message<T: Sender<Key = Self::Key, Message = Self::Message>>(
+        &mut self,
+        message: Option<Self::Message>,
+        _: &mut T,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        match (self, message) {
+            (BurstInternal::Consumer(values), Some(m)) => {
+                values.extend(m);
+                Ok(NextState::GetTimeout(Duration::from_millis(100)))
+            }
+            (BurstInternal::Consumer(values), None) => {
+                Ok(NextState::Terminate(Some(values.clone())))
+            }
+            (BurstInternal::Producer(_), _) => Ok(NextState::Get),
+        }
+    }
+}
+
+/// Run a burst into a bounded mailbox and return the rejected count and recieved values.
+fn run_burst(overflow: OverflowPolicy) -> (usize, Vec<usize>) {
+    let mut system = CrossbeamSystem::new();
+    system.add_actor(0, BurstInternal::Producer(None), None);
+    system.add_actor(
+        1,
+        BurstInternal::Consumer(Vec::new()),
+        Some(SyncParameters::new(Some(CAPACITY), overflow)),
+    );
+    system.add_channel(&0, &1);
+    system.add_terminal(0);
+    system.add_terminal(1);
+
+    let monitor = system.mailbox_monitor();
+    let mut values = system.run().unwrap();
+
+    let report = monitor.get(&1).unwrap();
+    assert_eq!(report.capacity, Some(CAPACITY));
+    assert_eq!(report.high_watermark, CAPACITY);
+    assert_eq!(monitor.get(&0).unwrap().capacity, None);
+
+    let rejected = values.remove(&0).unwrap().unwrap()[0];
+    assert_eq!(report.rejected, rejected);
+    assert_eq!(report.dropped + report.rejected, N_MESSAGES - CAPACITY);
+
+    (rejected, values.remove(&1).unwrap().unwrap())
+}
+
+#[test]
+fn test_drop_newest() {
+    let (rejected, values) = run_burst(OverflowPolicy::DropNewest);
+    assert_eq!(rejected, 0);
+    assert_eq!(values, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_drop_oldest() {
+    let (rejected, values) = run_burst(OverflowPolicy::DropOldest);
+    assert_eq!(rejected, 0);
+    assert_eq!(values, vec![7, 8, 9]);
+}
+
+#[test]
+#[should_panic(expected = "positive capacity")]
+fn test_drop_oldest_without_capacity() {
+    let mut system = CrossbeamSystem::new();
+    let parameters = SyncParameters::new(Some(0), OverflowPolicy::DropOldest);
+    system.add_actor(0, BurstInternal::Consumer(Vec::new()), Some(parameters));
+    system.add_terminal(0);
+    system.run().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "positive capacity")]
+fn test_pool_drop_oldest_without_capacity() {
+    let mut system = PoolSystem::new();
+    let parameters = SyncParameters::new(Some(0), OverflowPolicy::DropOldest);
+    system.add_actor(0, BurstInternal::Consumer(Vec::new()), Some(parameters));
+    system.add_terminal(0);
+    system.run().unwrap();
+}
+
+#[test]
+fn test_send_to_dropped_actor() {
+    for policy in [
+        OverflowPolicy::Block,
+        OverflowPolicy::DropNewest,
+        OverflowPolicy::DropOldest,
+        OverflowPolicy::Error,
+    ] {
+        let channel = InChannelCB::<usize>::bounded(CAPACITY, policy);
+        let tx = channel.tx();
+        drop(channel);
+        assert_eq!(tx.send(0), Err(SendError::Disconnected(0)), "{policy:?}");
+    }
+}
+
+#[test]
+fn test_error_when_full() {
+    let (rejected, values) = run_burst(OverflowPolicy::Error);
+    assert_eq!(rejected, N_MESSAGES - CAPACITY);
+    assert_eq!(values, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_block_when_full() {
+    let mut system = CrossbeamSystem::new();
+    system.add_actor(0, BurstInternal::Producer(None), None);
+    system.add_actor(
+        1,
+        BurstInternal::Consumer(Vec::new()),
+        Some(SyncParameters::bounded(CAPACITY)),
+    );
+    system.add_channel(&0, &1);
+    system.add_terminal(0);
+    system.add_terminal(1);
+
+    let monitor = system.mailbox_monitor();
+    let values = system.run().unwrap();
+
+    assert_eq!(values[&0], Some(vec![0]));
+    assert_eq!(values[&1], Some((0..N_MESSAGES).collect()));
+    assert_eq!(monitor.get(&1).unwrap().dropped, 0);
+}
+
+#[test]
+fn test_pool_block_when_full() {
+    // A pool system never blocks its senders, so a full mailbox rejects the message
+    let mut system = PoolSystem::with_workers(2);
+    system.add_actor(0, BurstInternal::Producer(None), None);
+    system.add_actor(
+        1,
+        BurstInternal::Consumer(Vec::new()),
+        Some(SyncParameters::bounded(CAPACITY)),
+    );
+    system.add_channel(&0, &1);
+    system.add_terminal(0);
+    system.add_terminal(1);
+
+    let values = system.run().unwrap();
+    assert_eq!(values[&0], Some(vec![N_MESSAGES - CAPACITY]));
+    assert_eq!(values[&1], Some(vec![0, 1, 2]));
+}
+
+#[test]
+fn test_tokio_parameters_bounded() {
+    let parameters = SyncParameters::from((ActorType::Heavy, 10, 10));
+    assert_eq!(parameters.capacity, Some(10));
+    assert_eq!(parameters.overflow, OverflowPolicy::Error);
+    assert_eq!(parameters.kind, Some(ActorType::Heavy));
+}
@@ -0,0 +1,227 @@
+use crate::trace::{Message, Player};
+use std::time::Duration;
+use system::internal::*;
+use system::metrics::{ChannelMetrics, Histogram, KindMetrics, Meter, Metered, Timed};
+use system::synchronous::PoolSystem;
+use system::tokio::sync::{ActorType, TokioSystem};
+use system::{CrossbeamSystem, System};
+
+/// Waits for a message `timeouts` times before terminating.
+#[derive(Debug)]
+pub struct Waiter {
+    timeouts: usize,
+}
+
+impl ActorInternal for Waiter {
+    type Message = ();
+    type Key = usize;
+    type Error = SendError<(usize, ())>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = ()>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<()>, Self::Error> {
+        Ok(NextState::GetTimeout(Duration::from_millis(1)))
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = ()>>(
+        &mut self,
+        _: Option<()>,
+        _: &mut S,
+    ) -> Result<NextState<()>, Self::Error> {
+        self.timeouts -= 1;
+        match self.timeouts {
+            0 => Ok(NextState::Terminate(None)),
+            _ => Ok(NextState::GetTimeout(Duration::from_millis(1))),
+        }
+    }
+}
+
+/// Waits for messages without a timeout, forever.
+#[derive(Debug)]
+pub struct Listener;
+
+impl ActorInternal for Listener {
+    type Message = ();
+    type Key = usize;
+    type Error = SendError<(usize, ())>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = ()>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<()>, Self::Error> {
+        Ok(NextState::Get)
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = ()>>(
+        &mut self,
+        _: Option<()>,
+        _: &mut S,
+    ) -> Result<NextState<()>, Self::Error> {
+        Ok(NextState::Get)
+    }
+}
+
+/// Drops the messages sent through it.
+#[derive(Debug)]
+struct Discard;
+
+impl Sender for Discard {
+    type Key = usize;
+    type Message = Timed<usize, ()>;
+
+    fn send(&mut self, _: &usize, _: Self::Message) -> SendResult<usize, Self::Message> {
+        Ok(())
+    }
+}
+
+/// Meter two rounds of ping pong between actors 0 and 1.
+fn check_ping_pong<S: System<Internal = Metered<Player>>>(mut system: S)
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let meter = Meter::new();
+    let pinger = Player {
+        peer: None,
+        rounds: Some(2),
+    };
+    let ponger = Player {
+        peer: None,
+        rounds: None,
+    };
+    system.add_actor(
+        0,
+        meter.wrap(0, pinger),
+        Some((ActorType::Light, 10, 10).into()),
+    );
+    system.add_actor(
+        1,
+        meter.wrap(1, ponger),
+        Some((ActorType::Light, 10, 10).into()),
+    );
+    system.add_channel(&0, &1);
+    system.add_channel(&1, &0);
+    system.add_terminal(1);
+
+    let (values, metrics) = meter.run(system).unwrap();
+    assert_eq!(values[&1], Some(Message::Stop));
+
+    // Ping(0), Ping(1) and Stop one way, two pongs the other
+    let channel = |sent, delivered| ChannelMetrics { sent, delivered };
+    assert_eq!(metrics.channels.len(), 2);
+    assert_eq!(metrics.channels[&(0, 1)], channel(3, 3));
+    assert_eq!(metrics.channels[&(1, 0)], channel(2, 2));
+    assert_eq!(
+        (metrics.sent(), metrics.delivered(), metrics.timeouts()),
+        (5, 5, 0)
+    );
+
+    let (pinger, ponger) = (&metrics.actors[&0], &metrics.actors[&1]);
+    assert_eq!((pinger.sent, pinger.delivered), (3, 2));
+    assert_eq!((ponger.sent, ponger.delivered), (2, 3));
+    assert_eq!(pinger.processing_time.count(), 2);
+    assert_eq!(ponger.mailbox_time.count(), 3);
+    assert_eq!(metrics.mailbox_time().count(), 5);
+    assert!(metrics.processing_time().total() <= metrics.duration);
+    assert!(metrics.kinds.is_empty());
+}
+
+#[test]
+fn test_metrics_counts() {
+    check_ping_pong(CrossbeamSystem::new());
+    check_ping_pong(PoolSystem::with_workers(2));
+    check_ping_pong(TokioSystem::new(1));
+}
+
+#[test]
+fn test_metrics_kinds() {
+    let meter = Meter::new();
+    let mut system = CrossbeamSystem::new();
+    let pinger = Player {
+        peer: None,
+        rounds: Some(3),
+    };
+    let ponger = Player {
+        peer: None,
+        rounds: None,
+    };
+    system.add_actor(0, meter.wrap_classified(0, pinger), None);
+    system.add_actor(1, meter.wrap_classified(1, ponger), None);
+    system.add_channel(&0, &1);
+    system.add_channel(&1, &0);
+    system.add_terminal(1);
+
+    let (_, metrics) = meter.run(system).unwrap();
+    let size = std::mem::size_of::<Message>() as u64;
+    let kind = |count| KindMetrics {
+        sent: count,
+        delivered: count,
+        bytes: count * size,
+    };
+    assert_eq!(metrics.kinds.len(), 3);
+    assert_eq!(metrics.kinds["ping"], kind(3));
+    assert_eq!(metrics.kinds["pong"], kind(3));
+    assert_eq!(metrics.kinds["stop"], kind(1));
+}
+
+#[test]
+fn test_metrics_timeouts() {
+    let meter = Meter::new();
+    let mut system = CrossbeamSystem::new();
+    system.add_actor(0, meter.wrap(0, Waiter { timeouts: 3 }), None);
+    system.add_terminal(0);
+
+    let (_, metrics) = meter.run(system).unwrap();
+    let waiter = &metrics.actors[&0];
+    assert_eq!((waiter.sent, waiter.delivered, waiter.timeouts), (0, 0, 3));
+    assert_eq!(waiter.processing_time.count(), 3);
+    assert!(metrics.channels.is_empty());
+    assert!(metrics.duration >= Duration::from_millis(3));
+}
+
+#[test]
+fn test_metrics_missing_message() {
+    // Only a missing message after asking for a timeout is a timeout
+    let meter = Meter::new();
+    let mut listener = meter.wrap(0, Listener);
+    listener.start(&mut Discard).unwrap();
+    listener.process_message(None, &mut Discard).unwrap();
+    let mut waiter = meter.wrap(1, Waiter { timeouts: 2 });
+    waiter.start(&mut Discard).unwrap();
+    waiter.process_message(None, &mut Discard).unwrap();
+
+    let metrics = meter.metrics();
+    assert_eq!(metrics.actors[&0].timeouts, 0);
+    assert_eq!(metrics.actors[&1].timeouts, 1);
+}
+
+#[test]
+fn test_histogram() {
+    let mut histogram = Histogram::new();
+    assert_eq!((histogram.mean(), histogram.quantile(0.5)), (None, None));
+
+    for micros in [1, 2, 3, 100, 1000] {
+        histogram.record(Duration::from_micros(micros));
+    }
+    assert_eq!(histogram.count(), 5);
+    assert_eq!(histogram.min(), Some(Duration::from_micros(1)));
+    assert_eq!(histogram.max(), Some(Duration::from_micros(1000)));
+    assert_eq!(histogram.mean(), Some(Duration::from_nanos(221_200)));
+    // 3 falls in the bucket [2, 4) and 100 in [64, 128)
+    assert_eq!(histogram.quantile(0.6), Some(Duration::from_micros(4)));
+    assert_eq!(histogram.quantile(0.8), Some(Duration::from_micros(128)));
+    assert_eq!(histogram.quantile(1.0), Some(Duration::from_micros(1000)));
+
+    let mut other = Histogram::new();
+    other.record(Duration::from_nanos(10));
+    other.merge(&histogram);
+    assert_eq!(other.count(), 6);
+    assert_eq!(other.min(), Some(Duration::from_nanos(10)));
+    assert_eq!(other.quantile(0.0), Some(Duration::from_micros(1)));
+}
@@ -0,0 +1,173 @@
+// Tests for errors reported to actors when sending fails
+
+use system::internal::*;
+use system::synchronous::system::SystemError;
+use system::synchronous::PoolSystem;
+use system::tokio::local::LocalSystem;
+use system::tokio::sync::{self, ActorType, TokioSystem};
+use system::{CrossbeamSystem, System};
+
+/// An actor sending a single message to a given key and returning the outcome.
+///
+/// A probe without a target waits for a message and returns it.
+#[derive(Debug)]
+pub struct ProbeInternal {
+    target: Option<usize>,
+    on_unknown: OnUnknown,
+}
+
+/// What a probe does when its target is unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnUnknown {
+    /// Terminate with [`UNKNOWN_KEY`].
+    Report,
+    /// Fail with the send error.
+    Fail,
+    Panic,
+}
+
+fn probe(target: Option<usize>) -> ProbeInternal {
+    ProbeInternal {
+        target,
+        on_unknown: OnUnknown::Report,
+    }
+}
+
+/// The termination message of a probe whose message was sent successfully.
+const SENT: usize = 0;
+/// The termination message of a probe that tried to send to an unknown key.
+const UNKNOWN_KEY: usize = 1;
+
+impl ActorInternal for ProbeInternal {
+    type Message = usize;
+    type Error = SendError<(usize, usize)>;
+    type Key = usize;
+
+    fn new_incoming_key(&mut self, _: &Self::Key) {}
+
+    fn new_outgoing_key(&mut self, _: &Self::Key) {}
+
+    fn start<T: Sender<Key = Self::Key, Message = Self::Message>>(
+        &mut self,
+        tx: &mut T,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        let target = match self.target {
+            Some(target) => target,
+            None => return Ok(NextState::Get),
+        };
+        let outcome = match tx.send(&target, 5) {
+            Ok(()) => SENT,
+            Err(SendError::UnknownKey((key, message))) => {
+                assert_eq!((key, message), (target, 5));
+                match self.on_unknown {
+                    OnUnknown::Report => UNKNOWN_KEY,
+                    OnUnknown::Fail => return Err(SendError::UnknownKey((key, message))),
+                    OnUnknown::Panic => panic!("unknown key {}", key),
+                }
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(NextState::Terminate(Some(outcome)))
+    }
+
+    fn process_message<T: Sender<Key = Self::Key, Message = Self::Message>>(
+        &mut self,
+        message: Option<Self::Message>,
+        _: &mut T,
+    ) -> Result<NextState<Self::Message>, Self::Error> {
+        Ok(NextState::Terminate(message))
+    }
+}
+
+fn check_unknown_key<S: System<Internal = ProbeInternal>>(mut system: S, kind: ActorType)
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let params = || Some((kind, 10, 10).into());
+    system.add_actor(0, probe(Some(1)), params());
+    system.add_actor(1, probe(None), params());
+    system.add_actor(2, probe(Some(7)), params());
+    system.add_channel(&0, &1);
+    for key in 0..3 {
+        system.add_terminal(key);
+    }
+
+    let values = system.run().unwrap();
+    assert_eq!(values[&0], Some(SENT));
+    assert_eq!(values[&1], Some(5));
+    assert_eq!(values[&2], Some(UNKNOWN_KEY));
+}
+
+#[test]
+fn test_sync_unknown_key() {
+    check_unknown_key(CrossbeamSystem::new(), ActorType::Light);
+    check_unknown_key(PoolSystem::with_workers(2), ActorType::Light);
+}
+
+#[test]
+fn test_tokio_unknown_key() {
+    check_unknown_key(TokioSystem::new(3), ActorType::Light);
+    check_unknown_key(TokioSystem::new(3), ActorType::Blocking);
+    check_unknown_key(TokioSystem::new(3), ActorType::Heavy);
+}
+
+/// Run a probe failing as set by `on_unknown` next to a probe waiting for a message which never
+/// comes, both terminals, returning the error of the run.
+fn run_failing_terminal<S: System<Internal = ProbeInternal>>(
+    mut system: S,
+    kind: ActorType,
+    on_unknown: OnUnknown,
+) -> S::Error
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let params = || Some((kind, 10, 10).into());
+    let failing = ProbeInternal {
+        on_unknown,
+        ..probe(Some(7))
+    };
+    system.add_actor(0, failing, params());
+    system.add_actor(1, probe(None), params());
+    system.add_terminal(0);
+    system.add_terminal(1);
+
+    system.run().unwrap_err()
+}
+
+#[test]
+fn test_sync_failing_terminal() {
+    let unknown = SendError::UnknownKey((7, 5));
+    for on_unknown in [OnUnknown::Fail, OnUnknown::Panic] {
+        let crossbeam = run_failing_terminal(CrossbeamSystem::new(), ActorType::Light, on_unknown);
+        let pool = run_failing_terminal(PoolSystem::with_workers(2), ActorType::Light, on_unknown);
+        for err in [crossbeam, pool] {
+            match (on_unknown, err) {
+                (OnUnknown::Fail, SystemError::ActorError(err)) => assert_eq!(err, unknown),
+                (OnUnknown::Panic, SystemError::ThreadError(_)) => {}
+                (_, err) => panic!("unexpected error {:?}", err),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tokio_failing_terminal() {
+    let unknown = SendError::UnknownKey((7, 5));
+    for on_unknown in [OnUnknown::Fail, OnUnknown::Panic] {
+        let mut errors = vec![run_failing_terminal(
+            LocalSystem::new(),
+            ActorType::Light,
+            on_unknown,
+        )];
+        for kind in [ActorType::Light, ActorType::Blocking, ActorType::Heavy] {
+            errors.push(run_failing_terminal(TokioSystem::new(2), kind, on_unknown));
+        }
+        for err in errors {
+            match (on_unknown, err) {
+                (OnUnknown::Fail, sync::SystemError::ActorError(err)) => assert_eq!(err, unknown),
+                (OnUnknown::Panic, sync::SystemError::AgentError) => {}
+                (_, err) => panic!("unexpected error {:?}", err),
+            }
+        }
+    }
+}
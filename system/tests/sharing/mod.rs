@@ -0,0 +1,221 @@
+use system::internal::*;
+use system::sharing::{SharedInternal, Sharing, SharingError};
+use system::tokio::sync::{ActorType, TokioSystem};
+use system::TerminalValues;
+use system::{CrossbeamSystem, System};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Data(Vec<u8>),
+    Ack,
+}
+
+/// The hub of a star broadcasts a payload to its leaves `rounds` times, waiting for all of them
+/// to acknowledge each round. A leaf acknowledges each payload and terminates after `rounds`.
+#[derive(Debug)]
+pub struct Spoke {
+    hub: bool,
+    rounds: usize,
+    payload: usize,
+    peers: Vec<usize>,
+    acks: usize,
+}
+
+impl Spoke {
+    pub fn new(hub: bool, rounds: usize, payload: usize) -> Self {
+        Spoke {
+            hub,
+            rounds,
+            payload,
+            peers: Vec::new(),
+            acks: 0,
+        }
+    }
+
+    fn broadcast<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        tx.broadcast(&self.peers, &Message::Data(vec![0; self.payload]))?;
+        Ok(NextState::Get)
+    }
+
+    fn step<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        message: &Message,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, SendError<(usize, Message)>> {
+        match (self.hub, message) {
+            (true, Message::Ack) => {
+                self.acks += 1;
+                if self.acks < self.peers.len() {
+                    return Ok(NextState::Get);
+                }
+                self.acks = 0;
+                self.rounds -= 1;
+                match self.rounds {
+                    0 => Ok(NextState::Terminate(Some(Message::Ack))),
+                    _ => self.broadcast(tx),
+                }
+            }
+            (false, Message::Data(data)) => {
+                assert_eq!(data.len(), self.payload);
+                tx.send(&self.peers[0], Message::Ack)?;
+                self.rounds -= 1;
+                match self.rounds {
+                    0 => Ok(NextState::Terminate(Some(message.clone()))),
+                    _ => Ok(NextState::Get),
+                }
+            }
+            _ => panic!("unexpected message {:?}", message),
+        }
+    }
+}
+
+impl ActorInternal for Spoke {
+    type Message = Message;
+    type Key = usize;
+    type Error = SendError<(usize, Message)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, key: &usize) {
+        self.peers.push(*key);
+    }
+
+    fn start<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        match self.hub {
+            true => self.broadcast(tx),
+            false => Ok(NextState::Get),
+        }
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        message: Option<Message>,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        self.step(&message.unwrap(), tx)
+    }
+}
+
+impl SharedInternal for Spoke {
+    type Message = Message;
+    type Key = usize;
+    type Error = SendError<(usize, Message)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, key: &usize) {
+        self.peers.push(*key);
+    }
+
+    fn start<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        match self.hub {
+            true => self.broadcast(tx),
+            false => Ok(NextState::Get),
+        }
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        message: Option<Shared<Message>>,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        self.step(&message.unwrap(), tx)
+    }
+}
+
+/// Set up a star of `n` leaves around the hub 0, all of them terminals.
+pub fn star<S, I>(mut system: S, n: usize, rounds: usize, payload: usize, wrap: fn(Spoke) -> I) -> S
+where
+    S: System<Internal = I>,
+    I: ActorInternal<Key = usize>,
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let parameters = || Some((ActorType::Light, 2 * n, 2 * n).into());
+    let leaves: Vec<usize> = (1..=n).collect();
+    system.add_actor(0, wrap(Spoke::new(true, rounds, payload)), parameters());
+    for &leaf in &leaves {
+        system.add_actor(leaf, wrap(Spoke::new(false, rounds, payload)), parameters());
+    }
+    system::topology::star(&mut system, &0, &leaves, system::topology::Direction::Both);
+    system.add_terminal(0);
+    for leaf in leaves {
+        system.add_terminal(leaf);
+    }
+    system
+}
+
+/// Collects the messages sent through it.
+#[derive(Debug, Default)]
+struct Collect(Vec<(usize, Shared<Message>)>);
+
+impl Sender for Collect {
+    type Key = usize;
+    type Message = Shared<Message>;
+
+    fn send(&mut self, key: &usize, message: Shared<Message>) -> SendResult<usize, Self::Message> {
+        match *key {
+            0 => Err(SendError::UnknownKey((0, message))),
+            _ => {
+                self.0.push((*key, message));
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sharing_broadcast() {
+    let mut hub = Sharing(Spoke::new(true, 1, 100));
+    for key in 1..=3 {
+        ActorInternal::new_outgoing_key(&mut hub, &key);
+    }
+    let mut tx = Collect::default();
+    hub.start(&mut tx).unwrap();
+
+    // A single allocation is sent to every leaf
+    let sent = &tx.0;
+    assert_eq!(
+        sent.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert!(sent.iter().all(|(_, m)| Shared::ptr_eq(m, &sent[0].1)));
+    assert_eq!(*sent[0].1, Message::Data(vec![0; 100]));
+
+    // Errors give back the plain message
+    ActorInternal::new_outgoing_key(&mut hub, &0);
+    let err = hub.start(&mut Collect::default()).unwrap_err();
+    let expected = SendError::UnknownKey((0, Message::Data(vec![0; 100])));
+    assert_eq!(err, SharingError(expected));
+}
+
+#[test]
+fn test_sharing_star() {
+    let n = 10;
+    let check = |values: TerminalValues<Sharing<Spoke>>| {
+        assert_eq!(values.len(), n + 1);
+        assert_eq!(values[&0].as_deref(), Some(&Message::Ack));
+        assert_eq!(values[&1].as_deref(), Some(&Message::Data(vec![0; 8])));
+    };
+    check(
+        star(CrossbeamSystem::new(), n, 3, 8, Sharing)
+            .run()
+            .unwrap(),
+    );
+    check(
+        star(TokioSystem::new(n + 1), n, 3, 8, Sharing)
+            .run_blocking()
+            .unwrap(),
+    );
+
+    let values = star(CrossbeamSystem::new(), n, 3, 8, |spoke| spoke)
+        .run()
+        .unwrap();
+    assert_eq!(values[&1], Some(Message::Data(vec![0; 8])));
+}
@@ -0,0 +1,195 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use system::internal::*;
+use system::tokio::sync::ActorType;
+use system::topology::{self, Direction, Graph};
+use system::synchronous::PoolSystem;
+use system::tokio::local::LocalSystem;
+use system::tokio::sync::TokioSystem;
+use system::{CrossbeamSystem, System};
+
+#[derive(Debug)]
+pub struct Idle;
+
+impl ActorInternal for Idle {
+    type Message = ();
+    type Key = usize;
+    type Error = SendError<(usize, ())>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = ()>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<()>, Self::Error> {
+        Ok(NextState::Terminate(None))
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = ()>>(
+        &mut self,
+        _: Option<()>,
+        _: &mut S,
+    ) -> Result<NextState<()>, Self::Error> {
+        Ok(NextState::Terminate(None))
+    }
+}
+
+/// A system recording the actors, channels and terminals added to it, without running anything.
+#[derive(Debug, Default)]
+pub struct Wiring {
+    pub actors: HashMap<usize, Option<(ActorType, usize, usize)>>,
+    pub channels: Vec<(usize, usize)>,
+    pub terminals: HashSet<usize>,
+    pub graph: Graph<usize>,
+}
+
+impl Wiring {
+    pub fn channels(&self) -> BTreeSet<(usize, usize)> {
+        let channels: BTreeSet<_> = self.channels.iter().copied().collect();
+        assert_eq!(channels.len(), self.channels.len(), "a channel was added twice");
+        channels
+    }
+}
+
+impl System for Wiring {
+    type Internal = Idle;
+    type ActorParameters = (ActorType, usize, usize);
+    type Error = ();
+
+    fn add_actor(&mut self, key: usize, _: Idle, parameters: Option<(ActorType, usize, usize)>) {
+        self.actors.insert(key, parameters);
+    }
+
+    fn add_channel(&mut self, sender: &usize, reciever: &usize) {
+        self.channels.push((*sender, *reciever));
+    }
+
+    fn add_terminal(&mut self, key: usize) {
+        self.terminals.insert(key);
+    }
+
+    fn graph(&self) -> &Graph<usize> {
+        &self.graph
+    }
+
+    fn run(self) -> Result<HashMap<usize, Option<()>>, ()> {
+        Ok(HashMap::new())
+    }
+}
+
+fn wire(shape: impl FnOnce(&mut Wiring)) -> BTreeSet<(usize, usize)> {
+    let mut system = Wiring::default();
+    shape(&mut system);
+    system.channels()
+}
+
+#[test]
+fn test_ring() {
+    let forward = wire(|s| topology::ring(s, &[0, 1, 2], Direction::Forward));
+    assert_eq!(forward, [(0, 1), (1, 2), (2, 0)].into());
+
+    let backward = wire(|s| topology::ring(s, &[0, 1, 2], Direction::Backward));
+    assert_eq!(backward, [(1, 0), (2, 1), (0, 2)].into());
+
+    let both = wire(|s| topology::ring(s, &[0, 1, 2], Direction::Both));
+    assert_eq!(both, forward.union(&backward).copied().collect());
+}
+
+#[test]
+fn test_small_ring() {
+    for direction in [Direction::Forward, Direction::Backward, Direction::Both] {
+        assert!(wire(|s| topology::ring(s, &[0], direction)).is_empty());
+    }
+
+    let forward = wire(|s| topology::ring(s, &[0, 1], Direction::Forward));
+    assert_eq!(forward, [(0, 1), (1, 0)].into());
+
+    let both = wire(|s| topology::ring(s, &[0, 1], Direction::Both));
+    assert_eq!(both, [(0, 1), (1, 0)].into());
+}
+
+#[test]
+fn test_complete() {
+    let forward = wire(|s| topology::complete(s, &[0, 1, 2], Direction::Forward));
+    assert_eq!(forward, [(0, 1), (0, 2), (1, 2)].into());
+
+    let both = wire(|s| topology::complete(s, &[0, 1, 2, 3], Direction::Both));
+    assert_eq!(both.len(), 12);
+    assert!(both.iter().all(|(a, b)| a != b));
+}
+
+#[test]
+fn test_star() {
+    let channels = wire(|s| topology::star(s, &0, &[1, 2, 3], Direction::Backward));
+    assert_eq!(channels, [(1, 0), (2, 0), (3, 0)].into());
+}
+
+#[test]
+fn test_bipartite() {
+    let channels = wire(|s| topology::bipartite(s, &[0, 1], &[2, 3, 4], Direction::Both));
+    assert_eq!(channels.len(), 12);
+    assert!(channels.contains(&(1, 4)) && channels.contains(&(4, 1)));
+    assert!(!channels.contains(&(0, 1)) && !channels.contains(&(2, 3)));
+}
+
+#[test]
+fn test_random() {
+    let keys: Vec<usize> = (0..20).collect();
+    let random = |seed| wire(|s| topology::random(s, &keys, 0.3, seed, Direction::Forward));
+
+    assert_eq!(random(7), random(7));
+    assert!(random(7).iter().all(|(a, b)| a < b));
+
+    let none = wire(|s| topology::random(s, &keys, 0.0, 7, Direction::Forward));
+    assert!(none.is_empty());
+    let all = wire(|s| topology::random(s, &keys, 1.0, 7, Direction::Forward));
+    assert_eq!(all, wire(|s| topology::complete(s, &keys, Direction::Forward)));
+}
+
+#[test]
+fn test_grid() {
+    // 0 1 2
+    // 3 4
+    let channels = wire(|s| topology::grid(s, &[0, 1, 2, 3, 4], 3, Direction::Forward));
+    assert_eq!(channels, [(0, 1), (1, 2), (3, 4), (0, 3), (1, 4)].into());
+}
+
+/// Add two actors and a terminal in a star of channels pointing to the terminal.
+fn star_system<S: System<Internal = Idle>>(mut system: S) -> S
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    system.add_actor(0, Idle, Some((ActorType::Light, 10, 10).into()));
+    system.add_actor(1, Idle, Some((ActorType::Heavy, 10, 10).into()));
+    system.add_actor(2, Idle, Some((ActorType::Blocking, 10, 10).into()));
+    topology::star(&mut system, &0, &[1, 2], Direction::Backward);
+    system.add_terminal(0);
+    system
+}
+
+#[test]
+fn test_to_dot() {
+    let expected = "\
+digraph system {
+    node [shape=circle];
+    \"0\" [label=\"0\\nlight\", shape=doublecircle, style=filled, fillcolor=lightblue];
+    \"1\" [label=\"1\\nheavy\"];
+    \"2\" [label=\"2\\nblocking\"];
+    \"1\" -> \"0\";
+    \"2\" -> \"0\";
+}
+";
+    assert_eq!(star_system(TokioSystem::new(1)).to_dot(), expected);
+    assert_eq!(star_system(CrossbeamSystem::new()).to_dot(), expected);
+    assert_eq!(star_system(PoolSystem::with_workers(1)).to_dot(), expected);
+
+    let local = star_system(LocalSystem::new()).to_dot();
+    assert!(local.contains("\"1\" [label=\"1\"];"));
+}
+
+#[test]
+fn test_to_dot_escapes_keys() {
+    let mut graph = Graph::new();
+    graph.add_actor(String::from("say \"hi\""), None);
+    assert!(graph.to_dot().contains(r#""\"say \\\"hi\\\"\"" [label="\"say \\\"hi\\\"\""];"#));
+}
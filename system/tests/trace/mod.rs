@@ -0,0 +1,292 @@
+use system::internal::*;
+use system::tokio::sync::{ActorType, TokioSystem};
+use system::trace::{
+    variant_name, ClockLog, Event, EventKind, Recorder, SequenceChart, SHIVIZ_REGEX,
+};
+use system::{CrossbeamSystem, System};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Ping(usize),
+    Pong(usize),
+    Stop,
+}
+
+impl Classify for Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Ping(_) => "ping",
+            Message::Pong(_) => "pong",
+            Message::Stop => "stop",
+        }
+    }
+}
+
+/// Either pings its peer `rounds` times, waiting for a pong each time, and then stops it, or
+/// answers each ping with a pong until it is stopped.
+#[derive(Debug)]
+pub struct Player {
+    pub peer: Option<usize>,
+    pub rounds: Option<usize>,
+}
+
+impl ActorInternal for Player {
+    type Message = Message;
+    type Key = usize;
+    type Error = SendError<(usize, Message)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, key: &usize) {
+        self.peer = Some(*key);
+    }
+
+    fn start<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        if self.rounds.is_some() {
+            tx.send(&self.peer.unwrap(), Message::Ping(0))?;
+        }
+        Ok(NextState::Get)
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        message: Option<Message>,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        let peer = self.peer.unwrap();
+        match (message, self.rounds) {
+            (Some(Message::Pong(n)), Some(rounds)) if n + 1 < rounds => {
+                tx.send(&peer, Message::Ping(n + 1))?;
+                Ok(NextState::Get)
+            }
+            (_, Some(_)) => {
+                tx.send(&peer, Message::Stop)?;
+                Ok(NextState::Terminate(None))
+            }
+            (Some(Message::Ping(n)), None) => {
+                tx.send(&peer, Message::Pong(n))?;
+                Ok(NextState::Get)
+            }
+            (_, None) => Ok(NextState::Terminate(Some(Message::Stop))),
+        }
+    }
+}
+
+/// Record two rounds of ping pong between actors 0 and 1.
+fn record_ping_pong<S: System<Internal = system::trace::Recorded<Player>>>(
+    mut system: S,
+) -> Vec<Event<usize, Message>>
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let recorder = Recorder::new();
+    let pinger = Player {
+        peer: None,
+        rounds: Some(2),
+    };
+    let ponger = Player {
+        peer: None,
+        rounds: None,
+    };
+    system.add_actor(
+        0,
+        recorder.wrap(0, pinger),
+        Some((ActorType::Light, 10, 10).into()),
+    );
+    system.add_actor(
+        1,
+        recorder.wrap(1, ponger),
+        Some((ActorType::Light, 10, 10).into()),
+    );
+    system.add_channel(&0, &1);
+    system.add_channel(&1, &0);
+    system.add_terminal(1);
+    system.run().unwrap();
+
+    recorder.events()
+}
+
+#[test]
+fn test_recorded_events() {
+    for events in [
+        record_ping_pong(CrossbeamSystem::new()),
+        record_ping_pong(TokioSystem::new(1)),
+    ] {
+        let ponger: Vec<_> = events
+            .iter()
+            .filter(|event| event.actor == 1)
+            .map(|event| &event.kind)
+            .collect();
+        assert_eq!(
+            ponger,
+            [
+                &EventKind::Receive {
+                    message: Message::Ping(0)
+                },
+                &EventKind::Send {
+                    to: 0,
+                    message: Message::Pong(0)
+                },
+                &EventKind::Receive {
+                    message: Message::Ping(1)
+                },
+                &EventKind::Send {
+                    to: 0,
+                    message: Message::Pong(1)
+                },
+                &EventKind::Receive {
+                    message: Message::Stop
+                },
+                &EventKind::Terminate {
+                    value: Some(Message::Stop)
+                },
+            ]
+        );
+        assert!(events.iter().enumerate().all(|(i, event)| event.seq == i));
+    }
+}
+
+#[test]
+fn test_sequence_chart_formats() {
+    let events = record_ping_pong(CrossbeamSystem::new());
+    let chart = SequenceChart::new(&events).variants(["Ping", "Stop"]);
+
+    let text: Vec<String> = chart
+        .to_text()
+        .lines()
+        .map(|line| line.trim().split_once("  ").unwrap().1.to_string())
+        .collect();
+    assert_eq!(
+        text,
+        [
+            "0 -> 1: Ping(0)",
+            "0 -> 1: Ping(1)",
+            "0 -> 1: Stop",
+            "0 terminated with None",
+            "1 terminated with Some(Stop)",
+        ]
+    );
+
+    let plantuml = chart.to_plantuml();
+    assert!(plantuml.starts_with("@startuml\nparticipant \"0\" as a0\nparticipant \"1\" as a1\n"));
+    assert!(plantuml.contains("a0 -> a1 : Ping(1)\n"));
+    assert!(!plantuml.contains("Pong"));
+    assert!(plantuml.ends_with("@enduml\n"));
+
+    let kinds = SequenceChart::new(&events).kinds(["pong"]).to_text();
+    assert_eq!(
+        kinds.lines().filter(|line| line.contains("Pong")).count(),
+        2
+    );
+    assert_eq!(chart.variants(["ping"]).to_text().lines().count(), 2);
+
+    let mermaid = SequenceChart::new(&events).actors([1]).to_mermaid();
+    assert!(mermaid.contains("    a1->>a0: Pong(0)\n"));
+    assert!(mermaid.contains("    Note over a1: terminated with Some(Stop)\n"));
+    assert!(!mermaid.contains("Note over a0"));
+}
+
+#[test]
+fn test_variant_name() {
+    assert_eq!(variant_name(&Message::Ping(3)), "Ping");
+    assert_eq!(variant_name(&Message::Stop), "Stop");
+    assert_eq!(variant_name(&Some(1)), "Some");
+    assert_eq!(variant_name(&7), "7");
+}
+
+#[test]
+fn test_vector_clocks() {
+    let log = ClockLog::new();
+    let mut system = TokioSystem::new(1);
+    let pinger = Player {
+        peer: None,
+        rounds: Some(2),
+    };
+    let ponger = Player {
+        peer: None,
+        rounds: None,
+    };
+    system.add_actor(
+        0,
+        log.wrap(0, pinger),
+        Some((ActorType::Light, 10, 10).into()),
+    );
+    system.add_actor(
+        1,
+        log.wrap(1, ponger),
+        Some((ActorType::Blocking, 10, 10).into()),
+    );
+    system.add_channel(&0, &1);
+    system.add_channel(&1, &0);
+    system.add_terminal(1);
+    let values = system.run_blocking().unwrap();
+
+    // The termination message of the ponger carries its final clock
+    let stop = values[&1].as_ref().unwrap();
+    assert_eq!(stop.message, Message::Stop);
+    assert_eq!((stop.clock.get(&0), stop.clock.get(&1)), (5, 6));
+
+    let clocks = |actor: usize| -> Vec<(String, u64, u64)> {
+        log.entries()
+            .into_iter()
+            .filter(|entry| entry.actor == actor)
+            .map(|entry| (entry.description, entry.clock.get(&0), entry.clock.get(&1)))
+            .collect()
+    };
+    let entry = |description: &str, first, second| (description.to_string(), first, second);
+    assert_eq!(
+        clocks(0),
+        [
+            entry("send Ping(0) to 1", 1, 0),
+            entry("receive Pong(0) from 1", 2, 2),
+            entry("send Ping(1) to 1", 3, 2),
+            entry("receive Pong(1) from 1", 4, 4),
+            entry("send Stop to 1", 5, 4),
+            entry("terminate with None", 6, 4),
+        ]
+    );
+    assert_eq!(
+        clocks(1),
+        [
+            entry("receive Ping(0) from 0", 1, 1),
+            entry("send Pong(0) to 0", 1, 2),
+            entry("receive Ping(1) from 0", 3, 3),
+            entry("send Pong(1) to 0", 3, 4),
+            entry("receive Stop from 0", 5, 5),
+            entry("terminate with Some(Stop)", 5, 6),
+        ]
+    );
+
+    let shiviz = log.to_shiviz();
+    assert_eq!(shiviz.lines().count(), 24);
+    assert!(shiviz.contains("1 {\"0\":3,\"1\":3}\nreceive Ping(1) from 0\n"));
+    assert_eq!(SHIVIZ_REGEX, r"(?<host>\S*) (?<clock>{.*})\n(?<event>.*)");
+}
+
+#[test]
+fn test_vector_clocks_run() {
+    let log = ClockLog::new();
+    let mut system = CrossbeamSystem::new();
+    let pinger = Player {
+        peer: None,
+        rounds: Some(1),
+    };
+    let ponger = Player {
+        peer: None,
+        rounds: None,
+    };
+    system.add_actor(0, log.wrap(0, pinger), None);
+    system.add_actor(1, log.wrap(1, ponger), None);
+    system.add_channel(&0, &1);
+    system.add_channel(&1, &0);
+    system.add_terminal(1);
+
+    // The termination messages are returned as the actors sent them, the clocks are in the log
+    let values = log.run(system).unwrap();
+    assert_eq!(values[&1], Some(Message::Stop));
+    let entries = log.entries();
+    let last = entries.iter().rfind(|entry| entry.actor == 1).unwrap();
+    assert_eq!(last.description, "terminate with Some(Stop)");
+}
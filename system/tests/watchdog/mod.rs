@@ -0,0 +1,369 @@
+use crate::trace::{Message, Player};
+use std::sync::Arc;
+use std::time::Duration;
+use system::internal::*;
+use system::synchronous::PoolSystem;
+use system::tokio::local::LocalSystem;
+use system::tokio::sync::{ActorType, RuntimeSettings, TokioSystem};
+use system::watchdog::{Deadline, Deadlock, Watchdog, WatchdogError, Watched};
+use system::{CrossbeamSystem, System};
+
+/// Waits for a message with a timeout, terminating after `retries` timeouts or retrying forever.
+///
+/// A failing retrier ends with an error instead of terminating, sending to an unknown actor.
+#[derive(Debug)]
+pub struct Retrier {
+    retries: Option<usize>,
+    timeout: Duration,
+    fail: bool,
+    // Shared by the retriers of a run, to tell whether they were all dropped.
+    _alive: Arc<()>,
+}
+
+fn retrier(retries: Option<usize>) -> Retrier {
+    Retrier {
+        retries,
+        timeout: Duration::from_millis(1),
+        fail: false,
+        _alive: Arc::new(()),
+    }
+}
+
+impl ActorInternal for Retrier {
+    type Message = Message;
+    type Key = usize;
+    type Error = SendError<(usize, Message)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        Ok(NextState::GetTimeout(self.timeout))
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: Option<Message>,
+        tx: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        match self.retries.as_mut() {
+            Some(0) if self.fail => {
+                tx.send(&usize::MAX, Message::Stop)?;
+                Ok(NextState::Get)
+            }
+            Some(0) => Ok(NextState::Terminate(Some(Message::Stop))),
+            Some(retries) => {
+                *retries -= 1;
+                Ok(NextState::GetTimeout(self.timeout))
+            }
+            None => Ok(NextState::GetTimeout(self.timeout)),
+        }
+    }
+}
+
+fn player(rounds: Option<usize>) -> Player {
+    Player { peer: None, rounds }
+}
+
+/// Two rounds of ping pong between actors 0 and 1, next to an idle actor 2 if it is a terminal.
+fn check_ping_pong<S>(mut system: S, idle_terminal: bool)
+where
+    S: System<Internal = Watched<Player>> + Send + 'static,
+    S::Error: Send,
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let watchdog = Watchdog::new();
+    let parameters = || Some((ActorType::Light, 10, 10).into());
+    system.add_actor(0, watchdog.wrap(0, player(Some(2))), parameters());
+    system.add_actor(1, watchdog.wrap(1, player(None)), parameters());
+    system.add_channel(&0, &1);
+    system.add_channel(&1, &0);
+    system.add_terminal(1);
+    if idle_terminal {
+        system.add_actor(2, watchdog.wrap(2, player(None)), parameters());
+        system.add_terminal(2);
+    }
+
+    match watchdog.run(system) {
+        Ok(values) => {
+            assert!(!idle_terminal);
+            assert_eq!(values[&1], Some(Message::Stop));
+        }
+        Err(WatchdogError::Deadlock(deadlock)) => {
+            assert!(idle_terminal);
+            let expected = Deadlock {
+                blocked: vec![2],
+                unfinished: vec![2],
+            };
+            assert_eq!(deadlock, expected);
+        }
+        Err(err) => panic!("{err:?}"),
+    }
+}
+
+#[test]
+fn test_watchdog_finished_run() {
+    check_ping_pong(CrossbeamSystem::new(), false);
+    check_ping_pong(PoolSystem::with_workers(2), false);
+    check_ping_pong(TokioSystem::new(1), false);
+}
+
+#[test]
+fn test_watchdog_deadlock_after_run() {
+    check_ping_pong(CrossbeamSystem::new(), true);
+    check_ping_pong(PoolSystem::with_workers(2), true);
+    check_ping_pong(TokioSystem::new(1), true);
+}
+
+#[test]
+fn test_watchdog_waiting_players() {
+    let watchdog = Watchdog::new();
+    let mut system = PoolSystem::with_workers(2);
+    for key in 0..3 {
+        system.add_actor(key, watchdog.wrap(key, player(None)), None);
+    }
+    system.add_channel(&0, &1);
+    system.add_channel(&1, &0);
+    system.add_terminal(0);
+    system.add_terminal(2);
+
+    match watchdog.run(system) {
+        Err(WatchdogError::Deadlock(deadlock)) => {
+            assert_eq!(deadlock.blocked, vec![0, 1, 2]);
+            assert_eq!(deadlock.unfinished, vec![0, 2]);
+            assert_eq!(
+                deadlock.to_string(),
+                "deadlock: actors [0, 1, 2] wait for messages that never come, \
+                 terminals [0, 2] never finish"
+            );
+        }
+        _ => panic!("no player starts"),
+    }
+}
+
+/// Waits for a message which never comes.
+#[derive(Debug)]
+pub struct Idle {
+    _alive: Arc<()>,
+}
+
+impl ActorInternal for Idle {
+    type Message = Message;
+    type Key = usize;
+    type Error = SendError<(usize, Message)>;
+
+    fn new_incoming_key(&mut self, _: &usize) {}
+    fn new_outgoing_key(&mut self, _: &usize) {}
+
+    fn start<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        Ok(NextState::Get)
+    }
+
+    fn process_message<S: Sender<Key = usize, Message = Message>>(
+        &mut self,
+        _: Option<Message>,
+        _: &mut S,
+    ) -> Result<NextState<Message>, Self::Error> {
+        Ok(NextState::Get)
+    }
+}
+
+/// A deadlock of idle actors of the given type, which are all dropped by the time the run
+/// returns.
+fn check_deadlock_teardown<S>(mut system: S, kind: ActorType)
+where
+    S: System<Internal = Watched<Idle>> + Send + 'static,
+    S::Error: Send,
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let watchdog = Watchdog::new();
+    let alive = Arc::new(());
+    for key in 0..3 {
+        let idle = Idle {
+            _alive: alive.clone(),
+        };
+        let parameters = Some((kind, 10, 10).into());
+        system.add_actor(key, watchdog.wrap(key, idle), parameters);
+    }
+    system.add_channel(&0, &1);
+    system.add_terminal(1);
+
+    match watchdog.run(system) {
+        Err(WatchdogError::Deadlock(deadlock)) => assert_eq!(deadlock.unfinished, vec![1]),
+        _ => panic!("no actor ever gets a message"),
+    }
+    assert_eq!(Arc::strong_count(&alive), 1);
+}
+
+#[test]
+fn test_watchdog_deadlock_teardown() {
+    check_deadlock_teardown(CrossbeamSystem::new(), ActorType::Light);
+    check_deadlock_teardown(PoolSystem::with_workers(2), ActorType::Light);
+    check_deadlock_teardown(LocalSystem::new(), ActorType::Light);
+    for kind in [ActorType::Light, ActorType::Blocking, ActorType::Heavy] {
+        check_deadlock_teardown(TokioSystem::new(1), kind);
+    }
+}
+
+/// Run a retrier giving up after three timeouts next to one retrying forever, both terminals.
+fn check_deadline<S>(mut system: S, kind: ActorType, timeout: Duration, deadline: Deadline)
+where
+    S: System<Internal = Watched<Retrier>> + Send + 'static,
+    S::Error: Send,
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let watchdog = Watchdog::new().deadline(deadline);
+    let alive = Arc::new(());
+    for (key, retries) in [(0, Some(3)), (1, None)] {
+        let retrier = Retrier {
+            timeout,
+            _alive: alive.clone(),
+            ..retrier(retries)
+        };
+        let parameters = Some((kind, 10, 10).into());
+        system.add_actor(key, watchdog.wrap(key, retrier), parameters);
+        system.add_terminal(key);
+    }
+
+    match watchdog.run(system) {
+        Err(WatchdogError::Expired(expired)) => {
+            assert_eq!(expired.values.len(), 1);
+            assert_eq!(expired.values[&0], Some(Message::Stop));
+            assert_eq!(expired.running, vec![1]);
+            assert_eq!(
+                expired.to_string(),
+                "deadline expired: actors [1] did not terminate"
+            );
+        }
+        _ => panic!("the second retrier never terminates"),
+    }
+    // The system is stopped before the run returns
+    assert_eq!(Arc::strong_count(&alive), 1);
+}
+
+#[test]
+fn test_watchdog_wall_deadline() {
+    let (kind, timeout, deadline) = (
+        ActorType::Light,
+        Duration::from_millis(1),
+        Deadline::Wall(Duration::from_millis(200)),
+    );
+    check_deadline(CrossbeamSystem::new(), kind, timeout, deadline);
+    check_deadline(PoolSystem::with_workers(2), kind, timeout, deadline);
+    check_deadline(TokioSystem::new(2), kind, timeout, deadline);
+}
+
+#[test]
+fn test_watchdog_virtual_deadline() {
+    // An hour of retrying every second, the paused clock jumping from one timeout to the next.
+    // Heavy steps run on the threads of a pool, which read the clock of the runtime as well.
+    let deadline = Deadline::Virtual(Duration::from_secs(3600));
+    for kind in [ActorType::Light, ActorType::Heavy] {
+        let mut system = TokioSystem::new(2);
+        system.set_runtime(RuntimeSettings::paused());
+        check_deadline(system, kind, Duration::from_secs(1), deadline);
+    }
+}
+
+#[test]
+fn test_watchdog_deadline_not_reached() {
+    let watchdog = Watchdog::new().deadline(Deadline::Wall(Duration::from_secs(60)));
+    let mut system = CrossbeamSystem::new();
+    system.add_actor(0, watchdog.wrap(0, retrier(Some(1))), None);
+    system.add_terminal(0);
+
+    let values = watchdog.run(system).unwrap();
+    assert_eq!(values[&0], Some(Message::Stop));
+}
+
+/// Retriers giving up after one and two timeouts, next to one retrying forever, all terminals.
+fn retriers<S: System<Internal = Watched<Retrier>>>(
+    mut system: S,
+    watchdog: &Watchdog<Retrier>,
+    alive: &Arc<()>,
+) -> S
+where
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    for (key, retries) in [(0, Some(1)), (1, Some(2)), (2, None)] {
+        let retrier = Retrier {
+            _alive: alive.clone(),
+            ..retrier(retries)
+        };
+        let parameters = Some((ActorType::Light, 10, 10).into());
+        system.add_actor(key, watchdog.wrap(key, retrier), parameters);
+        system.add_terminal(key);
+    }
+    system
+}
+
+fn check_quorum<S>(system: S)
+where
+    S: System<Internal = Watched<Retrier>> + Send + 'static,
+    S::Error: Send,
+    S::ActorParameters: From<(ActorType, usize, usize)>,
+{
+    let watchdog = Watchdog::new().quorum(2);
+    let alive = Arc::new(());
+    let values = watchdog.run(retriers(system, &watchdog, &alive)).unwrap();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[&0], Some(Message::Stop));
+    assert_eq!(values[&1], Some(Message::Stop));
+    // The retrier left running is stopped together with the system
+    assert_eq!(Arc::strong_count(&alive), 1);
+}
+
+#[test]
+fn test_watchdog_quorum() {
+    check_quorum(CrossbeamSystem::new());
+    check_quorum(PoolSystem::with_workers(2));
+    check_quorum(TokioSystem::new(3));
+}
+
+#[test]
+fn test_watchdog_until() {
+    let watchdog = Watchdog::new().until(|values| values.contains_key(&1));
+    let alive = Arc::new(());
+    let system = retriers(PoolSystem::with_workers(2), &watchdog, &alive);
+    let values = watchdog.run(system).unwrap();
+    assert_eq!(values[&1], Some(Message::Stop));
+    assert!(!values.contains_key(&2));
+    assert_eq!(Arc::strong_count(&alive), 1);
+
+    // A condition which never holds does not keep the run from finishing
+    let watchdog = Watchdog::new().until(|_| false);
+    let mut system = CrossbeamSystem::new();
+    system.add_actor(0, watchdog.wrap(0, retrier(Some(1))), None);
+    system.add_terminal(0);
+    let values = watchdog.run(system).unwrap();
+    assert_eq!(values[&0], Some(Message::Stop));
+}
+
+#[test]
+fn test_watchdog_first_error() {
+    // The failing retrier is not a terminal, so without the watchdog the run would never end
+    let watchdog = Watchdog::new().first_error();
+    let mut system = TokioSystem::new(1);
+    let failing = Retrier {
+        fail: true,
+        ..retrier(Some(1))
+    };
+    let parameters = || Some((ActorType::Light, 10, 10).into());
+    system.add_actor(0, watchdog.wrap(0, failing), parameters());
+    system.add_actor(1, watchdog.wrap(1, retrier(None)), parameters());
+    system.add_terminal(1);
+
+    match watchdog.run(system) {
+        Err(WatchdogError::Failed(key, err)) => {
+            assert_eq!(key, 0);
+            assert_eq!(err, SendError::UnknownKey((usize::MAX, Message::Stop)));
+        }
+        _ => panic!("the first retrier fails"),
+    }
+}